@@ -39,14 +39,20 @@ pub struct ScriptRequest {
     pub working_dir: Option<String>,
 }
 
-#[async_trait]
-impl Executable for ScriptRequest {
-    async fn spawn(
-        &self,
-        current_dir: &Path,
-        _approvals: Arc<dyn ExecutorApprovalService>,
-        env: &ExecutionEnv,
-    ) -> Result<SpawnedChild, ExecutorError> {
+impl ScriptRequest {
+    /// A bash script that installs an agent's CLI or logs in to it
+    pub fn tool_install(script: impl Into<String>) -> Self {
+        Self {
+            script: script.into(),
+            language: ScriptRequestLanguage::Bash,
+            context: ScriptContext::ToolInstallScript,
+            working_dir: None,
+        }
+    }
+
+    /// The shell command running the script in its working dir with `env` applied; stdio is
+    /// left to the caller
+    pub fn command(&self, current_dir: &Path, env: &ExecutionEnv) -> Command {
         // Use working_dir if specified, otherwise use current_dir
         let effective_dir = match &self.working_dir {
             Some(rel_path) => current_dir.join(rel_path),
@@ -57,15 +63,29 @@ impl Executable for ScriptRequest {
         let mut command = Command::new(shell_cmd);
         command
             .kill_on_drop(true)
-            .stdin(std::process::Stdio::null())
-            .stdout(std::process::Stdio::piped())
-            .stderr(std::process::Stdio::piped())
             .arg(shell_arg)
             .arg(&self.script)
             .current_dir(&effective_dir);
 
         // Apply environment variables
         env.apply_to_command(&mut command);
+        command
+    }
+}
+
+#[async_trait]
+impl Executable for ScriptRequest {
+    async fn spawn(
+        &self,
+        current_dir: &Path,
+        _approvals: Arc<dyn ExecutorApprovalService>,
+        env: &ExecutionEnv,
+    ) -> Result<SpawnedChild, ExecutorError> {
+        let mut command = self.command(current_dir, env);
+        command
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::piped());
 
         let child = command.group_spawn()?;
 
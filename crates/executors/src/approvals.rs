@@ -1,12 +1,15 @@
-use std::fmt;
+use std::{fmt, path::PathBuf, sync::Arc};
 
 use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use thiserror::Error;
+use tokio::sync::mpsc;
 use tokio_util::sync::CancellationToken;
 use workspace_utils::approvals::ApprovalStatus;
 
+use crate::protected::{ProtectedPathViolation, ProtectedPaths};
+
 /// Errors emitted by executor approval services.
 #[derive(Debug, Error)]
 pub enum ExecutorApprovalError {
@@ -58,7 +61,145 @@ impl ExecutorApprovalService for NoopExecutorApprovalService {
     }
 }
 
+/// Approves everything except leaving plan mode: the request to execute a plan is denied so
+/// the turn ends and the user can review the plan, then switch modes to execute it in the
+/// same session (see [`crate::executors::LiveInput::SetMode`]).
+#[derive(Debug, Default)]
+pub struct PlanReviewApprovalService;
+
+const PLAN_REVIEW_DENY_REASON: &str = "The user reviews the plan before anything is executed. \
+     Stop here and wait for their next message.";
+
+impl PlanReviewApprovalService {
+    fn is_plan_exit(tool_name: &str, tool_input: &Value) -> bool {
+        // Claude Code's ExitPlanMode tool; ACP agents report a `switch_mode` tool call
+        tool_name == "ExitPlanMode"
+            || tool_input
+                .pointer("/tool_call/kind")
+                .and_then(Value::as_str)
+                .is_some_and(|kind| kind == "switch_mode")
+    }
+}
+
+#[async_trait]
+impl ExecutorApprovalService for PlanReviewApprovalService {
+    async fn request_tool_approval(
+        &self,
+        tool_name: &str,
+        tool_input: Value,
+        _tool_call_id: &str,
+        _cancel: CancellationToken,
+    ) -> Result<ApprovalStatus, ExecutorApprovalError> {
+        if Self::is_plan_exit(tool_name, &tool_input) {
+            return Ok(ApprovalStatus::Denied {
+                reason: Some(PLAN_REVIEW_DENY_REASON.to_string()),
+            });
+        }
+        Ok(ApprovalStatus::Approved)
+    }
+}
+
+/// Denies tool calls that would change a protected path and reports them on `violations`;
+/// everything else is decided by the wrapped service.
+pub struct ProtectedPathsApprovalService {
+    paths: Arc<ProtectedPaths>,
+    current_dir: PathBuf,
+    inner: Arc<dyn ExecutorApprovalService>,
+    violations: mpsc::UnboundedSender<ProtectedPathViolation>,
+}
+
+impl ProtectedPathsApprovalService {
+    pub fn new(
+        paths: Arc<ProtectedPaths>,
+        current_dir: PathBuf,
+        inner: Arc<dyn ExecutorApprovalService>,
+        violations: mpsc::UnboundedSender<ProtectedPathViolation>,
+    ) -> Self {
+        Self {
+            paths,
+            current_dir,
+            inner,
+            violations,
+        }
+    }
+}
+
+#[async_trait]
+impl ExecutorApprovalService for ProtectedPathsApprovalService {
+    async fn request_tool_approval(
+        &self,
+        tool_name: &str,
+        tool_input: Value,
+        tool_call_id: &str,
+        cancel: CancellationToken,
+    ) -> Result<ApprovalStatus, ExecutorApprovalError> {
+        let violations =
+            self.paths
+                .violations_in_tool_call(tool_name, &tool_input, &self.current_dir);
+        if violations.is_empty() {
+            return self
+                .inner
+                .request_tool_approval(tool_name, tool_input, tool_call_id, cancel)
+                .await;
+        }
+        let paths: Vec<&str> = violations.iter().map(|v| v.path.as_str()).collect();
+        let reason = format!(
+            "Protected paths must not be changed: {}. Leave them as they are and continue \
+             without them, or tell the user what change they need.",
+            paths.join(", ")
+        );
+        for violation in violations {
+            let _ = self.violations.send(violation);
+        }
+        Ok(ApprovalStatus::Denied {
+            reason: Some(reason),
+        })
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct ToolCallMetadata {
     pub tool_call_id: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn decide(tool_name: &str, tool_input: Value) -> ApprovalStatus {
+        PlanReviewApprovalService
+            .request_tool_approval(tool_name, tool_input, "call-1", CancellationToken::new())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn plan_review_denies_leaving_plan_mode() {
+        // Claude Code
+        assert!(matches!(
+            decide("ExitPlanMode", serde_json::json!({ "plan": "1. do it" })).await,
+            ApprovalStatus::Denied { reason: Some(_) }
+        ));
+        // ACP agents
+        let switch_mode = serde_json::json!({
+            "tool_call": { "toolCallId": "t1", "kind": "switch_mode", "title": "Ready to code?" }
+        });
+        assert!(matches!(
+            decide("Ready to code?", switch_mode).await,
+            ApprovalStatus::Denied { reason: Some(_) }
+        ));
+    }
+
+    #[tokio::test]
+    async fn plan_review_approves_other_tools() {
+        assert!(matches!(
+            decide("Read", serde_json::json!({ "file_path": "src/lib.rs" })).await,
+            ApprovalStatus::Approved
+        ));
+        let edit = serde_json::json!({ "tool_call": { "toolCallId": "t2", "kind": "edit" } });
+        assert!(matches!(
+            decide("Edit", edit).await,
+            ApprovalStatus::Approved
+        ));
+    }
+}
@@ -0,0 +1,137 @@
+//! Files attached to a prompt.
+//!
+//! Images are passed to agents that accept them natively (see
+//! [`BaseAgentCapability::ImageInput`](crate::executors::BaseAgentCapability::ImageInput));
+//! other files are inlined into the prompt text so every agent can see them.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use base64::{Engine, engine::general_purpose::STANDARD};
+use thiserror::Error;
+
+/// Max size of an attached image
+pub const MAX_IMAGE_BYTES: u64 = 5 * 1024 * 1024;
+/// Max size of an attached (inlined) text file
+pub const MAX_FILE_BYTES: u64 = 256 * 1024;
+
+#[derive(Debug, Error)]
+pub enum AttachmentError {
+    #[error("Attachment '{}' not found", .0.display())]
+    NotFound(PathBuf),
+    #[error("Attachment '{}' is {size} bytes, the limit is {limit}", path.display())]
+    TooLarge {
+        path: PathBuf,
+        size: u64,
+        limit: u64,
+    },
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImageAttachment {
+    pub path: PathBuf,
+    /// e.g. `image/png`
+    pub media_type: &'static str,
+}
+
+impl ImageAttachment {
+    pub fn read_base64(&self) -> Result<String, AttachmentError> {
+        Ok(STANDARD.encode(fs::read(&self.path)?))
+    }
+}
+
+fn image_media_type(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    match ext.as_str() {
+        "png" => Some("image/png"),
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "gif" => Some("image/gif"),
+        "webp" => Some("image/webp"),
+        _ => None,
+    }
+}
+
+fn checked_size(path: &Path, limit: u64) -> Result<(), AttachmentError> {
+    let size = fs::metadata(path)
+        .map_err(|_| AttachmentError::NotFound(path.to_path_buf()))?
+        .len();
+    if size > limit {
+        return Err(AttachmentError::TooLarge {
+            path: path.to_path_buf(),
+            size,
+            limit,
+        });
+    }
+    Ok(())
+}
+
+/// Resolve attachments relative to `current_dir`. Text files are appended to the prompt;
+/// images are returned for the executor to send natively.
+pub fn prepare_attachments(
+    prompt: &str,
+    paths: &[PathBuf],
+    current_dir: &Path,
+) -> Result<(String, Vec<ImageAttachment>), AttachmentError> {
+    let mut prompt = prompt.to_string();
+    let mut images = Vec::new();
+
+    for path in paths {
+        let resolved = current_dir.join(path);
+        match image_media_type(&resolved) {
+            Some(media_type) => {
+                checked_size(&resolved, MAX_IMAGE_BYTES)?;
+                images.push(ImageAttachment {
+                    path: resolved.canonicalize()?,
+                    media_type,
+                });
+            }
+            None => {
+                checked_size(&resolved, MAX_FILE_BYTES)?;
+                let content = String::from_utf8_lossy(&fs::read(&resolved)?).into_owned();
+                prompt.push_str(&format!(
+                    "\n\nAttached file `{}`:\n````\n{}\n````",
+                    path.display(),
+                    content.trim_end()
+                ));
+            }
+        }
+    }
+
+    Ok((prompt, images))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_images_and_inlines_files() {
+        let dir = std::env::temp_dir().join(format!("attach-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("notes.txt"), "remember this\n").unwrap();
+        fs::write(dir.join("shot.PNG"), [0x89, b'P', b'N', b'G']).unwrap();
+
+        let (prompt, images) = prepare_attachments(
+            "Fix it",
+            &[PathBuf::from("notes.txt"), PathBuf::from("shot.PNG")],
+            &dir,
+        )
+        .unwrap();
+        assert!(prompt.starts_with("Fix it\n\nAttached file `notes.txt`:"));
+        assert!(prompt.contains("remember this"));
+        assert_eq!(images.len(), 1);
+        assert_eq!(images[0].media_type, "image/png");
+        assert_eq!(images[0].read_base64().unwrap(), "iVBORw==");
+
+        assert!(matches!(
+            prepare_attachments("x", &[PathBuf::from("missing.txt")], &dir),
+            Err(AttachmentError::NotFound(_))
+        ));
+
+        fs::remove_dir_all(dir).ok();
+    }
+}
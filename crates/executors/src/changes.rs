@@ -0,0 +1,205 @@
+//! End-of-run changed-files report: the real git diff of a run reconciled against the file edits
+//! its normalized log reported, so reviewers see changes made through shell commands and
+//! reported edits that never landed.
+
+use std::{
+    collections::BTreeSet,
+    path::{Component, Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use workspace_utils::diff::{Diff, DiffChangeKind, compute_line_change_counts};
+
+use crate::logs::{ActionType, NormalizedEntry, NormalizedEntryType, ToolStatus};
+
+/// A file changed by the run, according to git
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+pub struct ChangedFile {
+    pub path: String,
+    /// Previous path of a renamed or copied file
+    pub old_path: Option<String>,
+    pub change: DiffChangeKind,
+    pub additions: usize,
+    pub deletions: usize,
+    /// Contents were too large or binary; line counts may be missing
+    pub content_omitted: bool,
+    /// Whether the agent reported a file edit for this path; unreported changes usually come
+    /// from shell commands
+    pub reported: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ChangesReport {
+    pub files: Vec<ChangedFile>,
+    /// Paths the agent reported editing that show no change in the worktree
+    pub reported_not_changed: Vec<String>,
+    pub additions: usize,
+    pub deletions: usize,
+    /// Worktree state after the run: modified tracked files and untracked files
+    pub uncommitted_tracked: usize,
+    pub untracked: usize,
+}
+
+impl ChangesReport {
+    /// Reconcile `diffs` (paths relative to `repo_root`) with the file edits in `entries` (paths
+    /// relative to `current_dir`, or absolute)
+    pub fn new<'a>(
+        diffs: Vec<Diff>,
+        entries: impl IntoIterator<Item = &'a NormalizedEntry>,
+        repo_root: &Path,
+        current_dir: &Path,
+        (uncommitted_tracked, untracked): (usize, usize),
+    ) -> Self {
+        let reported = reported_edits(entries, repo_root, current_dir);
+        let mut changed = BTreeSet::new();
+        let mut files: Vec<ChangedFile> = diffs
+            .into_iter()
+            .map(|diff| {
+                let path = diff
+                    .new_path
+                    .clone()
+                    .or_else(|| diff.old_path.clone())
+                    .unwrap_or_default();
+                let old_path = diff.old_path.clone().filter(|old| *old != path);
+                let (additions, deletions) = match (diff.additions, diff.deletions) {
+                    (Some(a), Some(d)) => (a, d),
+                    _ if diff.content_omitted => (0, 0),
+                    _ => compute_line_change_counts(
+                        diff.old_content.as_deref().unwrap_or_default(),
+                        diff.new_content.as_deref().unwrap_or_default(),
+                    ),
+                };
+                let is_reported = reported.contains(&path)
+                    || old_path.as_ref().is_some_and(|old| reported.contains(old));
+                changed.insert(path.clone());
+                changed.extend(old_path.clone());
+                ChangedFile {
+                    path,
+                    old_path,
+                    change: diff.change,
+                    additions,
+                    deletions,
+                    content_omitted: diff.content_omitted,
+                    reported: is_reported,
+                }
+            })
+            .collect();
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+
+        Self {
+            additions: files.iter().map(|f| f.additions).sum(),
+            deletions: files.iter().map(|f| f.deletions).sum(),
+            reported_not_changed: reported.difference(&changed).cloned().collect(),
+            files,
+            uncommitted_tracked,
+            untracked,
+        }
+    }
+
+    /// Changed files the agent never reported editing
+    pub fn unreported(&self) -> impl Iterator<Item = &ChangedFile> {
+        self.files.iter().filter(|f| !f.reported)
+    }
+}
+
+/// Repo-relative paths of file edits the agent reported and did not fail
+fn reported_edits<'a>(
+    entries: impl IntoIterator<Item = &'a NormalizedEntry>,
+    repo_root: &Path,
+    current_dir: &Path,
+) -> BTreeSet<String> {
+    entries
+        .into_iter()
+        .filter_map(|entry| match &entry.entry_type {
+            NormalizedEntryType::ToolUse {
+                action_type: ActionType::FileEdit { path, .. },
+                status,
+                ..
+            } if matches!(status, ToolStatus::Created | ToolStatus::Success) => {
+                repo_relative(path, repo_root, current_dir)
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+pub(crate) fn repo_relative(path: &str, repo_root: &Path, current_dir: &Path) -> Option<String> {
+    let mut normalized = PathBuf::new();
+    for component in current_dir.join(path).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    let relative = normalized.strip_prefix(repo_root).ok()?;
+    let relative = relative.to_string_lossy().replace('\\', "/");
+    (!relative.is_empty()).then_some(relative)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(path: &str, status: ToolStatus) -> NormalizedEntry {
+        NormalizedEntry {
+            timestamp: None,
+            entry_type: NormalizedEntryType::ToolUse {
+                tool_name: "Edit".to_string(),
+                action_type: ActionType::FileEdit {
+                    path: path.to_string(),
+                    changes: vec![],
+                },
+                status,
+            },
+            content: String::new(),
+            metadata: None,
+        }
+    }
+
+    fn diff(path: &str, old: &str, new: &str) -> Diff {
+        Diff {
+            change: DiffChangeKind::Modified,
+            old_path: Some(path.to_string()),
+            new_path: Some(path.to_string()),
+            old_content: Some(old.to_string()),
+            new_content: Some(new.to_string()),
+            content_omitted: false,
+            additions: None,
+            deletions: None,
+            repo_id: None,
+        }
+    }
+
+    #[test]
+    fn flags_unreported_changes_and_edits_that_did_not_land() {
+        let root = Path::new("/repo");
+        let entries = vec![
+            edit("src/lib.rs", ToolStatus::Success),
+            edit("/repo/app/./main.rs", ToolStatus::Success),
+            edit("README.md", ToolStatus::Success),
+            edit("denied.rs", ToolStatus::Failed),
+        ];
+        let diffs = vec![
+            diff("app/src/lib.rs", "a\n", "a\nb\n"),
+            diff("app/main.rs", "a\nb\n", "c\n"),
+            diff("Cargo.lock", "x\n", "y\n"),
+        ];
+        let report = ChangesReport::new(diffs, &entries, root, &root.join("app"), (3, 0));
+
+        let unreported: Vec<_> = report.unreported().map(|f| f.path.as_str()).collect();
+        assert_eq!(unreported, vec!["Cargo.lock"]);
+        assert_eq!(report.reported_not_changed, vec!["app/README.md"]);
+        assert_eq!((report.additions, report.deletions), (3, 3));
+        let lib = report
+            .files
+            .iter()
+            .find(|f| f.path == "app/src/lib.rs")
+            .unwrap();
+        assert_eq!((lib.additions, lib.deletions), (1, 0));
+    }
+}
@@ -6,7 +6,7 @@ use thiserror::Error;
 use ts_rs::TS;
 use workspace_utils::shell::resolve_executable_path;
 
-use crate::executors::ExecutorError;
+use crate::{executors::ExecutorError, tool_cache};
 
 #[derive(Debug, Error)]
 pub enum CommandBuildError {
@@ -31,8 +31,13 @@ impl CommandParts {
         Self { program, args }
     }
 
+    /// The executable and its arguments; `npx` command lines of managed agent CLIs run from
+    /// the tool cache when the package is installed there (see [`tool_cache::cached_command`])
     pub async fn into_resolved(self) -> Result<(PathBuf, Vec<String>), ExecutorError> {
         let CommandParts { program, args } = self;
+        if let Some(cached) = tool_cache::cached_command(&program, &args).await {
+            return Ok(cached);
+        }
         let executable = resolve_executable_path(&program)
             .await
             .ok_or(ExecutorError::ExecutableNotFound { program })?;
@@ -40,6 +45,16 @@ impl CommandParts {
     }
 }
 
+impl std::fmt::Display for CommandParts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let words = std::iter::once(&self.program).chain(&self.args);
+        match shlex::try_join(words.clone().map(String::as_str)) {
+            Ok(line) => f.write_str(&line),
+            Err(_) => f.write_str(&words.cloned().collect::<Vec<_>>().join(" ")),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, TS, JsonSchema, Default)]
 pub struct CmdOverrides {
     #[schemars(
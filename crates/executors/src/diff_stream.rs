@@ -0,0 +1,217 @@
+//! Live worktree diffs: while an agent runs, the worktree is polled and its changes against a
+//! snapshot of the worktree taken when the run started are pushed to the [`MsgStore`] as diff
+//! patches keyed by path under `/diffs/`, so edits that were already uncommitted before the run
+//! are not reported as the agent's. The
+//! diffs come from git, so they show up whichever agent made the change and whether or not its
+//! normalizer parsed the edit. Ignored files are skipped and large files are sent with
+//! `content_omitted` (see [`GitService::get_diffs`]).
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+
+use git::{Commit, DiffTarget, GitService, GitServiceError, StatusEntry};
+use tokio::{task::JoinHandle, time::MissedTickBehavior};
+use tokio_util::sync::CancellationToken;
+use workspace_utils::{diff::Diff, msg_store::MsgStore};
+
+use crate::logs::utils::patch::{ConversationPatch, DiffSource, escape_json_pointer_segment};
+
+/// Cheap fingerprint of the worktree; diffs are only recomputed when it changes
+#[derive(PartialEq)]
+struct WorktreeStamp {
+    head: Option<String>,
+    entries: Vec<(StatusEntry, Option<(u64, Option<SystemTime>)>)>,
+}
+
+fn stamp(worktree_path: &Path) -> Option<WorktreeStamp> {
+    let git = GitService::new();
+    let status = git.get_worktree_status(worktree_path).ok()?;
+    let head = git.get_head_info(worktree_path).ok().map(|h| h.oid);
+    let entries = status
+        .entries
+        .into_iter()
+        .map(|entry| {
+            let path = worktree_path.join(String::from_utf8_lossy(&entry.path).as_ref());
+            let meta = std::fs::metadata(path)
+                .ok()
+                .map(|m| (m.len(), m.modified().ok()));
+            (entry, meta)
+        })
+        .collect();
+    Some(WorktreeStamp { head, entries })
+}
+
+/// Diffs already sent, by path, as serialized JSON to detect changes
+#[derive(Default)]
+struct SentDiffs(HashMap<String, String>);
+
+impl SentDiffs {
+    fn update(&mut self, msg_store: &MsgStore, diffs: Vec<Diff>) {
+        let mut current = HashMap::with_capacity(diffs.len());
+        for diff in diffs {
+            let path = GitService::diff_path(&diff);
+            let Ok(json) = serde_json::to_string(&diff) else {
+                continue;
+            };
+            let key = escape_json_pointer_segment(&path);
+            match self.0.get(&path) {
+                None => msg_store.push_patch(ConversationPatch::add_diff(
+                    DiffSource::Worktree,
+                    key,
+                    diff,
+                )),
+                Some(sent) if *sent != json => msg_store.push_patch(
+                    ConversationPatch::replace_diff(DiffSource::Worktree, key, diff),
+                ),
+                Some(_) => {}
+            }
+            current.insert(path, json);
+        }
+        for path in self.0.keys().filter(|path| !current.contains_key(*path)) {
+            msg_store.push_patch(ConversationPatch::remove_diff(
+                DiffSource::Worktree,
+                escape_json_pointer_segment(path),
+            ));
+        }
+        self.0 = current;
+    }
+}
+
+/// Poll `worktree_path` (a repository top level) every `interval` and stream its diffs against
+/// `base_commit` (the run-base snapshot) until `cancel` fires; a final pass then picks up the
+/// last changes.
+pub fn spawn_diff_streamer(
+    msg_store: Arc<MsgStore>,
+    worktree_path: PathBuf,
+    base_commit: Commit,
+    interval: Duration,
+    cancel: CancellationToken,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut last_stamp: Option<WorktreeStamp> = None;
+        let mut sent = SentDiffs::default();
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            let stop = tokio::select! {
+                _ = cancel.cancelled() => true,
+                _ = ticker.tick() => false,
+            };
+
+            let worktree_path = worktree_path.clone();
+            let base_commit = base_commit.clone();
+            let previous = last_stamp.take();
+            let result =
+                tokio::task::spawn_blocking(move || poll(&worktree_path, &base_commit, previous))
+                    .await;
+            match result {
+                Ok((stamp, diffs)) => {
+                    last_stamp = stamp;
+                    match diffs {
+                        Some(Ok(diffs)) => sent.update(&msg_store, diffs),
+                        Some(Err(e)) => tracing::debug!("Failed to compute worktree diffs: {e}"),
+                        None => {}
+                    }
+                }
+                Err(e) => {
+                    tracing::warn!("Diff streamer task failed: {e}");
+                    break;
+                }
+            }
+            if stop {
+                break;
+            }
+        }
+    })
+}
+
+/// The new stamp, and the diffs if the worktree changed since `previous`
+type PollResult = (
+    Option<WorktreeStamp>,
+    Option<Result<Vec<Diff>, GitServiceError>>,
+);
+
+fn poll(worktree_path: &Path, base_commit: &Commit, previous: Option<WorktreeStamp>) -> PollResult {
+    let stamp = stamp(worktree_path);
+    if stamp.is_some() && stamp == previous {
+        return (stamp, None);
+    }
+    let diffs = GitService::new().get_diffs(
+        DiffTarget::Worktree {
+            worktree_path,
+            base_commit,
+        },
+        None,
+    );
+    (stamp, Some(diffs))
+}
+
+#[cfg(test)]
+mod tests {
+    use workspace_utils::{diff::DiffChangeKind, log_msg::LogMsg};
+
+    use super::*;
+
+    fn diff(path: &str, new_content: &str) -> Diff {
+        Diff {
+            change: DiffChangeKind::Modified,
+            old_path: Some(path.to_string()),
+            new_path: Some(path.to_string()),
+            old_content: Some(String::new()),
+            new_content: Some(new_content.to_string()),
+            content_omitted: false,
+            additions: None,
+            deletions: None,
+            repo_id: None,
+        }
+    }
+
+    fn ops(msg_store: &MsgStore) -> Vec<(String, String)> {
+        msg_store
+            .get_history()
+            .into_iter()
+            .filter_map(|msg| match msg {
+                LogMsg::JsonPatch(patch) => serde_json::to_value(&patch).ok(),
+                _ => None,
+            })
+            .flat_map(|value| value.as_array().cloned().unwrap_or_default())
+            .map(|op| {
+                (
+                    op["op"].as_str().unwrap_or_default().to_string(),
+                    op["path"].as_str().unwrap_or_default().to_string(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn emits_add_replace_and_remove_keyed_by_path() {
+        let msg_store = MsgStore::new();
+        let mut sent = SentDiffs::default();
+        sent.update(&msg_store, vec![diff("src/a.rs", "1"), diff("b.rs", "1")]);
+        sent.update(&msg_store, vec![diff("src/a.rs", "2"), diff("b.rs", "1")]);
+        sent.update(&msg_store, vec![diff("src/a.rs", "2")]);
+
+        let mut first = ops(&msg_store)[..2].to_vec();
+        first.sort();
+        assert_eq!(
+            first,
+            vec![
+                ("add".to_string(), "/diffs/b.rs".to_string()),
+                ("add".to_string(), "/diffs/src~1a.rs".to_string()),
+            ]
+        );
+        assert_eq!(
+            ops(&msg_store)[2..],
+            [
+                ("replace".to_string(), "/diffs/src~1a.rs".to_string()),
+                ("remove".to_string(), "/diffs/b.rs".to_string()),
+            ]
+        );
+    }
+}
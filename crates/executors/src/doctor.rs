@@ -0,0 +1,532 @@
+//! Deep availability checks behind `code-marshal doctor`: resolve and start each agent's CLI,
+//! ask whether it is authenticated and parse its MCP config, with a remediation for every
+//! check that fails. [`AvailabilityInfo`](crate::executors::AvailabilityInfo) only looks for
+//! files.
+
+use std::{path::Path, process::Stdio, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use strum_macros::Display;
+use tokio::process::Command;
+use ts_rs::TS;
+
+use crate::{
+    command::CommandParts,
+    executors::{
+        BaseCodingAgent, CodingAgent, StandardCodingAgentExecutor, aider::Aider, amp::Amp, claude,
+        codex::Codex, copilot::Copilot, cursor::CursorAgent, droid::Droid, gemini::Gemini,
+        goose::Goose, opencode::Opencode, qwen::QwenCode,
+    },
+    mcp_config::read_agent_config,
+};
+
+/// Long enough for `npx` to download a pinned CLI on first use
+const VERSION_PROBE_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Whether an agent's CLI is authenticated, see
+/// [`StandardCodingAgentExecutor::auth_status`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthStatus {
+    /// Logged in, with the method when the agent reports it
+    LoggedIn {
+        method: Option<String>,
+    },
+    /// The configured provider needs no login (e.g. a local model)
+    NotRequired,
+    LoggedOut,
+    /// The agent cannot tell and no stored login was found
+    Unknown,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, TS, Display)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+#[ts(export)]
+pub enum CheckKind {
+    /// The agent's program resolves on `PATH`
+    Command,
+    /// The CLI starts and prints its version
+    Version,
+    /// A login or a credential environment variable
+    Auth,
+    /// The agent's MCP config file parses
+    McpConfig,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum CheckStatus {
+    Ok,
+    Warning,
+    Failed,
+    /// Not applicable to the agent, or blocked by an earlier failure
+    Skipped,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, TS)]
+#[ts(export)]
+pub struct DoctorCheck {
+    pub kind: CheckKind,
+    pub status: CheckStatus,
+    pub detail: String,
+    /// What to do about a failure or warning
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub remediation: Option<String>,
+}
+
+impl DoctorCheck {
+    fn new(kind: CheckKind, status: CheckStatus, detail: impl Into<String>) -> Self {
+        Self {
+            kind,
+            status,
+            detail: detail.into(),
+            remediation: None,
+        }
+    }
+
+    fn fix(mut self, remediation: impl Into<String>) -> Self {
+        self.remediation = Some(remediation.into());
+        self
+    }
+}
+
+/// Every check for one agent, in the order they ran
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, TS)]
+#[ts(export)]
+pub struct AgentDiagnosis {
+    pub agent: BaseCodingAgent,
+    pub checks: Vec<DoctorCheck>,
+}
+
+impl AgentDiagnosis {
+    pub fn is_healthy(&self) -> bool {
+        self.checks
+            .iter()
+            .all(|check| check.status != CheckStatus::Failed)
+    }
+}
+
+/// Environment variables that authenticate an agent without a login, and how to log in
+struct Credentials {
+    vars: &'static [&'static str],
+    /// `None` when the agent has no login and one of `vars` is required
+    login: Option<&'static str>,
+}
+
+fn credentials(agent: BaseCodingAgent) -> Option<Credentials> {
+    let (vars, login): (&'static [&'static str], _) = match agent {
+        BaseCodingAgent::ClaudeCode => (
+            &["ANTHROPIC_API_KEY", "CLAUDE_CODE_OAUTH_TOKEN"],
+            Some("run `/login` in Claude Code"),
+        ),
+        BaseCodingAgent::Gemini => (
+            &["GEMINI_API_KEY", "GOOGLE_API_KEY"],
+            Some("sign in with Google in the Gemini CLI"),
+        ),
+        BaseCodingAgent::QwenCode => (
+            &["OPENAI_API_KEY", "DASHSCOPE_API_KEY"],
+            Some("sign in with Qwen OAuth in Qwen Code"),
+        ),
+        BaseCodingAgent::Codex => (
+            &["OPENAI_API_KEY", "CODEX_API_KEY"],
+            Some("run `codex login`"),
+        ),
+        BaseCodingAgent::CursorAgent => (&["CURSOR_API_KEY"], Some("run `cursor-agent login`")),
+        BaseCodingAgent::Amp => (&["AMP_API_KEY"], Some("run `amp login`")),
+        BaseCodingAgent::Copilot => (
+            &["COPILOT_GITHUB_TOKEN", "GH_TOKEN", "GITHUB_TOKEN"],
+            Some("run `/login` in the Copilot CLI"),
+        ),
+        BaseCodingAgent::Droid => (&["FACTORY_API_KEY"], Some("run `/login` in droid")),
+        BaseCodingAgent::Aider => (
+            &[
+                "OPENAI_API_KEY",
+                "ANTHROPIC_API_KEY",
+                "GEMINI_API_KEY",
+                "DEEPSEEK_API_KEY",
+                "OPENROUTER_API_KEY",
+            ],
+            None,
+        ),
+        _ => return None,
+    };
+    Some(Credentials { vars, login })
+}
+
+/// The agent's base command (or the profile's override) and whether it takes `--version`;
+/// `None` for plugins, which report their own availability
+fn probe_base(agent: &CodingAgent) -> Option<(String, bool)> {
+    let (default, versioned) = match agent {
+        CodingAgent::ClaudeCode(agent) => (
+            claude::base_command(agent.claude_code_router.unwrap_or(false)),
+            true,
+        ),
+        CodingAgent::Amp(_) => (Amp::base_command(), true),
+        CodingAgent::Gemini(_) => (Gemini::base_command(), true),
+        CodingAgent::Codex(_) => (Codex::base_command(), true),
+        CodingAgent::Opencode(_) => (Opencode::base_command(), true),
+        CodingAgent::CursorAgent(_) => (CursorAgent::base_command(), true),
+        CodingAgent::QwenCode(_) => (QwenCode::base_command(), true),
+        CodingAgent::Copilot(_) => (Copilot::base_command(), true),
+        CodingAgent::Droid(_) => (Droid::base_command(), true),
+        CodingAgent::Aider(_) => (Aider::base_command(), true),
+        CodingAgent::Goose(_) => (Goose::base_command(), true),
+        // Arbitrary CLIs: `--version` may be unknown to them or start a session
+        CodingAgent::Acp(agent) => (agent.command.as_str(), false),
+        CodingAgent::Custom(agent) => (agent.command.as_str(), false),
+        CodingAgent::Plugin(_) => return None,
+        #[cfg(feature = "qa-mode")]
+        CodingAgent::QaMock(_) => return None,
+    };
+    let base = agent
+        .cmd_overrides()
+        .and_then(|cmd| cmd.base_command_override.clone())
+        .unwrap_or_else(|| default.to_string());
+    Some((base, versioned))
+}
+
+/// The program of `base` and the arguments that select the CLI itself: the package of an
+/// `npx`/`bunx` runner, but no subcommand such as `goose run`
+fn probe_parts(base: &str, versioned: bool) -> Option<CommandParts> {
+    let mut words = shlex::split(base)?.into_iter();
+    let program = words.next()?;
+    let runner = Path::new(&program)
+        .file_stem()
+        .is_some_and(|stem| stem == "npx" || stem == "bunx");
+    let mut args = Vec::new();
+    if runner {
+        for word in words {
+            let package = !word.starts_with('-');
+            args.push(word);
+            if package {
+                break;
+            }
+        }
+    }
+    if versioned {
+        args.push("--version".to_string());
+    }
+    Some(CommandParts::new(program, args))
+}
+
+/// Run every check for `agent`. `setup_command` is the command that installs and logs in the
+/// agent when it has a setup helper, offered as the remediation where it helps.
+pub async fn diagnose(
+    agent: &CodingAgent,
+    current_dir: &Path,
+    setup_command: Option<&str>,
+) -> AgentDiagnosis {
+    let mut checks = cli_checks(agent, current_dir, setup_command).await;
+    checks.push(auth_check(agent, current_dir, setup_command).await);
+    checks.push(mcp_config_check(agent).await);
+    AgentDiagnosis {
+        agent: BaseCodingAgent::from(agent),
+        checks,
+    }
+}
+
+async fn cli_checks(
+    agent: &CodingAgent,
+    current_dir: &Path,
+    setup_command: Option<&str>,
+) -> Vec<DoctorCheck> {
+    let Some((base, versioned)) = probe_base(agent) else {
+        let check = if agent.get_availability_info().is_available() {
+            DoctorCheck::new(CheckKind::Command, CheckStatus::Ok, "reported available")
+        } else {
+            DoctorCheck::new(CheckKind::Command, CheckStatus::Failed, "not available")
+                .fix("check the plugin's executable and its `availability` response")
+        };
+        return vec![check];
+    };
+    let Some(parts) = probe_parts(&base, versioned) else {
+        return vec![
+            DoctorCheck::new(
+                CheckKind::Command,
+                CheckStatus::Failed,
+                format!("cannot parse the base command `{base}`"),
+            )
+            .fix("fix `base_command_override` in the profile"),
+        ];
+    };
+
+    let display = format!("{parts}");
+    let (program, args) = match parts.into_resolved().await {
+        Ok(resolved) => resolved,
+        Err(err) => {
+            let program = base.split_whitespace().next().unwrap_or_default();
+            let fix = match setup_command {
+                _ if program == "npx" => "install Node.js so `npx` is on PATH".to_string(),
+                Some(setup) => format!("run `{setup}`"),
+                None => format!(
+                    "install `{program}`, or point `base_command_override` in the profile at it"
+                ),
+            };
+            return vec![
+                DoctorCheck::new(CheckKind::Command, CheckStatus::Failed, err.to_string()).fix(fix),
+                DoctorCheck::new(
+                    CheckKind::Version,
+                    CheckStatus::Skipped,
+                    "command not found",
+                ),
+            ];
+        }
+    };
+    let mut checks = vec![DoctorCheck::new(
+        CheckKind::Command,
+        CheckStatus::Ok,
+        program.display().to_string(),
+    )];
+    if !versioned {
+        checks.push(DoctorCheck::new(
+            CheckKind::Version,
+            CheckStatus::Skipped,
+            "no version flag for this agent",
+        ));
+        return checks;
+    }
+
+    let mut command = Command::new(&program);
+    command
+        .args(&args)
+        .current_dir(current_dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .env("NPM_CONFIG_LOGLEVEL", "error");
+    if let Some(env) = agent.cmd_overrides().and_then(|cmd| cmd.env.as_ref()) {
+        command.envs(env);
+    }
+    let check = match tokio::time::timeout(VERSION_PROBE_TIMEOUT, command.output()).await {
+        Ok(Ok(output)) if output.status.success() => {
+            let stdout = String::from_utf8_lossy(&output.stdout);
+            let version = stdout.lines().find(|line| !line.trim().is_empty());
+            DoctorCheck::new(
+                CheckKind::Version,
+                CheckStatus::Ok,
+                version.unwrap_or("(no output)").trim(),
+            )
+        }
+        Ok(Ok(output)) => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let reason = stderr.lines().rfind(|line| !line.trim().is_empty());
+            DoctorCheck::new(
+                CheckKind::Version,
+                CheckStatus::Failed,
+                format!(
+                    "`{display}` exited with {}: {}",
+                    output.status,
+                    reason.unwrap_or("no output").trim()
+                ),
+            )
+            .fix(format!("run `{display}` to see the full error"))
+        }
+        Ok(Err(err)) => DoctorCheck::new(CheckKind::Version, CheckStatus::Failed, err.to_string())
+            .fix(format!("check that `{}` is executable", program.display())),
+        Err(_) => DoctorCheck::new(
+            CheckKind::Version,
+            CheckStatus::Warning,
+            format!(
+                "`{display}` did not finish within {}s",
+                VERSION_PROBE_TIMEOUT.as_secs()
+            ),
+        )
+        .fix(format!(
+            "run `{display}` to see whether it hangs or waits for input"
+        )),
+    };
+    checks.push(check);
+    checks
+}
+
+/// The first credential variable set in the environment or the profile's `env`
+fn credential_var(agent: &CodingAgent, vars: &[&'static str]) -> Option<&'static str> {
+    let profile_env = agent.cmd_overrides().and_then(|cmd| cmd.env.as_ref());
+    vars.iter().copied().find(|var| {
+        profile_env
+            .and_then(|env| env.get(*var).cloned())
+            .or_else(|| std::env::var(var).ok())
+            .is_some_and(|value| !value.trim().is_empty())
+    })
+}
+
+async fn auth_check(
+    agent: &CodingAgent,
+    current_dir: &Path,
+    setup_command: Option<&str>,
+) -> DoctorCheck {
+    let credentials = credentials(BaseCodingAgent::from(agent));
+    let env_var = credentials
+        .as_ref()
+        .and_then(|credentials| credential_var(agent, credentials.vars));
+    let status = agent.auth_status(current_dir).await;
+
+    match (&status, env_var) {
+        (Ok(AuthStatus::LoggedIn { method }), _) => {
+            let detail = match method {
+                Some(method) => format!("logged in ({method})"),
+                None => "logged in".to_string(),
+            };
+            return DoctorCheck::new(CheckKind::Auth, CheckStatus::Ok, detail);
+        }
+        (Ok(AuthStatus::NotRequired), _) => {
+            return DoctorCheck::new(
+                CheckKind::Auth,
+                CheckStatus::Ok,
+                "no login needed for the configured provider",
+            );
+        }
+        (_, Some(var)) => {
+            return DoctorCheck::new(CheckKind::Auth, CheckStatus::Ok, format!("{var} is set"));
+        }
+        _ => {}
+    }
+
+    let Some(credentials) = credentials else {
+        return match status {
+            Ok(AuthStatus::LoggedOut) => {
+                DoctorCheck::new(CheckKind::Auth, CheckStatus::Failed, "not logged in")
+            }
+            Err(err) => DoctorCheck::new(
+                CheckKind::Auth,
+                CheckStatus::Warning,
+                format!("could not check: {err}"),
+            ),
+            _ => DoctorCheck::new(
+                CheckKind::Auth,
+                CheckStatus::Skipped,
+                "authentication is configured in the agent itself",
+            ),
+        };
+    };
+    let vars = credentials.vars.join(", ");
+    let Some(login) = credentials.login else {
+        return DoctorCheck::new(
+            CheckKind::Auth,
+            CheckStatus::Failed,
+            format!("none of {vars} is set"),
+        )
+        .fix(format!(
+            "set the API key for the model's provider ({vars}), in the shell or the profile's `env`"
+        ));
+    };
+    let login = match setup_command {
+        Some(setup) => format!("run `{setup}`"),
+        None => login.to_string(),
+    };
+    let fix = format!("{login}, or set one of {vars}");
+    match status {
+        Ok(AuthStatus::LoggedOut) => DoctorCheck::new(
+            CheckKind::Auth,
+            CheckStatus::Failed,
+            format!("not logged in and none of {vars} is set"),
+        )
+        .fix(fix),
+        Err(err) => DoctorCheck::new(
+            CheckKind::Auth,
+            CheckStatus::Warning,
+            format!("could not check the login ({err}) and none of {vars} is set"),
+        )
+        .fix(fix),
+        _ => DoctorCheck::new(
+            CheckKind::Auth,
+            CheckStatus::Warning,
+            format!("no stored login found and none of {vars} is set"),
+        )
+        .fix(fix),
+    }
+}
+
+async fn mcp_config_check(agent: &CodingAgent) -> DoctorCheck {
+    let Some(path) = agent.default_mcp_config_path() else {
+        return DoctorCheck::new(
+            CheckKind::McpConfig,
+            CheckStatus::Skipped,
+            "the agent has no MCP config file",
+        );
+    };
+    if !path.exists() {
+        return DoctorCheck::new(
+            CheckKind::McpConfig,
+            CheckStatus::Ok,
+            format!("{} does not exist yet", path.display()),
+        );
+    }
+    let mcp_config = agent.get_mcp_config();
+    let config = match read_agent_config(&path, &mcp_config).await {
+        Ok(config) => config,
+        Err(err) => {
+            return DoctorCheck::new(
+                CheckKind::McpConfig,
+                CheckStatus::Failed,
+                format!("{} does not parse: {err}", path.display()),
+            )
+            .fix(format!("fix the syntax of {}", path.display()));
+        }
+    };
+    let servers_path = mcp_config.servers_path.join(".");
+    match mcp_config
+        .servers_path
+        .iter()
+        .try_fold(&config, |value, key| value.get(key))
+    {
+        None => DoctorCheck::new(
+            CheckKind::McpConfig,
+            CheckStatus::Ok,
+            format!("{}: no MCP servers", path.display()),
+        ),
+        Some(Value::Object(servers)) => DoctorCheck::new(
+            CheckKind::McpConfig,
+            CheckStatus::Ok,
+            format!("{}: {} MCP server(s)", path.display(), servers.len()),
+        ),
+        Some(_) => DoctorCheck::new(
+            CheckKind::McpConfig,
+            CheckStatus::Failed,
+            format!("`{servers_path}` in {} is not a table", path.display()),
+        )
+        .fix(format!(
+            "make `{servers_path}` a table of servers keyed by name in {}",
+            path.display()
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn probe_keeps_the_runner_package_and_drops_subcommands() {
+        let parts = probe_parts("npx -y @openai/codex@0.98.0 app-server", true).unwrap();
+        assert_eq!(parts.to_string(), "npx -y @openai/codex@0.98.0 --version");
+
+        let parts = probe_parts("goose run", true).unwrap();
+        assert_eq!(parts.to_string(), "goose --version");
+
+        let parts = probe_parts("/opt/my-agent --acp", false).unwrap();
+        assert_eq!(parts.to_string(), "/opt/my-agent");
+    }
+
+    #[test]
+    fn credential_vars_come_from_the_profile_env_first() {
+        let aider: CodingAgent = serde_json::from_value(serde_json::json!({
+            "AIDER": { "env": { "OPENAI_API_KEY": "sk-test" } }
+        }))
+        .unwrap();
+        assert_eq!(
+            credential_var(&aider, credentials(BaseCodingAgent::Aider).unwrap().vars),
+            Some("OPENAI_API_KEY")
+        );
+
+        // A blank value in the profile hides the shell's
+        let aider: CodingAgent = serde_json::from_value(serde_json::json!({
+            "AIDER": { "env": { "OPENAI_API_KEY": " " } }
+        }))
+        .unwrap();
+        assert_eq!(credential_var(&aider, &["OPENAI_API_KEY"]), None);
+    }
+}
@@ -3,7 +3,7 @@ use std::{collections::HashMap, path::PathBuf};
 use git::GitService;
 use tokio::process::Command;
 
-use crate::command::CmdOverrides;
+use crate::{attachments::ImageAttachment, command::CmdOverrides};
 
 /// Repository context for executor operations
 #[derive(Debug, Clone, Default)]
@@ -81,6 +81,11 @@ pub struct ExecutionEnv {
     pub repo_context: RepoContext,
     pub commit_reminder: bool,
     pub commit_reminder_prompt: String,
+    /// Images attached to the prompt, for executors that accept image input
+    pub images: Vec<ImageAttachment>,
+    /// Keep the agent process alive after the first turn so further messages can be sent
+    /// through [`SpawnedChild::live_input`](crate::executors::SpawnedChild::live_input)
+    pub interactive: bool,
 }
 
 impl ExecutionEnv {
@@ -94,9 +99,23 @@ impl ExecutionEnv {
             repo_context,
             commit_reminder,
             commit_reminder_prompt,
+            images: Vec::new(),
+            interactive: false,
         }
     }
 
+    /// Return a new env carrying the given prompt images
+    pub fn with_images(mut self, images: Vec<ImageAttachment>) -> Self {
+        self.images = images;
+        self
+    }
+
+    /// Return a new env requesting a live (interactive) session
+    pub fn with_interactive(mut self, interactive: bool) -> Self {
+        self.interactive = interactive;
+        self
+    }
+
     /// Insert an environment variable
     pub fn insert(&mut self, key: impl Into<String>, value: impl Into<String>) {
         self.vars.insert(key.into(), value.into());
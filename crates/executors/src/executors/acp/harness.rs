@@ -23,7 +23,11 @@ use crate::{
     approvals::ExecutorApprovalService,
     command::{CmdOverrides, CommandParts},
     env::ExecutionEnv,
-    executors::{ExecutorError, ExecutorExitResult, SpawnedChild, acp::AcpEvent},
+    executors::{
+        ExecutorError, ExecutorExitResult, LiveInput, LiveInputReceiver, SessionMode, SpawnedChild,
+        acp::AcpEvent, live_input_channel, recv_live_input,
+    },
+    logs::{ModeMarker, SteerMarker},
 };
 
 /// Reusable harness for ACP-based conns (Gemini, Qwen, etc.)
@@ -98,6 +102,7 @@ impl AcpAgentHarness {
 
         let (exit_tx, exit_rx) = tokio::sync::oneshot::channel::<ExecutorExitResult>();
         let cancel = CancellationToken::new();
+        let (live_input, live_rx) = live_input_channel();
 
         Self::bootstrap_acp_connection(
             &mut child,
@@ -105,6 +110,8 @@ impl AcpAgentHarness {
             None,
             prompt,
             Some(exit_tx),
+            live_rx,
+            env.interactive,
             self.session_namespace.clone(),
             self.model.clone(),
             self.mode.clone(),
@@ -117,6 +124,7 @@ impl AcpAgentHarness {
             child,
             exit_signal: Some(exit_rx),
             cancel: Some(cancel),
+            live_input: Some(live_input),
         })
     }
 
@@ -151,6 +159,7 @@ impl AcpAgentHarness {
 
         let (exit_tx, exit_rx) = tokio::sync::oneshot::channel::<ExecutorExitResult>();
         let cancel = CancellationToken::new();
+        let (live_input, live_rx) = live_input_channel();
 
         Self::bootstrap_acp_connection(
             &mut child,
@@ -158,6 +167,8 @@ impl AcpAgentHarness {
             Some(session_id.to_string()),
             prompt,
             Some(exit_tx),
+            live_rx,
+            env.interactive,
             self.session_namespace.clone(),
             self.model.clone(),
             self.mode.clone(),
@@ -170,6 +181,7 @@ impl AcpAgentHarness {
             child,
             exit_signal: Some(exit_rx),
             cancel: Some(cancel),
+            live_input: Some(live_input),
         })
     }
 
@@ -180,6 +192,8 @@ impl AcpAgentHarness {
         existing_session: Option<String>,
         prompt: String,
         exit_signal: Option<tokio::sync::oneshot::Sender<ExecutorExitResult>>,
+        live_rx: LiveInputReceiver,
+        interactive: bool,
         session_namespace: String,
         model: Option<String>,
         mode: Option<String>,
@@ -324,7 +338,7 @@ impl AcpAgentHarness {
                             .await;
 
                         // Handle session creation/forking
-                        let (acp_session_id, display_session_id, prompt_to_send) =
+                        let (acp_session_id, display_session_id, prompt_to_send, modes) =
                             if let Some(existing) = existing_session {
                                 // Fork existing session
                                 let new_ui_id = uuid::Uuid::new_v4().to_string();
@@ -345,7 +359,12 @@ impl AcpAgentHarness {
                                         let resume_prompt = session_manager
                                             .generate_resume_prompt(&new_ui_id, &prompt)
                                             .unwrap_or_else(|_| prompt.clone());
-                                        (resp.session_id.0.to_string(), new_ui_id, resume_prompt)
+                                        (
+                                            resp.session_id.0.to_string(),
+                                            new_ui_id,
+                                            resume_prompt,
+                                            resp.modes,
+                                        )
                                     }
                                     Err(e) => {
                                         error!("Failed to create session: {}", e);
@@ -360,7 +379,7 @@ impl AcpAgentHarness {
                                 {
                                     Ok(resp) => {
                                         let sid = resp.session_id.0.to_string();
-                                        (sid.clone(), sid, prompt)
+                                        (sid.clone(), sid, prompt, resp.modes)
                                     }
                                     Err(e) => {
                                         error!("Failed to create session: {}", e);
@@ -369,6 +388,17 @@ impl AcpAgentHarness {
                                 }
                             };
 
+                        // Mode ids the agent offers, for switching modes mid-session
+                        let available_modes: Vec<String> = modes
+                            .map(|state| {
+                                state
+                                    .available_modes
+                                    .into_iter()
+                                    .map(|mode| mode.id.0.to_string())
+                                    .collect()
+                            })
+                            .unwrap_or_default();
+
                         // Emit session ID
                         let _ = log_tx
                             .send(AcpEvent::SessionStart(display_session_id.clone()).to_string());
@@ -446,6 +476,9 @@ impl AcpAgentHarness {
                         );
 
                         let mut current_req = Some(initial_req);
+                        let mut live_rx = Some(live_rx);
+                        let mut steer: Option<String> = None;
+                        let mut queued_messages = std::collections::VecDeque::new();
 
                         while let Some(req) = current_req.take() {
                             if cancel.is_cancelled() {
@@ -454,13 +487,45 @@ impl AcpAgentHarness {
                             }
 
                             tracing::trace!(?req, "sending ACP prompt request");
-                            // Send the prompt and await completion to obtain stop_reason
-                            let prompt_result = tokio::select! {
-                                _ = cancel.cancelled() => {
-                                    tracing::debug!("ACP executor cancelled during prompt");
-                                    break;
+                            // Send the prompt and await completion to obtain stop_reason, while
+                            // handling interrupts and messages sent during the turn
+                            let prompt_fut = conn.prompt(req);
+                            tokio::pin!(prompt_fut);
+                            let prompt_result = loop {
+                                tokio::select! {
+                                    _ = cancel.cancelled() => break None,
+                                    result = &mut prompt_fut => break Some(result),
+                                    Some(input) = recv_live_input(&mut live_rx) => match input {
+                                        LiveInput::UserMessage(message) => {
+                                            queued_messages.push_back(message);
+                                        }
+                                        LiveInput::Interrupt { instruction } => {
+                                            let _ = log_tx.send(
+                                                SteerMarker::new(instruction.clone()).raw(),
+                                            );
+                                            steer = instruction.or(steer);
+                                            let _ = conn
+                                                .cancel(proto::CancelNotification::new(
+                                                    proto::SessionId::new(acp_session_id.clone()),
+                                                ))
+                                                .await;
+                                        }
+                                        LiveInput::SetMode(mode) => {
+                                            let marker = switch_session_mode(
+                                                &conn,
+                                                &acp_session_id,
+                                                &available_modes,
+                                                mode,
+                                            )
+                                            .await;
+                                            let _ = log_tx.send(marker.raw());
+                                        }
+                                    },
                                 }
-                                result = conn.prompt(req) => result,
+                            };
+                            let Some(prompt_result) = prompt_result else {
+                                tracing::debug!("ACP executor cancelled during prompt");
+                                break;
                             };
 
                             match prompt_result {
@@ -486,6 +551,20 @@ impl AcpAgentHarness {
                                 }
                             }
 
+                            let new_prompt_req = |text: String| {
+                                proto::PromptRequest::new(
+                                    proto::SessionId::new(acp_session_id.clone()),
+                                    vec![proto::ContentBlock::Text(proto::TextContent::new(text))],
+                                )
+                            };
+
+                            // A steer instruction replaces the interrupted turn
+                            // (the steer marker already records it as a user message)
+                            if let Some(instruction) = steer.take() {
+                                current_req = Some(new_prompt_req(instruction));
+                                continue;
+                            }
+
                             // Flush any pending user feedback after finish
                             let feedback = client_feedback_handle
                                 .drain_feedback()
@@ -495,14 +574,52 @@ impl AcpAgentHarness {
                                 .to_string();
                             if !feedback.is_empty() {
                                 tracing::trace!(?feedback, "sending ACP follow-up feedback");
-                                let session_id = proto::SessionId::new(acp_session_id.clone());
-                                let feedback_req = proto::PromptRequest::new(
-                                    session_id.clone(),
-                                    vec![proto::ContentBlock::Text(proto::TextContent::new(
-                                        feedback,
-                                    ))],
+                                current_req = Some(new_prompt_req(feedback));
+                                continue;
+                            }
+
+                            // Live sessions continue with queued or the next user message
+                            if !interactive {
+                                continue;
+                            }
+                            let message = match queued_messages.pop_front() {
+                                Some(message) => Some(message),
+                                None => loop {
+                                    tokio::select! {
+                                        _ = cancel.cancelled() => break None,
+                                        input = recv_live_input(&mut live_rx) => match input {
+                                            Some(LiveInput::UserMessage(message)) => {
+                                                break Some(message);
+                                            }
+                                            // Nothing is running to interrupt
+                                            Some(LiveInput::Interrupt { instruction }) => {
+                                                if instruction.is_some() {
+                                                    break instruction;
+                                                }
+                                            }
+                                            Some(LiveInput::SetMode(mode)) => {
+                                                let marker = switch_session_mode(
+                                                    &conn,
+                                                    &acp_session_id,
+                                                    &available_modes,
+                                                    mode,
+                                                )
+                                                .await;
+                                                let _ = log_tx.send(marker.raw());
+                                            }
+                                            None => break None,
+                                        },
+                                    }
+                                },
+                            };
+                            if let Some(message) = message {
+                                client_feedback_handle.record_user_prompt_event(&message);
+                                let _ = session_manager.append_raw_line(
+                                    &display_session_id,
+                                    &serde_json::to_string(&serde_json::json!({ "user": message }))
+                                        .unwrap_or_default(),
                                 );
-                                current_req = Some(feedback_req);
+                                current_req = Some(new_prompt_req(message));
                             }
                         }
 
@@ -531,3 +648,85 @@ impl AcpAgentHarness {
         Ok(())
     }
 }
+
+/// ACP mode ids are agent specific; pick the advertised id matching `mode`
+fn acp_mode_id(mode: SessionMode, available: &[String]) -> Option<String> {
+    let candidates: &[&str] = match mode {
+        SessionMode::Plan => &["plan"],
+        SessionMode::Default => &["default"],
+        SessionMode::AcceptEdits => &["autoEdit", "auto-edit", "auto_edit", "acceptEdits"],
+        SessionMode::Bypass => &["yolo", "bypassPermissions", "bypass"],
+    };
+    candidates.iter().find_map(|candidate| {
+        available
+            .iter()
+            .find(|id| id.eq_ignore_ascii_case(candidate))
+            .cloned()
+    })
+}
+
+async fn switch_session_mode(
+    conn: &proto::ClientSideConnection,
+    session_id: &str,
+    available: &[String],
+    mode: SessionMode,
+) -> ModeMarker {
+    let Some(mode_id) = acp_mode_id(mode, available) else {
+        return ModeMarker::failed(mode, "the agent does not offer this mode");
+    };
+    match conn
+        .set_session_mode(proto::SetSessionModeRequest::new(
+            proto::SessionId::new(session_id.to_string()),
+            mode_id,
+        ))
+        .await
+    {
+        Ok(_) => ModeMarker::new(mode),
+        Err(e) => ModeMarker::failed(mode, e.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn maps_session_modes_to_acp_mode_ids() {
+        // Claude Code over ACP
+        let claude = ids(&["default", "acceptEdits", "plan", "bypassPermissions"]);
+        assert_eq!(
+            acp_mode_id(SessionMode::Plan, &claude).as_deref(),
+            Some("plan")
+        );
+        assert_eq!(
+            acp_mode_id(SessionMode::AcceptEdits, &claude).as_deref(),
+            Some("acceptEdits")
+        );
+        assert_eq!(
+            acp_mode_id(SessionMode::Bypass, &claude).as_deref(),
+            Some("bypassPermissions")
+        );
+
+        // Gemini / Qwen; ids match case-insensitively and keep the agent's spelling
+        let gemini = ids(&["Default", "autoEdit", "yolo"]);
+        assert_eq!(
+            acp_mode_id(SessionMode::Default, &gemini).as_deref(),
+            Some("Default")
+        );
+        assert_eq!(
+            acp_mode_id(SessionMode::AcceptEdits, &gemini).as_deref(),
+            Some("autoEdit")
+        );
+        assert_eq!(
+            acp_mode_id(SessionMode::Bypass, &gemini).as_deref(),
+            Some("yolo")
+        );
+        assert_eq!(acp_mode_id(SessionMode::Plan, &gemini), None);
+
+        assert_eq!(acp_mode_id(SessionMode::Default, &[]), None);
+    }
+}
@@ -17,6 +17,7 @@ use crate::{
     logs::{
         ActionType, FileChange, NormalizedEntry, NormalizedEntryError, NormalizedEntryType,
         TodoItem, ToolResult, ToolResultValueType, ToolStatus as LogToolStatus,
+        control_marker_entries,
         stderr_processor::normalize_stderr_logs,
         utils::{ConversationPatch, EntryIndexProvider},
     },
@@ -39,6 +40,16 @@ pub fn normalize_logs(msg_store: Arc<MsgStore>, worktree_path: &Path) {
 
         let mut stdout_lines = msg_store.stdout_lines_stream();
         while let Some(Ok(line)) = stdout_lines.next().await {
+            if let Some(entries) = control_marker_entries(&line) {
+                streaming.assistant_text = None;
+                streaming.thinking_text = None;
+                for entry in entries {
+                    let idx = entry_index.next();
+                    msg_store.push_patch(ConversationPatch::add_normalized_entry(idx, entry));
+                }
+                continue;
+            }
+
             if let Some(parsed) = AcpEventParser::parse_line(&line) {
                 tracing::trace!("Parsed ACP line: {:?}", parsed);
                 match parsed {
@@ -1,13 +1,13 @@
 use std::{
     fs::{self, OpenOptions},
-    io::{self, Result, Write},
+    io::{Result, Write},
     path::PathBuf,
     str::FromStr,
 };
 
 use serde::{Deserialize, Serialize};
 
-use crate::executors::acp::AcpEvent;
+use crate::executors::{acp::AcpEvent, utils::session_dir};
 
 /// Manages session persistence and state for ACP interactions
 pub struct SessionManager {
@@ -17,19 +17,7 @@ pub struct SessionManager {
 impl SessionManager {
     /// Create a new session manager with the given namespace
     pub fn new(namespace: impl Into<String>) -> Result<Self> {
-        let namespace = namespace.into();
-        let mut vk_dir = dirs::home_dir()
-            .ok_or_else(|| io::Error::other("Could not determine home directory"))?
-            .join(".code-marshal");
-
-        if cfg!(debug_assertions) {
-            vk_dir = vk_dir.join("dev");
-        }
-
-        let base_dir = vk_dir.join(&namespace);
-
-        fs::create_dir_all(&base_dir)?;
-
+        let base_dir = session_dir(&namespace.into())?;
         Ok(Self { base_dir })
     }
 
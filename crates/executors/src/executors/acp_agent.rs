@@ -0,0 +1,237 @@
+//! Generic executor for any agent that speaks the Agent Client Protocol. Everything that differs
+//! between ACP agents (command, session namespace, default mode and model, capabilities) comes
+//! from the profile, so a new ACP agent is a `profiles.json` entry:
+//!
+//! ```json
+//! { "executors": { "ACP": { "MY_AGENT": { "ACP": {
+//!     "command": "my-agent", "args": ["--acp"], "session_namespace": "my_agent_sessions"
+//! } } } } }
+//! ```
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use derivative::Derivative;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+use workspace_utils::msg_store::MsgStore;
+
+use crate::{
+    approvals::ExecutorApprovalService,
+    command::{CmdOverrides, CommandBuildError, CommandBuilder, apply_overrides},
+    env::ExecutionEnv,
+    executors::{
+        AppendPrompt, AvailabilityInfo, BaseAgentCapability, ExecutorError, SpawnedChild,
+        StandardCodingAgentExecutor, acp::AcpAgentHarness,
+    },
+};
+
+const DEFAULT_SESSION_NAMESPACE: &str = "acp_sessions";
+
+/// Capabilities the ACP harness implements for every agent; a profile can only narrow them
+const HARNESS_CAPABILITIES: &[BaseAgentCapability] = &[
+    BaseAgentCapability::SessionFork,
+    BaseAgentCapability::LiveInput,
+    BaseAgentCapability::Interrupt,
+    BaseAgentCapability::ModeSwitch,
+    BaseAgentCapability::Approvals,
+];
+
+#[derive(Derivative, Clone, Serialize, Deserialize, TS, JsonSchema)]
+#[derivative(Debug, PartialEq)]
+pub struct Acp {
+    #[serde(default)]
+    pub append_prompt: AppendPrompt,
+    #[schemars(
+        title = "Command",
+        description = "Command that starts the agent in ACP mode (e.g. \"npx -y my-agent-acp\")"
+    )]
+    pub command: String,
+    #[schemars(
+        title = "Arguments",
+        description = "Arguments appended to the command, e.g. the flag that enables ACP"
+    )]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    #[schemars(
+        title = "Session Namespace",
+        description = "Directory name under which session logs are kept for follow-ups"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_namespace: Option<String>,
+    #[schemars(
+        title = "Mode",
+        description = "Session mode id set before the first prompt (e.g. \"plan\")"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mode: Option<String>,
+    #[schemars(
+        title = "Model",
+        description = "Session model id set before the first prompt"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    #[schemars(
+        title = "Capabilities",
+        description = "What the agent supports, out of SESSION_FORK, LIVE_INPUT, INTERRUPT, \
+                       MODE_SWITCH and APPROVALS (default: all of them); others are ignored"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capabilities: Option<Vec<BaseAgentCapability>>,
+    #[schemars(
+        title = "MCP Config Path",
+        description = "The agent's config file holding its MCP servers, if any"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mcp_config_path: Option<PathBuf>,
+    #[serde(flatten)]
+    pub cmd: CmdOverrides,
+    #[serde(skip)]
+    #[ts(skip)]
+    #[derivative(Debug = "ignore", PartialEq = "ignore")]
+    pub approvals: Option<Arc<dyn ExecutorApprovalService>>,
+}
+
+impl Acp {
+    fn build_command_builder(&self) -> Result<CommandBuilder, CommandBuildError> {
+        let builder = CommandBuilder::new(self.command.as_str()).params(self.args.clone());
+        apply_overrides(builder, &self.cmd)
+    }
+
+    fn harness(&self) -> AcpAgentHarness {
+        let mut harness = AcpAgentHarness::with_session_namespace(
+            self.session_namespace
+                .as_deref()
+                .unwrap_or(DEFAULT_SESSION_NAMESPACE),
+        );
+        if let Some(model) = &self.model {
+            harness = harness.with_model(model);
+        }
+        if let Some(mode) = &self.mode {
+            harness = harness.with_mode(mode);
+        }
+        harness
+    }
+
+    /// The configured capabilities that the harness supports
+    pub fn capabilities(&self) -> Vec<BaseAgentCapability> {
+        match &self.capabilities {
+            Some(configured) => HARNESS_CAPABILITIES
+                .iter()
+                .filter(|capability| configured.contains(capability))
+                .cloned()
+                .collect(),
+            None => HARNESS_CAPABILITIES.to_vec(),
+        }
+    }
+}
+
+#[async_trait]
+impl StandardCodingAgentExecutor for Acp {
+    fn use_approvals(&mut self, approvals: Arc<dyn ExecutorApprovalService>) {
+        self.approvals = Some(approvals);
+    }
+
+    async fn spawn(
+        &self,
+        current_dir: &Path,
+        prompt: &str,
+        env: &ExecutionEnv,
+    ) -> Result<SpawnedChild, ExecutorError> {
+        let command = self.build_command_builder()?.build_initial()?;
+        let combined_prompt = self.append_prompt.combine_prompt(prompt);
+        self.harness()
+            .spawn_with_command(
+                current_dir,
+                combined_prompt,
+                command,
+                env,
+                &self.cmd,
+                self.approvals.clone(),
+            )
+            .await
+    }
+
+    async fn spawn_follow_up(
+        &self,
+        current_dir: &Path,
+        prompt: &str,
+        session_id: &str,
+        _reset_to_message_id: Option<&str>,
+        env: &ExecutionEnv,
+    ) -> Result<SpawnedChild, ExecutorError> {
+        if !self
+            .capabilities()
+            .contains(&BaseAgentCapability::SessionFork)
+        {
+            return Err(ExecutorError::FollowUpNotSupported(format!(
+                "the ACP profile for `{}` does not list SESSION_FORK",
+                self.command
+            )));
+        }
+        let command = self.build_command_builder()?.build_follow_up(&[])?;
+        let combined_prompt = self.append_prompt.combine_prompt(prompt);
+        self.harness()
+            .spawn_follow_up_with_command(
+                current_dir,
+                combined_prompt,
+                session_id,
+                command,
+                env,
+                &self.cmd,
+                self.approvals.clone(),
+            )
+            .await
+    }
+
+    fn normalize_logs(&self, msg_store: Arc<MsgStore>, worktree_path: &Path) {
+        crate::executors::acp::normalize_logs(msg_store, worktree_path);
+    }
+
+    fn default_mcp_config_path(&self) -> Option<PathBuf> {
+        self.mcp_config_path.clone()
+    }
+
+    fn get_availability_info(&self) -> AvailabilityInfo {
+        let base = self
+            .cmd
+            .base_command_override
+            .as_deref()
+            .unwrap_or(&self.command);
+        let program = shlex::split(base).and_then(|words| words.into_iter().next());
+        match program {
+            Some(program)
+                if workspace_utils::shell::resolve_executable_path_blocking(&program).is_some() =>
+            {
+                AvailabilityInfo::InstallationFound
+            }
+            _ => AvailabilityInfo::NotFound,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_narrows_capabilities_to_the_harness() {
+        let acp: Acp = serde_json::from_value(serde_json::json!({
+            "command": "my-agent",
+            "args": ["--acp"],
+            "capabilities": ["LIVE_INPUT", "IMAGE_INPUT", "INTERRUPT"]
+        }))
+        .unwrap();
+        assert_eq!(
+            acp.capabilities(),
+            vec![
+                BaseAgentCapability::LiveInput,
+                BaseAgentCapability::Interrupt
+            ]
+        );
+    }
+}
@@ -0,0 +1,227 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use command_group::AsyncCommandGroup;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+use ts_rs::TS;
+use workspace_utils::msg_store::MsgStore;
+
+use crate::{
+    command::{CmdOverrides, CommandBuildError, CommandBuilder, apply_overrides},
+    env::ExecutionEnv,
+    executors::{
+        AppendPrompt, AvailabilityInfo, ExecutorError, SpawnedChild, StandardCodingAgentExecutor,
+        utils::session_dir,
+    },
+    logs::utils::EntryIndexProvider,
+    stdout_dup::tee_stdout_with_appender,
+};
+
+pub mod normalize_logs;
+
+use normalize_logs::{SESSION_MARKER, normalize_logs};
+
+/// Aider executor configuration
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, TS, JsonSchema)]
+pub struct Aider {
+    #[serde(default)]
+    pub append_prompt: AppendPrompt,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(
+        title = "Model",
+        description = "Model name or alias (e.g., sonnet, opus, gpt-4.1, o3, gemini, deepseek)"
+    )]
+    pub model: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(
+        title = "Edit Format",
+        description = "How the model edits files: diff, diff-fenced, udiff, whole"
+    )]
+    pub edit_format: Option<String>,
+
+    #[serde(default = "default_to_true")]
+    #[schemars(
+        title = "Yes Always",
+        description = "Confirm every prompt, including running suggested shell commands"
+    )]
+    pub yes_always: bool,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(
+        title = "Auto Commits",
+        description = "Commit each change aider makes (aider's default is on)"
+    )]
+    pub auto_commits: Option<bool>,
+
+    #[serde(flatten)]
+    pub cmd: CmdOverrides,
+}
+
+fn default_to_true() -> bool {
+    true
+}
+
+/// Where a session's chat and input history live, so follow-ups can restore the chat
+fn session_files(session_id: &str) -> io::Result<(PathBuf, PathBuf)> {
+    // The id comes from `--follow-up`, so it must not reach outside the sessions directory
+    if session_id.is_empty() || session_id.contains(['/', '\\']) || session_id.contains("..") {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid aider session id `{session_id}`"),
+        ));
+    }
+    let dir = session_dir("aider_sessions")?;
+    Ok((
+        dir.join(format!("{session_id}.chat.md")),
+        dir.join(format!("{session_id}.input.history")),
+    ))
+}
+
+impl Aider {
+    pub fn base_command() -> &'static str {
+        "aider"
+    }
+
+    fn build_command_builder(&self) -> Result<CommandBuilder, CommandBuildError> {
+        let mut builder = CommandBuilder::new(Self::base_command()).params([
+            "--no-pretty",
+            "--no-fancy-input",
+            "--no-check-update",
+            "--no-show-release-notes",
+        ]);
+        if self.yes_always {
+            builder = builder.extend_params(["--yes-always"]);
+        }
+        match self.auto_commits {
+            Some(true) => builder = builder.extend_params(["--auto-commits"]),
+            Some(false) => builder = builder.extend_params(["--no-auto-commits"]),
+            None => {}
+        }
+        if let Some(model) = &self.model {
+            builder = builder.extend_params(["--model", model.as_str()]);
+        }
+        if let Some(edit_format) = &self.edit_format {
+            builder = builder.extend_params(["--edit-format", edit_format.as_str()]);
+        }
+
+        apply_overrides(builder, &self.cmd)
+    }
+
+    async fn spawn_aider(
+        &self,
+        current_dir: &Path,
+        prompt: &str,
+        session_id: &str,
+        restore: bool,
+        env: &ExecutionEnv,
+    ) -> Result<SpawnedChild, ExecutorError> {
+        let (chat_history, input_history) = session_files(session_id).map_err(ExecutorError::Io)?;
+        if restore && !chat_history.exists() {
+            return Err(ExecutorError::FollowUpNotSupported(format!(
+                "no aider chat history for session {session_id}"
+            )));
+        }
+
+        let combined_prompt = self.append_prompt.combine_prompt(prompt);
+        let mut history_args = vec![
+            "--chat-history-file".to_string(),
+            chat_history.to_string_lossy().into_owned(),
+            "--input-history-file".to_string(),
+            input_history.to_string_lossy().into_owned(),
+        ];
+        if restore {
+            history_args.push("--restore-chat-history".to_string());
+        }
+        let command_parts = self
+            .build_command_builder()?
+            .extend_params(history_args)
+            .extend_params(["--message".to_string(), combined_prompt])
+            .build_initial()?;
+        let (program_path, args) = command_parts.into_resolved().await?;
+
+        let mut command = Command::new(program_path);
+        command
+            .kill_on_drop(true)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .current_dir(current_dir)
+            .args(args);
+
+        env.clone()
+            .with_profile(&self.cmd)
+            .apply_to_command(&mut command);
+
+        let mut child = command.group_spawn()?;
+
+        // Aider has no session ids of its own; announce ours so follow-ups can restore the chat
+        let (_, appender) = tee_stdout_with_appender(&mut child)?;
+        appender.append_line(format!("{SESSION_MARKER}{session_id}"));
+
+        Ok(child.into())
+    }
+}
+
+#[async_trait]
+impl StandardCodingAgentExecutor for Aider {
+    async fn spawn(
+        &self,
+        current_dir: &Path,
+        prompt: &str,
+        env: &ExecutionEnv,
+    ) -> Result<SpawnedChild, ExecutorError> {
+        let session_id = uuid::Uuid::new_v4().to_string();
+        self.spawn_aider(current_dir, prompt, &session_id, false, env)
+            .await
+    }
+
+    async fn spawn_follow_up(
+        &self,
+        current_dir: &Path,
+        prompt: &str,
+        session_id: &str,
+        _reset_to_message_id: Option<&str>,
+        env: &ExecutionEnv,
+    ) -> Result<SpawnedChild, ExecutorError> {
+        self.spawn_aider(current_dir, prompt, session_id, true, env)
+            .await
+    }
+
+    fn normalize_logs(&self, msg_store: Arc<MsgStore>, _current_dir: &Path) {
+        normalize_logs(
+            msg_store.clone(),
+            self.edit_format.clone(),
+            EntryIndexProvider::start_from(&msg_store),
+        );
+    }
+
+    fn default_mcp_config_path(&self) -> Option<PathBuf> {
+        None
+    }
+
+    fn get_availability_info(&self) -> AvailabilityInfo {
+        let base = self
+            .cmd
+            .base_command_override
+            .as_deref()
+            .unwrap_or(Self::base_command());
+        let program = shlex::split(base).and_then(|words| words.into_iter().next());
+        match program {
+            Some(program)
+                if workspace_utils::shell::resolve_executable_path_blocking(&program).is_some() =>
+            {
+                AvailabilityInfo::InstallationFound
+            }
+            _ => AvailabilityInfo::NotFound,
+        }
+    }
+}
@@ -0,0 +1,746 @@
+//! Normalizes aider's `--no-pretty` output. Aider prints plain text: startup and status lines,
+//! the model's reply, edits as SEARCH/REPLACE blocks (or unified diffs / whole files, depending
+//! on the edit format), `Applied edit to <path>` once an edit lands, and `Running <command>`
+//! followed by the output of shell commands it runs.
+
+use std::{collections::HashMap, sync::Arc};
+
+use futures::StreamExt;
+use workspace_utils::{
+    diff::{create_unified_diff, normalize_unified_diff},
+    msg_store::MsgStore,
+};
+
+use crate::logs::{
+    ActionType, CommandRunResult, FileChange, NormalizedEntry, NormalizedEntryError,
+    NormalizedEntryType, ToolStatus,
+    stderr_processor::normalize_stderr_logs,
+    utils::{ConversationPatch, EntryIndexProvider},
+};
+
+/// Line the executor adds to stdout to announce the session id
+pub const SESSION_MARKER: &str = "[code-marshal] aider session: ";
+
+const SEARCH_MARKER: &str = "<<<<<<< SEARCH";
+const DIVIDER_MARKER: &str = "=======";
+const REPLACE_MARKER: &str = ">>>>>>> REPLACE";
+
+/// Status lines aider prints around the model's reply
+const SYSTEM_PREFIXES: &[&str] = &[
+    "Aider v",
+    "Main model:",
+    "Weak model:",
+    "Editor model:",
+    "Git repo:",
+    "Repo-map:",
+    "Use /help",
+    "https://aider.chat",
+    "Tokens:",
+    "Cost:",
+    "Warning:",
+    "Creating empty file",
+];
+
+pub fn normalize_logs(
+    msg_store: Arc<MsgStore>,
+    edit_format: Option<String>,
+    entry_index_provider: EntryIndexProvider,
+) {
+    normalize_stderr_logs(msg_store.clone(), entry_index_provider.clone());
+
+    tokio::spawn(async move {
+        let mut lines = msg_store.stdout_lines_stream();
+        let mut parser = AiderOutputParser::new(edit_format.as_deref());
+        let mut indices: HashMap<usize, usize> = HashMap::new();
+        let mut push = |updates: Vec<EntryUpdate>| {
+            for EntryUpdate { slot, entry } in updates {
+                let patch = match indices.get(&slot) {
+                    Some(&index) => ConversationPatch::replace(index, entry),
+                    None => {
+                        let index = entry_index_provider.next();
+                        indices.insert(slot, index);
+                        ConversationPatch::add_normalized_entry(index, entry)
+                    }
+                };
+                msg_store.push_patch(patch);
+            }
+        };
+
+        while let Some(Ok(line)) = lines.next().await {
+            let line = strip_ansi_escapes::strip_str(&line);
+            if let Some(session_id) = line.strip_prefix(SESSION_MARKER) {
+                msg_store.push_session_id(session_id.trim().to_string());
+                continue;
+            }
+            push(parser.process_line(&line));
+        }
+        push(parser.finish());
+    });
+}
+
+/// A new entry, or a newer version of one, identified by the parser's slot number
+#[derive(Debug)]
+pub struct EntryUpdate {
+    pub slot: usize,
+    pub entry: NormalizedEntry,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TextKind {
+    Assistant,
+    System,
+}
+
+#[derive(Debug)]
+enum State {
+    Idle,
+    Text {
+        kind: TextKind,
+        slot: usize,
+        lines: Vec<String>,
+    },
+    SearchReplace {
+        path: String,
+        search: Vec<String>,
+        replace: Option<Vec<String>>,
+    },
+    UnifiedDiff {
+        lines: Vec<String>,
+    },
+    WholeFile {
+        path: String,
+        lines: Vec<String>,
+    },
+    Command {
+        slot: usize,
+        command: String,
+        output: Vec<String>,
+    },
+}
+
+pub struct AiderOutputParser {
+    whole_format: bool,
+    state: State,
+    next_slot: usize,
+    /// Skip the fence that closes a SEARCH/REPLACE block
+    skip_closing_fence: bool,
+    /// Between aider's "Run shell command?" and "Add command output to the chat?" prompts
+    running_commands: bool,
+    /// Edits awaiting `Applied edit to <path>`, by path
+    pending_edits: HashMap<String, Vec<(usize, NormalizedEntry)>>,
+}
+
+impl AiderOutputParser {
+    pub fn new(edit_format: Option<&str>) -> Self {
+        Self {
+            whole_format: edit_format.is_some_and(|format| format.ends_with("whole")),
+            state: State::Idle,
+            next_slot: 0,
+            skip_closing_fence: false,
+            running_commands: false,
+            pending_edits: HashMap::new(),
+        }
+    }
+
+    fn slot(&mut self) -> usize {
+        self.next_slot += 1;
+        self.next_slot - 1
+    }
+
+    pub fn process_line(&mut self, line: &str) -> Vec<EntryUpdate> {
+        let line = line.trim_end_matches(['\n', '\r']);
+        let mut updates = Vec::new();
+        match std::mem::replace(&mut self.state, State::Idle) {
+            State::SearchReplace {
+                path,
+                mut search,
+                mut replace,
+            } => {
+                let marker = line.trim_end();
+                if let Some(replace) = replace.as_ref().filter(|_| marker == REPLACE_MARKER) {
+                    let diff =
+                        create_unified_diff(&path, &join_lines(&search), &join_lines(replace));
+                    let change = FileChange::Edit {
+                        unified_diff: diff,
+                        has_line_numbers: false,
+                    };
+                    updates.push(self.add_edit(path, change));
+                    self.skip_closing_fence = true;
+                    return updates;
+                }
+                match replace.as_mut() {
+                    None if marker == DIVIDER_MARKER => replace = Some(Vec::new()),
+                    None => search.push(line.to_string()),
+                    Some(replace) => replace.push(line.to_string()),
+                }
+                self.state = State::SearchReplace {
+                    path,
+                    search,
+                    replace,
+                };
+                return updates;
+            }
+            State::UnifiedDiff { mut lines } => {
+                if is_fence(line) {
+                    if let Some(update) = self.add_unified_diff(&lines) {
+                        updates.push(update);
+                    }
+                } else {
+                    lines.push(line.to_string());
+                    self.state = State::UnifiedDiff { lines };
+                }
+                return updates;
+            }
+            State::WholeFile { path, mut lines } => {
+                if is_fence(line) {
+                    let change = FileChange::Write {
+                        content: join_lines(&lines),
+                    };
+                    updates.push(self.add_edit(path, change));
+                } else {
+                    lines.push(line.to_string());
+                    self.state = State::WholeFile { path, lines };
+                }
+                return updates;
+            }
+            State::Command {
+                slot,
+                command,
+                mut output,
+            } => {
+                if self.classify(line) == LineKind::Text {
+                    output.push(line.to_string());
+                    updates.push(EntryUpdate {
+                        slot,
+                        entry: command_entry(&command, &output, ToolStatus::Created),
+                    });
+                    self.state = State::Command {
+                        slot,
+                        command,
+                        output,
+                    };
+                    return updates;
+                }
+                updates.push(EntryUpdate {
+                    slot,
+                    entry: command_entry(&command, &output, ToolStatus::Success),
+                });
+            }
+            state @ (State::Idle | State::Text { .. }) => self.state = state,
+        }
+
+        if std::mem::take(&mut self.skip_closing_fence) && is_fence(line) {
+            return updates;
+        }
+
+        match self.classify(line) {
+            LineKind::SearchStart => {
+                let path = self.take_edit_header().unwrap_or_default();
+                updates.extend(self.close_text());
+                self.state = State::SearchReplace {
+                    path,
+                    search: Vec::new(),
+                    replace: None,
+                };
+            }
+            LineKind::DiffHeader if self.text_tail_is_fence() => {
+                self.take_edit_header();
+                updates.extend(self.close_text());
+                self.state = State::UnifiedDiff {
+                    lines: vec![line.to_string()],
+                };
+            }
+            LineKind::Fence if self.whole_format && self.text_tail_is_path() => {
+                let path = self.take_edit_header().unwrap_or_default();
+                updates.extend(self.close_text());
+                self.state = State::WholeFile {
+                    path,
+                    lines: Vec::new(),
+                };
+            }
+            LineKind::AppliedEdit(path) => {
+                updates.extend(self.close_text());
+                updates.extend(self.resolve_edits(path, ToolStatus::Success));
+            }
+            LineKind::FailedEdit(path) => {
+                updates.extend(self.close_text());
+                updates.extend(self.resolve_edits(path, ToolStatus::Failed));
+                updates.push(self.error(line));
+            }
+            LineKind::Error => {
+                updates.extend(self.close_text());
+                updates.push(self.error(line));
+            }
+            LineKind::Running(command) => {
+                updates.extend(self.close_text());
+                let slot = self.slot();
+                updates.push(EntryUpdate {
+                    slot,
+                    entry: command_entry(command, &[], ToolStatus::Created),
+                });
+                self.state = State::Command {
+                    slot,
+                    command: command.to_string(),
+                    output: Vec::new(),
+                };
+            }
+            LineKind::Prompt => {
+                if line.starts_with("Run shell command") {
+                    self.running_commands = true;
+                } else if line.starts_with("Add command output") {
+                    self.running_commands = false;
+                }
+            }
+            LineKind::System => updates.extend(self.push_text(TextKind::System, line)),
+            LineKind::Text | LineKind::Fence | LineKind::DiffHeader => {
+                updates.extend(self.push_text(TextKind::Assistant, line))
+            }
+        }
+        updates
+    }
+
+    /// Flush whatever is still open when the output ends
+    pub fn finish(&mut self) -> Vec<EntryUpdate> {
+        let mut updates = match std::mem::replace(&mut self.state, State::Idle) {
+            State::Command {
+                slot,
+                command,
+                output,
+            } => vec![EntryUpdate {
+                slot,
+                entry: command_entry(&command, &output, ToolStatus::Success),
+            }],
+            state => {
+                self.state = state;
+                self.close_text().into_iter().collect()
+            }
+        };
+        // Edits never confirmed as applied did not land
+        let pending: Vec<String> = self.pending_edits.keys().cloned().collect();
+        for path in pending {
+            updates.extend(self.resolve_edits(&path, ToolStatus::Failed));
+        }
+        updates
+    }
+
+    fn push_text(&mut self, kind: TextKind, line: &str) -> Vec<EntryUpdate> {
+        let mut updates = Vec::new();
+        let continues =
+            matches!(&self.state, State::Text { kind: current, .. } if *current == kind);
+        if !continues {
+            if line.trim().is_empty() {
+                return updates;
+            }
+            updates.extend(self.close_text());
+            let slot = self.slot();
+            self.state = State::Text {
+                kind,
+                slot,
+                lines: Vec::new(),
+            };
+        }
+        let State::Text { slot, lines, .. } = &mut self.state else {
+            unreachable!("text state was just ensured");
+        };
+        lines.push(line.to_string());
+        // Hold back a trailing path or fence line, which may turn out to start an edit
+        let held_back = match kind {
+            TextKind::Assistant => edit_header_len(lines),
+            TextKind::System => 0,
+        };
+        let content = join_lines(&lines[..lines.len() - held_back])
+            .trim()
+            .to_string();
+        if !content.is_empty() {
+            updates.push(EntryUpdate {
+                slot: *slot,
+                entry: text_entry(kind, content),
+            });
+        }
+        updates
+    }
+
+    /// Close the open text entry, with everything it held back
+    fn close_text(&mut self) -> Option<EntryUpdate> {
+        let State::Text { kind, slot, lines } = std::mem::replace(&mut self.state, State::Idle)
+        else {
+            return None;
+        };
+        let content = join_lines(&lines).trim().to_string();
+        (!content.is_empty()).then(|| EntryUpdate {
+            slot,
+            entry: text_entry(kind, content),
+        })
+    }
+
+    fn text_lines(&self) -> &[String] {
+        match &self.state {
+            State::Text {
+                kind: TextKind::Assistant,
+                lines,
+                ..
+            } => lines,
+            _ => &[],
+        }
+    }
+
+    fn text_tail_is_fence(&self) -> bool {
+        self.text_lines().last().is_some_and(|line| is_fence(line))
+    }
+
+    fn text_tail_is_path(&self) -> bool {
+        self.text_lines()
+            .last()
+            .is_some_and(|line| looks_like_path(line))
+    }
+
+    /// Remove the path and fence lines that introduce an edit from the open text; returns the
+    /// path
+    fn take_edit_header(&mut self) -> Option<String> {
+        let State::Text {
+            kind: TextKind::Assistant,
+            lines,
+            ..
+        } = &mut self.state
+        else {
+            return None;
+        };
+        let header_len = edit_header_len(lines);
+        let header = lines.split_off(lines.len() - header_len);
+        header
+            .into_iter()
+            .find(|line| !is_fence(line))
+            .map(|path| path.trim().to_string())
+    }
+
+    fn add_edit(&mut self, path: String, change: FileChange) -> EntryUpdate {
+        let slot = self.slot();
+        let entry = edit_entry(&path, vec![change], ToolStatus::Created);
+        self.pending_edits
+            .entry(path)
+            .or_default()
+            .push((slot, entry.clone()));
+        EntryUpdate { slot, entry }
+    }
+
+    fn add_unified_diff(&mut self, lines: &[String]) -> Option<EntryUpdate> {
+        let path = lines
+            .iter()
+            .find_map(|line| line.strip_prefix("+++ "))
+            .map(|path| path.trim().trim_start_matches("b/").to_string())?;
+        let diff = normalize_unified_diff(&path, &join_lines(lines));
+        Some(self.add_edit(
+            path,
+            FileChange::Edit {
+                unified_diff: diff,
+                has_line_numbers: false,
+            },
+        ))
+    }
+
+    fn resolve_edits(&mut self, path: &str, status: ToolStatus) -> Vec<EntryUpdate> {
+        self.pending_edits
+            .remove(path)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(slot, mut entry)| {
+                if let NormalizedEntryType::ToolUse {
+                    status: current, ..
+                } = &mut entry.entry_type
+                {
+                    *current = status.clone();
+                }
+                EntryUpdate { slot, entry }
+            })
+            .collect()
+    }
+
+    fn classify<'a>(&self, line: &'a str) -> LineKind<'a> {
+        let trimmed = line.trim();
+        if trimmed == SEARCH_MARKER {
+            LineKind::SearchStart
+        } else if line.starts_with("--- ") {
+            LineKind::DiffHeader
+        } else if is_fence(line) {
+            LineKind::Fence
+        } else if let Some(path) = line.strip_prefix("Applied edit to ") {
+            LineKind::AppliedEdit(path.trim())
+        } else if let Some((_, path)) = line
+            .strip_prefix("## SearchReplaceNoExactMatch:")
+            .and_then(|rest| rest.rsplit_once(" in "))
+        {
+            LineKind::FailedEdit(path.trim())
+        } else if trimmed.starts_with("# ") && trimmed.contains("failed to match")
+            || line.starts_with("Error")
+            || line.starts_with("litellm.")
+        {
+            LineKind::Error
+        } else if let Some(command) = line
+            .strip_prefix("Running ")
+            .filter(|_| self.running_commands)
+        {
+            LineKind::Running(command.trim())
+        } else if line.contains("(Y)es/(N)o") {
+            LineKind::Prompt
+        } else if SYSTEM_PREFIXES
+            .iter()
+            .any(|prefix| line.starts_with(prefix))
+            || is_commit_line(line)
+            || line.starts_with("Added ") && line.contains(" to the chat")
+        {
+            LineKind::System
+        } else {
+            LineKind::Text
+        }
+    }
+
+    fn error(&mut self, line: &str) -> EntryUpdate {
+        EntryUpdate {
+            slot: self.slot(),
+            entry: NormalizedEntry {
+                timestamp: None,
+                entry_type: NormalizedEntryType::ErrorMessage {
+                    error_type: NormalizedEntryError::Other,
+                },
+                content: line.trim().to_string(),
+                metadata: None,
+            },
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum LineKind<'a> {
+    SearchStart,
+    DiffHeader,
+    Fence,
+    AppliedEdit(&'a str),
+    FailedEdit(&'a str),
+    Error,
+    Running(&'a str),
+    Prompt,
+    System,
+    Text,
+}
+
+/// `Commit 1a2b3c4 <message>`, printed for aider's auto-commits
+fn is_commit_line(line: &str) -> bool {
+    line.strip_prefix("Commit ")
+        .and_then(|rest| rest.split_whitespace().next())
+        .is_some_and(|sha| sha.len() >= 7 && sha.chars().all(|c| c.is_ascii_hexdigit()))
+}
+
+fn is_fence(line: &str) -> bool {
+    line.trim_start().starts_with("```")
+}
+
+fn looks_like_path(line: &str) -> bool {
+    let trimmed = line.trim();
+    !trimmed.is_empty()
+        && !trimmed.contains(char::is_whitespace)
+        && !trimmed.ends_with(':')
+        && (trimmed.contains('/') || trimmed.contains('.'))
+}
+
+/// Number of trailing lines that could introduce an edit: a path and a fence, in either order
+fn edit_header_len(lines: &[String]) -> usize {
+    let mut len = 0;
+    let mut seen_fence = false;
+    let mut seen_path = false;
+    for line in lines.iter().rev() {
+        if is_fence(line) && !seen_fence {
+            seen_fence = true;
+        } else if looks_like_path(line) && !seen_path {
+            seen_path = true;
+        } else {
+            break;
+        }
+        len += 1;
+    }
+    len
+}
+
+fn join_lines(lines: &[String]) -> String {
+    let mut joined = lines.join("\n");
+    if !joined.is_empty() {
+        joined.push('\n');
+    }
+    joined
+}
+
+fn text_entry(kind: TextKind, content: String) -> NormalizedEntry {
+    NormalizedEntry {
+        timestamp: None,
+        entry_type: match kind {
+            TextKind::Assistant => NormalizedEntryType::AssistantMessage,
+            TextKind::System => NormalizedEntryType::SystemMessage,
+        },
+        content,
+        metadata: None,
+    }
+}
+
+fn edit_entry(path: &str, changes: Vec<FileChange>, status: ToolStatus) -> NormalizedEntry {
+    NormalizedEntry {
+        timestamp: None,
+        entry_type: NormalizedEntryType::ToolUse {
+            tool_name: "edit".to_string(),
+            action_type: ActionType::FileEdit {
+                path: path.to_string(),
+                changes,
+            },
+            status,
+        },
+        content: path.to_string(),
+        metadata: None,
+    }
+}
+
+fn command_entry(command: &str, output: &[String], status: ToolStatus) -> NormalizedEntry {
+    let output = join_lines(output);
+    NormalizedEntry {
+        timestamp: None,
+        entry_type: NormalizedEntryType::ToolUse {
+            tool_name: "bash".to_string(),
+            action_type: ActionType::CommandRun {
+                command: command.to_string(),
+                result: (!output.trim().is_empty()).then(|| CommandRunResult {
+                    exit_status: None,
+                    output: Some(output.trim_end().to_string()),
+                }),
+            },
+            status,
+        },
+        content: command.to_string(),
+        metadata: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(edit_format: Option<&str>, output: &str) -> Vec<(usize, NormalizedEntry)> {
+        let mut parser = AiderOutputParser::new(edit_format);
+        let mut entries: Vec<(usize, NormalizedEntry)> = Vec::new();
+        let updates = output
+            .lines()
+            .flat_map(|line| parser.process_line(line))
+            .chain(parser.finish());
+        for EntryUpdate { slot, entry } in updates {
+            match entries.iter_mut().find(|(s, _)| *s == slot) {
+                Some((_, existing)) => *existing = entry,
+                None => entries.push((slot, entry)),
+            }
+        }
+        entries
+    }
+
+    #[test]
+    fn parses_search_replace_edits_and_commands() {
+        let output = "\
+Aider v0.86.1
+Main model: anthropic/claude-sonnet-4 with diff edit format
+I'll rename the function.
+
+src/lib.rs
+```rust
+<<<<<<< SEARCH
+fn old() {}
+=======
+fn new() {}
+>>>>>>> REPLACE
+```
+
+Applied edit to src/lib.rs
+Run shell command? (Y)es/(N)o/(D)on't ask again [Yes]: y
+Running cargo check
+    Checking demo v0.1.0
+Add command output to the chat? (Y)es/(N)o [Yes]: y
+Tokens: 2.3k sent, 120 received.
+";
+        let entries = run(None, output);
+        let kinds: Vec<_> = entries
+            .iter()
+            .map(|(_, entry)| match &entry.entry_type {
+                NormalizedEntryType::SystemMessage => "system",
+                NormalizedEntryType::AssistantMessage => "assistant",
+                NormalizedEntryType::ToolUse { tool_name, .. } => tool_name.as_str(),
+                _ => "other",
+            })
+            .collect();
+        assert_eq!(kinds, ["system", "assistant", "edit", "bash", "system"]);
+        assert_eq!(entries[1].1.content, "I'll rename the function.");
+
+        let NormalizedEntryType::ToolUse {
+            action_type: ActionType::FileEdit { path, changes },
+            status,
+            ..
+        } = &entries[2].1.entry_type
+        else {
+            panic!("expected an edit");
+        };
+        assert_eq!(path, "src/lib.rs");
+        assert!(matches!(status, ToolStatus::Success));
+        let [FileChange::Edit { unified_diff, .. }] = changes.as_slice() else {
+            panic!("expected one diff");
+        };
+        assert!(unified_diff.contains("-fn old() {}") && unified_diff.contains("+fn new() {}"));
+
+        let NormalizedEntryType::ToolUse {
+            action_type: ActionType::CommandRun { command, result },
+            ..
+        } = &entries[3].1.entry_type
+        else {
+            panic!("expected a command");
+        };
+        assert_eq!(command, "cargo check");
+        assert_eq!(
+            result.as_ref().and_then(|r| r.output.as_deref()),
+            Some("    Checking demo v0.1.0")
+        );
+    }
+
+    #[test]
+    fn parses_whole_file_edits_and_failed_edits() {
+        let output = "\
+notes.md
+```
+# Notes
+```
+Applied edit to notes.md
+";
+        let entries = run(Some("whole"), output);
+        assert_eq!(entries.len(), 1);
+        assert!(matches!(
+            &entries[0].1.entry_type,
+            NormalizedEntryType::ToolUse {
+                action_type: ActionType::FileEdit { changes, .. },
+                status: ToolStatus::Success,
+                ..
+            } if matches!(&changes[..], [FileChange::Write { content }] if content == "# Notes\n")
+        ));
+
+        let output = "\
+a.txt
+<<<<<<< SEARCH
+x
+=======
+y
+>>>>>>> REPLACE
+## SearchReplaceNoExactMatch: This SEARCH block failed to exactly match lines in a.txt
+";
+        let entries = run(None, output);
+        assert!(matches!(
+            &entries[0].1.entry_type,
+            NormalizedEntryType::ToolUse {
+                status: ToolStatus::Failed,
+                ..
+            }
+        ));
+        assert!(matches!(
+            &entries[1].1.entry_type,
+            NormalizedEntryType::ErrorMessage { .. }
+        ));
+    }
+}
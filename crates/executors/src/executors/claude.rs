@@ -27,29 +27,35 @@ use workspace_utils::{
 use self::{
     client::{AUTO_APPROVE_CALLBACK_ID, ClaudeAgentClient, STOP_GIT_CHECK_CALLBACK_ID},
     protocol::ProtocolPeer,
-    types::{ControlRequestType, ControlResponseType, PermissionMode},
+    types::{
+        ClaudeImageSource, ClaudeUserContent, ClaudeUserContentBlock, ControlRequestType,
+        ControlResponseType, PermissionMode,
+    },
 };
 use crate::{
     approvals::ExecutorApprovalService,
+    attachments::ImageAttachment,
     command::{CmdOverrides, CommandBuildError, CommandBuilder, CommandParts, apply_overrides},
     env::ExecutionEnv,
     executors::{
-        AppendPrompt, AvailabilityInfo, ExecutorError, SpawnedChild, StandardCodingAgentExecutor,
-        codex::client::LogWriter, utils::reorder_slash_commands,
+        AppendPrompt, AvailabilityInfo, BaseCodingAgent, ExecutorError, LiveInput, SpawnedChild,
+        StandardCodingAgentExecutor, codex::client::LogWriter, live_input_channel,
+        utils::reorder_slash_commands,
     },
     logs::{
-        ActionType, FileChange, NormalizedEntry, NormalizedEntryError, NormalizedEntryType,
-        TodoItem, ToolStatus,
+        ActionType, FileChange, ModeMarker, NormalizedEntry, NormalizedEntryError,
+        NormalizedEntryType, SteerMarker, TodoItem, ToolStatus, control_marker_entries,
         stderr_processor::normalize_stderr_logs,
         utils::{
             EntryIndexProvider,
-            patch::{self, ConversationPatch},
+            patch::{self, ConversationPatch, DiffSource},
         },
     },
+    models::{ModelCatalog, known_models},
     stdout_dup::create_stdout_pipe_writer,
 };
 
-fn base_command(claude_code_router: bool) -> &'static str {
+pub fn base_command(claude_code_router: bool) -> &'static str {
     if claude_code_router {
         "npx -y @musistudio/claude-code-router@1.0.66 code"
     } else {
@@ -57,6 +63,31 @@ fn base_command(claude_code_router: bool) -> &'static str {
     }
 }
 
+/// Build the initial user message; prompt images are sent as base64 content blocks
+fn build_user_content(
+    prompt: &str,
+    images: &[ImageAttachment],
+) -> Result<ClaudeUserContent, ExecutorError> {
+    if images.is_empty() {
+        return Ok(ClaudeUserContent::Text(prompt.to_string()));
+    }
+    let mut blocks = vec![ClaudeUserContentBlock::Text {
+        text: prompt.to_string(),
+    }];
+    for image in images {
+        let data = image
+            .read_base64()
+            .map_err(|e| ExecutorError::Io(std::io::Error::other(e)))?;
+        blocks.push(ClaudeUserContentBlock::Image {
+            source: ClaudeImageSource::Base64 {
+                media_type: image.media_type.to_string(),
+                data,
+            },
+        });
+    }
+    Ok(ClaudeUserContent::Blocks(blocks))
+}
+
 use derivative::Derivative;
 
 #[derive(Derivative, Clone, Serialize, Deserialize, TS, JsonSchema)]
@@ -86,7 +117,10 @@ pub struct ClaudeCode {
 }
 
 impl ClaudeCode {
-    async fn build_command_builder(&self) -> Result<CommandBuilder, CommandBuildError> {
+    async fn build_command_builder(
+        &self,
+        interactive: bool,
+    ) -> Result<CommandBuilder, CommandBuildError> {
         // If base_command_override is provided and claude_code_router is also set, log a warning
         if self.cmd.base_command_override.is_some() && self.claude_code_router.is_some() {
             tracing::warn!(
@@ -103,7 +137,8 @@ impl ClaudeCode {
         if plan && approvals {
             tracing::warn!("Both plan and approvals are enabled. Plan will take precedence.");
         }
-        if plan || approvals {
+        // Live sessions can switch modes at any time, so they need the same setup
+        if plan || approvals || interactive {
             // Enable bypass at startup, otherwise we cannot change to it after exiting plan mode
             builder = builder.extend_params(["--permission-prompt-tool=stdio"]);
             builder = builder.extend_params([format!(
@@ -188,13 +223,20 @@ impl StandardCodingAgentExecutor for ClaudeCode {
         self.approvals_service = Some(approvals);
     }
 
+    fn require_approvals(&mut self) -> bool {
+        self.approvals = Some(true);
+        self.dangerously_skip_permissions = Some(false);
+        // Plan mode only hooks ExitPlanMode; the approved plan then runs without asking
+        !self.plan.unwrap_or(false)
+    }
+
     async fn spawn(
         &self,
         current_dir: &Path,
         prompt: &str,
         env: &ExecutionEnv,
     ) -> Result<SpawnedChild, ExecutorError> {
-        let command_builder = self.build_command_builder().await?;
+        let command_builder = self.build_command_builder(env.interactive).await?;
         let command_parts = command_builder.build_initial()?;
         self.spawn_internal(current_dir, prompt, command_parts, env)
             .await
@@ -208,7 +250,7 @@ impl StandardCodingAgentExecutor for ClaudeCode {
         reset_to_message_id: Option<&str>,
         env: &ExecutionEnv,
     ) -> Result<SpawnedChild, ExecutorError> {
-        let command_builder = self.build_command_builder().await?;
+        let command_builder = self.build_command_builder(env.interactive).await?;
 
         let mut args = vec!["--resume".to_string(), session_id.to_string()];
 
@@ -244,6 +286,14 @@ impl StandardCodingAgentExecutor for ClaudeCode {
         dirs::home_dir().map(|home| home.join(".claude.json"))
     }
 
+    async fn discover_models(&self, _current_dir: &Path) -> Result<ModelCatalog, ExecutorError> {
+        // The router maps models to its own providers
+        if self.claude_code_router.unwrap_or(false) {
+            return Err(ExecutorError::ModelListingNotSupported);
+        }
+        known_models(BaseCodingAgent::ClaudeCode).ok_or(ExecutorError::ModelListingNotSupported)
+    }
+
     fn get_availability_info(&self) -> AvailabilityInfo {
         // Auth file indicates an interactive login happened before.
         let auth_file_path = dirs::home_dir().map(|home| home.join(".claude.json"));
@@ -307,6 +357,7 @@ impl ClaudeCode {
     ) -> Result<SpawnedChild, ExecutorError> {
         let (program_path, args) = command_parts.into_resolved().await?;
         let combined_prompt = self.append_prompt.combine_prompt(prompt);
+        let user_content = build_user_content(&combined_prompt, &env.images)?;
 
         let mut command = Command::new(program_path);
         command
@@ -344,12 +395,16 @@ impl ClaudeCode {
         // Create cancellation token for graceful shutdown
         let cancel = CancellationToken::new();
 
+        // Interrupts and (for live sessions) further turns are forwarded over stdin
+        let (live_input, mut live_rx) = live_input_channel();
+        let interactive = env.interactive;
+
         // Spawn task to handle the SDK client with control protocol
-        let prompt_clone = combined_prompt.clone();
         let approvals_clone = self.approvals_service.clone();
         let repo_context = env.repo_context.clone();
         let commit_reminder_prompt = env.commit_reminder_prompt.clone();
         let cancel_for_task = cancel.clone();
+        let cancel_for_live = cancel.clone();
         tokio::spawn(async move {
             let log_writer = LogWriter::new(new_stdout);
             let client = ClaudeAgentClient::new(
@@ -359,8 +414,13 @@ impl ClaudeCode {
                 commit_reminder_prompt,
                 cancel_for_task.clone(),
             );
-            let protocol_peer =
-                ProtocolPeer::spawn(child_stdin, child_stdout, client.clone(), cancel_for_task);
+            let protocol_peer = ProtocolPeer::spawn(
+                child_stdin,
+                child_stdout,
+                client.clone(),
+                cancel_for_task,
+                interactive,
+            );
 
             // Wait for Claude's init message before proceeding
             // Using a shorter 5s timeout and logging instead of failing to avoid blocking
@@ -385,18 +445,56 @@ impl ClaudeCode {
 
             // Send user message
             tokio::time::sleep(std::time::Duration::from_millis(500)).await;
-            if let Err(e) = protocol_peer.send_user_message(prompt_clone).await {
+            if let Err(e) = protocol_peer.send_user_message(user_content).await {
                 tracing::error!("Failed to send prompt: {e}");
                 let _ = log_writer
                     .log_raw(&format!("Error: Failed to send prompt - {e}"))
                     .await;
             }
+
+            loop {
+                tokio::select! {
+                    _ = cancel_for_live.cancelled() => break,
+                    _ = protocol_peer.closed() => break,
+                    input = live_rx.recv() => match input {
+                        Some(LiveInput::UserMessage(message)) => {
+                            if let Err(e) = protocol_peer.send_user_message(message).await {
+                                tracing::error!("Failed to send live message: {e}");
+                                break;
+                            }
+                        }
+                        Some(LiveInput::Interrupt { instruction }) => {
+                            let _ = log_writer
+                                .log_raw(&SteerMarker::new(instruction.clone()).raw())
+                                .await;
+                            if let Err(e) = protocol_peer.steer(instruction).await {
+                                tracing::error!("Failed to interrupt Claude: {e}");
+                            }
+                        }
+                        Some(LiveInput::SetMode(mode)) => {
+                            let marker =
+                                match protocol_peer.set_permission_mode(mode.into()).await {
+                                    Ok(()) => ModeMarker::new(mode),
+                                    Err(e) => ModeMarker::failed(mode, e.to_string()),
+                                };
+                            let _ = log_writer.log_raw(&marker.raw()).await;
+                        }
+                        None => break,
+                    },
+                }
+            }
+            // Claude finishes the running turn and exits once its input is closed.
+            // One-shot runs close it when the reader stops after the result.
+            if interactive {
+                protocol_peer.close_input().await;
+            }
         });
 
         Ok(SpawnedChild {
             child,
             exit_signal: None,
             cancel: Some(cancel),
+            live_input: Some(live_input),
         })
     }
 }
@@ -498,6 +596,16 @@ impl ClaudeLogProcessor {
                         continue;
                     }
 
+                    if let Some(entries) = control_marker_entries(trimmed) {
+                        for entry in entries {
+                            msg_store.push_patch(ConversationPatch::add_normalized_entry(
+                                entry_index_provider.next(),
+                                entry,
+                            ));
+                        }
+                        continue;
+                    }
+
                     match serde_json::from_str::<ClaudeJson>(trimmed) {
                         Ok(claude_json) => {
                             if !session_id_extracted
@@ -1041,7 +1149,10 @@ impl ClaudeLogProcessor {
                     let cur = entry_index_provider.current();
                     if cur > 0 {
                         for _ in 0..cur {
-                            patches.push(ConversationPatch::remove_diff(0.to_string()));
+                            patches.push(ConversationPatch::remove_diff(
+                                DiffSource::Entry,
+                                0.to_string(),
+                            ));
                         }
                         entry_index_provider.reset();
                         self.tool_map.clear();
@@ -2722,4 +2833,26 @@ mod tests {
         let parsed: ClaudeJson = serde_json::from_str(control_request_json).unwrap();
         assert!(matches!(parsed, ClaudeJson::ControlRequest { .. }));
     }
+
+    #[test]
+    fn require_approvals_routes_tool_calls_despite_skip_permissions() {
+        let mut executor: ClaudeCode =
+            serde_json::from_value(serde_json::json!({"dangerously_skip_permissions": true}))
+                .unwrap();
+        assert!(executor.require_approvals());
+        assert_eq!(executor.dangerously_skip_permissions, Some(false));
+        assert!(matches!(
+            executor.permission_mode(),
+            PermissionMode::Default
+        ));
+        let hooks = executor.get_hooks(false).unwrap();
+        assert_eq!(
+            hooks["PreToolUse"][0]["hookCallbackIds"][0],
+            "tool_approval"
+        );
+
+        let mut planner: ClaudeCode =
+            serde_json::from_value(serde_json::json!({"plan": true})).unwrap();
+        assert!(!planner.require_approvals());
+    }
 }
@@ -14,7 +14,10 @@ use crate::{
         ExecutorError,
         claude::{
             client::ClaudeAgentClient,
-            types::{Message, PermissionMode, SDKControlRequest, SDKControlRequestType},
+            types::{
+                ClaudeUserContent, Message, PermissionMode, SDKControlRequest,
+                SDKControlRequestType,
+            },
         },
     },
 };
@@ -22,7 +25,14 @@ use crate::{
 /// Handles bidirectional control protocol communication
 #[derive(Clone)]
 pub struct ProtocolPeer {
-    stdin: Arc<Mutex<ChildStdin>>,
+    /// `None` once input has been closed
+    stdin: Arc<Mutex<Option<ChildStdin>>>,
+    /// Live sessions keep reading after a result; Claude exits once stdin is closed
+    interactive: bool,
+    /// Tracks the running turn, so one-shot runs keep reading past turns a steer replaces
+    turns: Arc<std::sync::Mutex<TurnState>>,
+    /// Cancelled once the reader loop has finished
+    closed: CancellationToken,
 }
 
 impl ProtocolPeer {
@@ -31,9 +41,13 @@ impl ProtocolPeer {
         stdout: ChildStdout,
         client: Arc<ClaudeAgentClient>,
         cancel: CancellationToken,
+        interactive: bool,
     ) -> Self {
         let peer = Self {
-            stdin: Arc::new(Mutex::new(stdin)),
+            stdin: Arc::new(Mutex::new(Some(stdin))),
+            interactive,
+            turns: Default::default(),
+            closed: CancellationToken::new(),
         };
 
         let reader_peer = peer.clone();
@@ -41,6 +55,7 @@ impl ProtocolPeer {
             if let Err(e) = reader_peer.read_loop(stdout, client, cancel).await {
                 tracing::error!("Protocol reader loop error: {}", e);
             }
+            reader_peer.closed.cancel();
         });
 
         peer
@@ -88,7 +103,7 @@ impl ProtocolPeer {
                                     self.handle_control_request(&client, request_id, request)
                                         .await;
                                 }
-                                Ok(CLIMessage::Result(_)) => {
+                                Ok(CLIMessage::Result(_)) if !self.keep_reading() => {
                                     break;
                                 }
                                 _ => {}
@@ -189,15 +204,52 @@ impl ProtocolPeer {
 
     async fn send_json<T: serde::Serialize>(&self, message: &T) -> Result<(), ExecutorError> {
         let json = serde_json::to_string(message)?;
-        let mut stdin = self.stdin.lock().await;
+        let mut guard = self.stdin.lock().await;
+        let stdin = guard.as_mut().ok_or_else(|| {
+            ExecutorError::Io(std::io::Error::new(
+                std::io::ErrorKind::BrokenPipe,
+                "Claude Code input already closed",
+            ))
+        })?;
         stdin.write_all(json.as_bytes()).await?;
         stdin.write_all(b"\n").await?;
         stdin.flush().await?;
         Ok(())
     }
 
-    pub async fn send_user_message(&self, content: String) -> Result<(), ExecutorError> {
+    /// Whether to keep reading after a result
+    fn keep_reading(&self) -> bool {
+        let replaced = self.turns.lock().unwrap().on_result();
+        self.interactive || replaced
+    }
+
+    /// Resolves once the reader loop has finished
+    pub async fn closed(&self) {
+        self.closed.cancelled().await
+    }
+
+    /// Interrupt the running turn and, with an instruction, continue with it as the next turn
+    pub async fn steer(&self, instruction: Option<String>) -> Result<(), ExecutorError> {
+        let Some(instruction) = instruction else {
+            return self.interrupt().await;
+        };
+        // The interrupted turn still ends with a result; keep reading past it
+        self.turns.lock().unwrap().on_steer();
+        self.interrupt().await?;
+        self.send_user_message(instruction).await
+    }
+
+    /// Close Claude's stdin so it exits after finishing the current turn
+    pub async fn close_input(&self) {
+        self.stdin.lock().await.take();
+    }
+
+    pub async fn send_user_message(
+        &self,
+        content: impl Into<ClaudeUserContent>,
+    ) -> Result<(), ExecutorError> {
         let message = Message::new_user(content);
+        self.turns.lock().unwrap().on_message();
         self.send_json(&message).await
     }
 
@@ -219,3 +271,62 @@ impl ProtocolPeer {
         .await
     }
 }
+
+/// Turn bookkeeping for steering: a steer interrupts the running turn, which still ends with
+/// a result that must not end a one-shot run. A turn that already emitted its result has
+/// nothing left to interrupt, so steering it must not skip the next (real) result.
+#[derive(Debug, Default)]
+struct TurnState {
+    in_flight: bool,
+    skip_results: usize,
+}
+
+impl TurnState {
+    fn on_message(&mut self) {
+        self.in_flight = true;
+    }
+
+    fn on_steer(&mut self) {
+        if self.in_flight {
+            self.skip_results += 1;
+        }
+    }
+
+    /// Returns whether the result ended a turn replaced by a steer
+    fn on_result(&mut self) -> bool {
+        if self.skip_results > 0 {
+            self.skip_results -= 1;
+            return true;
+        }
+        self.in_flight = false;
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn steer_skips_the_interrupted_result() {
+        let mut turns = TurnState::default();
+        turns.on_message();
+        turns.on_steer();
+        turns.on_message();
+        // The interrupted turn's result, then the steered turn's
+        assert!(turns.on_result());
+        assert!(!turns.on_result());
+    }
+
+    #[test]
+    fn steer_after_result_skips_nothing() {
+        let mut turns = TurnState::default();
+        turns.on_message();
+        assert!(!turns.on_result());
+
+        // Nothing is running; the steer only starts a new turn whose result ends the run
+        turns.on_steer();
+        turns.on_message();
+        assert!(!turns.on_result());
+    }
+}
@@ -5,6 +5,8 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
+use crate::executors::SessionMode;
+
 /// Top-level message types from CLI stdout
 #[derive(Debug, Deserialize)]
 #[serde(tag = "type", rename_all = "snake_case")]
@@ -174,20 +176,47 @@ pub enum Message {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ClaudeUserMessage {
     role: String,
-    content: String,
+    content: ClaudeUserContent,
+}
+
+/// User message content: plain text, or content blocks (text + images)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum ClaudeUserContent {
+    Text(String),
+    Blocks(Vec<ClaudeUserContentBlock>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClaudeUserContentBlock {
+    Text { text: String },
+    Image { source: ClaudeImageSource },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClaudeImageSource {
+    Base64 { media_type: String, data: String },
 }
 
 impl Message {
-    pub fn new_user(content: String) -> Self {
+    pub fn new_user(content: impl Into<ClaudeUserContent>) -> Self {
         Self::User {
             message: ClaudeUserMessage {
                 role: "user".to_string(),
-                content,
+                content: content.into(),
             },
         }
     }
 }
 
+impl From<String> for ClaudeUserContent {
+    fn from(text: String) -> Self {
+        Self::Text(text)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "subtype", rename_all = "snake_case")]
 pub enum SDKControlRequestType {
@@ -221,6 +250,17 @@ impl PermissionMode {
     }
 }
 
+impl From<SessionMode> for PermissionMode {
+    fn from(mode: SessionMode) -> Self {
+        match mode {
+            SessionMode::Plan => Self::Plan,
+            SessionMode::Default => Self::Default,
+            SessionMode::AcceptEdits => Self::AcceptEdits,
+            SessionMode::Bypass => Self::BypassPermissions,
+        }
+    }
+}
+
 impl std::fmt::Display for PermissionMode {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.as_str())
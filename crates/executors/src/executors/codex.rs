@@ -1,5 +1,6 @@
 pub mod client;
 pub mod jsonrpc;
+pub mod models;
 pub mod normalize_logs;
 pub mod review;
 pub mod session;
@@ -9,6 +10,7 @@ use std::{
     env,
     path::{Path, PathBuf},
     sync::Arc,
+    time::Duration,
 };
 
 /// Returns the Codex home directory.
@@ -46,14 +48,18 @@ use self::{
     session::SessionHandler,
 };
 use crate::{
+    actions::{ExecutorAction, ExecutorActionType, script::ScriptRequest},
     approvals::ExecutorApprovalService,
     command::{CmdOverrides, CommandBuildError, CommandBuilder, CommandParts, apply_overrides},
-    env::ExecutionEnv,
+    doctor::AuthStatus,
+    env::{ExecutionEnv, RepoContext},
     executors::{
-        AppendPrompt, AvailabilityInfo, ExecutorError, ExecutorExitResult, SlashCommandDescription,
-        SpawnedChild, StandardCodingAgentExecutor,
+        AppendPrompt, AvailabilityInfo, ExecutorError, ExecutorExitResult, LiveInput,
+        LiveInputReceiver, SlashCommandDescription, SpawnedChild, StandardCodingAgentExecutor,
+        live_input_channel,
     },
-    logs::utils::patch,
+    logs::{ModeMarker, SteerMarker, utils::patch},
+    models::{ModelCatalog, ModelInfo, ModelSource, known_context_window},
     stdout_dup::create_stdout_pipe_writer,
 };
 
@@ -119,8 +125,13 @@ pub enum ReasoningSummaryFormat {
 }
 
 enum CodexSessionAction {
-    Chat { prompt: String },
-    Review { target: ReviewTarget },
+    Chat {
+        prompt: String,
+        images: Vec<PathBuf>,
+    },
+    Review {
+        target: ReviewTarget,
+    },
 }
 
 #[derive(Derivative, Clone, Serialize, Deserialize, TS, JsonSchema)]
@@ -169,6 +180,12 @@ impl StandardCodingAgentExecutor for Codex {
         self.approvals = Some(approvals);
     }
 
+    fn require_approvals(&mut self) -> bool {
+        // Codex then asks before every patch and every command it does not know to be safe
+        self.ask_for_approval = Some(AskForApproval::UnlessTrusted);
+        true
+    }
+
     async fn available_slash_commands(
         &self,
         _workdir: &Path,
@@ -230,6 +247,71 @@ impl StandardCodingAgentExecutor for Codex {
         codex_home().map(|home| home.join("config.toml"))
     }
 
+    async fn discover_models(&self, current_dir: &Path) -> Result<ModelCatalog, ExecutorError> {
+        let (config_model, config_provider) = models::configured_model(self.profile.as_deref());
+        let provider = self.model_provider.clone().or(config_provider);
+
+        // Local (`--oss`) and custom providers serve their own models; the app-server lists
+        // OpenAI's, so only the configured model is known
+        if self.oss.unwrap_or(false) || provider.is_some_and(|provider| provider != "openai") {
+            let models = self
+                .model
+                .clone()
+                .or(config_model)
+                .map(|id| ModelInfo {
+                    context_window: known_context_window(&id),
+                    id,
+                    display_name: None,
+                    is_default: true,
+                })
+                .into_iter()
+                .collect();
+            return Ok(ModelCatalog {
+                source: ModelSource::Known,
+                models,
+            });
+        }
+
+        let mut models = self
+            .query_app_server(current_dir, "list models", |client| async move {
+                models::fetch_models(&client).await
+            })
+            .await?;
+        if let Some(config_model) = config_model {
+            for model in &mut models {
+                model.is_default = model.id == config_model;
+            }
+        }
+        Ok(ModelCatalog::discovered(models))
+    }
+
+    async fn auth_status(&self, current_dir: &Path) -> Result<AuthStatus, ExecutorError> {
+        let status = self
+            .query_app_server(current_dir, "report its auth status", |client| async move {
+                client.get_auth_status().await
+            })
+            .await?;
+        Ok(match status.auth_method {
+            Some(method) => AuthStatus::LoggedIn {
+                method: Some(format!("{method:?}")),
+            },
+            None if status.requires_openai_auth == Some(false) => AuthStatus::NotRequired,
+            None => AuthStatus::LoggedOut,
+        })
+    }
+
+    async fn get_setup_helper_action(&self) -> Result<ExecutorAction, ExecutorError> {
+        // npx installs the pinned CLI on first use, so logging in is the only step
+        let base = self
+            .cmd
+            .base_command_override
+            .as_deref()
+            .unwrap_or(Self::base_command());
+        let login = ScriptRequest::tool_install(format!("{base} login"));
+        let login = ExecutorAction::new(ExecutorActionType::ScriptRequest(login), None);
+        Ok(login)
+    }
+
     fn get_availability_info(&self) -> AvailabilityInfo {
         if let Some(timestamp) = codex_home()
             .and_then(|home| std::fs::metadata(home.join("auth.json")).ok())
@@ -292,6 +374,52 @@ impl Codex {
         apply_overrides(builder, &self.cmd)
     }
 
+    /// Ask a short-lived app server for its model list
+    /// Start the app server, run `query` against it and stop it again
+    async fn query_app_server<T, F, Fut>(
+        &self,
+        current_dir: &Path,
+        what: &str,
+        query: F,
+    ) -> Result<T, ExecutorError>
+    where
+        T: Send + 'static,
+        F: FnOnce(Arc<AppServerClient>) -> Fut + Send + 'static,
+        Fut: std::future::Future<Output = Result<T, ExecutorError>> + Send + 'static,
+    {
+        let command_parts = self.build_command_builder()?.build_initial()?;
+        let env = ExecutionEnv::new(RepoContext::default(), false, String::new());
+        let (result_tx, result_rx) = tokio::sync::oneshot::channel();
+
+        let mut spawned = self
+            .spawn_app_server(
+                current_dir,
+                command_parts,
+                &env,
+                move |client, exit_signal_tx| async move {
+                    let _ = result_tx.send(query(client).await);
+                    exit_signal_tx
+                        .send_exit_signal(ExecutorExitResult::Success)
+                        .await;
+                    Ok(())
+                },
+            )
+            .await?;
+
+        let result = tokio::time::timeout(Duration::from_secs(60), result_rx).await;
+        let _ = workspace_utils::process::kill_process_group(&mut spawned.child).await;
+        match result {
+            Ok(Ok(result)) => result,
+            Ok(Err(_)) => Err(ExecutorError::Io(std::io::Error::other(format!(
+                "Codex app server exited before it could {what}"
+            )))),
+            Err(_) => Err(ExecutorError::Io(std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                format!("Timed out waiting for the Codex app server to {what}"),
+            ))),
+        }
+    }
+
     fn build_new_conversation_params(&self, cwd: &Path) -> NewConversationParams {
         let sandbox = match self.sandbox.as_ref() {
             None | Some(SandboxMode::Auto) => Some(CodexSandboxMode::WorkspaceWrite), // match the Auto preset in codex
@@ -377,8 +505,9 @@ impl Codex {
             env,
             move |client, _| async move {
                 match action {
-                    CodexSessionAction::Chat { prompt } => {
-                        Self::launch_codex_agent(params, resume_session, prompt, client).await
+                    CodexSessionAction::Chat { prompt, images } => {
+                        Self::launch_codex_agent(params, resume_session, prompt, images, client)
+                            .await
                     }
                     CodexSessionAction::Review { target } => {
                         review::launch_codex_review(params, resume_session, target, client).await
@@ -393,6 +522,7 @@ impl Codex {
         conversation_params: NewConversationParams,
         resume_session: Option<String>,
         combined_prompt: String,
+        images: Vec<PathBuf>,
         client: Arc<AppServerClient>,
     ) -> Result<(), ExecutorError> {
         let auth_status = client.get_auth_status().await?;
@@ -409,7 +539,7 @@ impl Codex {
                 client.register_session(&conversation_id).await?;
                 client.add_conversation_listener(conversation_id).await?;
                 client
-                    .send_user_message(conversation_id, combined_prompt)
+                    .send_user_message(conversation_id, combined_prompt, images)
                     .await?;
             }
             Some(session_id) => {
@@ -429,7 +559,7 @@ impl Codex {
                 client.register_session(&conversation_id).await?;
                 client.add_conversation_listener(conversation_id).await?;
                 client
-                    .send_user_message(conversation_id, combined_prompt)
+                    .send_user_message(conversation_id, combined_prompt, images)
                     .await?;
             }
         }
@@ -491,6 +621,11 @@ impl Codex {
         let commit_reminder = env.commit_reminder;
         let commit_reminder_prompt = env.commit_reminder_prompt.clone();
         let cancel_for_task = cancel.clone();
+        let cancel_for_live = cancel.clone();
+
+        // Interrupts and (for live sessions) further turns go to the running conversation
+        let (live_input, live_rx) = live_input_channel();
+        let interactive = env.interactive;
 
         tokio::spawn(async move {
             let exit_signal_tx = ExitSignalSender::new(exit_signal_tx);
@@ -517,7 +652,19 @@ impl Codex {
 
             let result = async {
                 client.initialize().await?;
-                task(client, exit_signal_tx.clone()).await
+                if interactive {
+                    client.keep_alive();
+                }
+                task(client.clone(), exit_signal_tx.clone()).await?;
+                forward_live_input(
+                    &client,
+                    live_rx,
+                    interactive,
+                    &exit_signal_tx,
+                    cancel_for_live,
+                )
+                .await;
+                Ok(())
             }
             .await;
 
@@ -557,6 +704,52 @@ impl Codex {
             child,
             exit_signal: Some(exit_signal_rx),
             cancel: Some(cancel),
+            live_input: Some(live_input),
         })
     }
 }
+
+/// Forward live input to the conversation until the sender is dropped. Live sessions then exit
+/// as soon as no turn is running.
+async fn forward_live_input(
+    client: &AppServerClient,
+    mut live_rx: LiveInputReceiver,
+    interactive: bool,
+    exit_signal_tx: &ExitSignalSender,
+    cancel: tokio_util::sync::CancellationToken,
+) {
+    loop {
+        tokio::select! {
+            _ = cancel.cancelled() => return,
+            input = live_rx.recv() => match input {
+                Some(LiveInput::UserMessage(message)) => {
+                    if let Err(err) = client.send_live_message(message).await {
+                        tracing::error!("Failed to send live message: {err}");
+                    }
+                }
+                Some(LiveInput::Interrupt { instruction }) => {
+                    let _ = client
+                        .log_writer()
+                        .log_raw(&SteerMarker::new(instruction.clone()).raw())
+                        .await;
+                    if let Err(err) = client.interrupt(instruction).await {
+                        tracing::error!("Failed to interrupt Codex: {err}");
+                    }
+                }
+                Some(LiveInput::SetMode(mode)) => {
+                    let marker = match client.set_mode(mode) {
+                        Ok(()) => ModeMarker::new(mode),
+                        Err(err) => ModeMarker::failed(mode, err.to_string()),
+                    };
+                    let _ = client.log_writer().log_raw(&marker.raw()).await;
+                }
+                None => break,
+            },
+        }
+    }
+    if interactive && client.end_live_session() {
+        exit_signal_tx
+            .send_exit_signal(ExecutorExitResult::Success)
+            .await;
+    }
+}
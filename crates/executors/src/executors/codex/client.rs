@@ -2,6 +2,7 @@ use std::{
     borrow::Cow,
     collections::VecDeque,
     io,
+    path::PathBuf,
     sync::{
         Arc, OnceLock,
         atomic::{AtomicBool, Ordering},
@@ -13,13 +14,19 @@ use codex_app_server_protocol::{
     AddConversationListenerParams, AddConversationSubscriptionResponse, ApplyPatchApprovalResponse,
     ClientInfo, ClientNotification, ClientRequest, ExecCommandApprovalResponse,
     GetAuthStatusParams, GetAuthStatusResponse, InitializeParams, InitializeResponse, InputItem,
-    JSONRPCError, JSONRPCNotification, JSONRPCRequest, JSONRPCResponse, ListMcpServerStatusParams,
-    ListMcpServerStatusResponse, NewConversationParams, NewConversationResponse, RequestId,
+    InterruptConversationParams, InterruptConversationResponse, JSONRPCError, JSONRPCNotification,
+    JSONRPCRequest, JSONRPCResponse, ListMcpServerStatusParams, ListMcpServerStatusResponse,
+    ModelListParams, ModelListResponse, NewConversationParams, NewConversationResponse, RequestId,
     ResumeConversationParams, ResumeConversationResponse, ReviewStartParams, ReviewStartResponse,
-    ReviewTarget, SendUserMessageParams, SendUserMessageResponse, ServerNotification,
-    ServerRequest,
+    ReviewTarget, SendUserMessageParams, SendUserMessageResponse, SendUserTurnParams,
+    ServerNotification, ServerRequest,
+};
+use codex_protocol::{
+    ThreadId,
+    config_types::ReasoningSummary,
+    openai_models::ReasoningEffort,
+    protocol::{AskForApproval, ReviewDecision, SandboxPolicy},
 };
-use codex_protocol::{ThreadId, protocol::ReviewDecision};
 use serde::{Serialize, de::DeserializeOwned};
 use serde_json::{self, Value};
 use tokio::{
@@ -33,7 +40,7 @@ use super::jsonrpc::{JsonRpcCallbacks, JsonRpcPeer};
 use crate::{
     approvals::{ExecutorApprovalError, ExecutorApprovalService},
     env::RepoContext,
-    executors::{ExecutorError, codex::normalize_logs::Approval},
+    executors::{ExecutorError, SessionMode, codex::normalize_logs::Approval},
 };
 
 pub struct AppServerClient {
@@ -47,9 +54,25 @@ pub struct AppServerClient {
     commit_reminder: bool,
     commit_reminder_prompt: String,
     commit_reminder_sent: AtomicBool,
+    /// Live sessions stay open after a completed turn until input ends
+    live: AtomicBool,
+    turn_active: AtomicBool,
+    /// Set while a user interrupt is in flight; the instruction (if any) is the next turn
+    interrupt_requested: AtomicBool,
+    pending_steer: Mutex<Option<String>>,
+    /// Mode switched to mid-session; later turns carry its approval and sandbox policies
+    mode: std::sync::Mutex<Option<SessionMode>>,
+    turn_settings: std::sync::Mutex<Option<TurnSettings>>,
     cancel: CancellationToken,
 }
 
+/// Conversation settings that `sendUserTurn` requires alongside the policies
+struct TurnSettings {
+    cwd: PathBuf,
+    model: String,
+    effort: Option<ReasoningEffort>,
+}
+
 impl AppServerClient {
     pub fn new(
         log_writer: LogWriter,
@@ -71,6 +94,12 @@ impl AppServerClient {
             commit_reminder,
             commit_reminder_prompt,
             commit_reminder_sent: AtomicBool::new(false),
+            live: AtomicBool::new(false),
+            turn_active: AtomicBool::new(false),
+            interrupt_requested: AtomicBool::new(false),
+            pending_steer: Mutex::new(None),
+            mode: std::sync::Mutex::new(None),
+            turn_settings: std::sync::Mutex::new(None),
             cancel,
         })
     }
@@ -87,6 +116,27 @@ impl AppServerClient {
         &self.log_writer
     }
 
+    /// Keep the app server running after a turn completes, see [`Self::end_live_session`]
+    pub fn keep_alive(&self) {
+        self.live.store(true, Ordering::SeqCst);
+    }
+
+    /// Stop accepting live input. Returns true if no turn is running, in which case the caller
+    /// should signal exit; otherwise the running turn's completion ends the session.
+    pub fn end_live_session(&self) -> bool {
+        self.live.store(false, Ordering::SeqCst);
+        !self.turn_active.load(Ordering::SeqCst)
+    }
+
+    /// Send a further user turn to the current conversation of a live session
+    pub async fn send_live_message(&self, message: String) -> Result<(), ExecutorError> {
+        let conversation_id = self.conversation_id.lock().await.ok_or_else(|| {
+            ExecutorError::Io(io::Error::other("Codex conversation has not started"))
+        })?;
+        self.spawn_user_message(conversation_id, message);
+        Ok(())
+    }
+
     pub async fn initialize(&self) -> Result<(), ExecutorError> {
         let request = ClientRequest::Initialize {
             request_id: self.next_request_id(),
@@ -109,11 +159,15 @@ impl AppServerClient {
         &self,
         params: NewConversationParams,
     ) -> Result<NewConversationResponse, ExecutorError> {
+        let cwd = params.cwd.clone();
         let request = ClientRequest::NewConversation {
             request_id: self.next_request_id(),
             params,
         };
-        self.send_request(request, "newConversation").await
+        let response: NewConversationResponse =
+            self.send_request(request, "newConversation").await?;
+        self.remember_turn_settings(cwd, &response.model, response.reasoning_effort);
+        Ok(response)
     }
 
     pub async fn resume_conversation(
@@ -121,6 +175,7 @@ impl AppServerClient {
         rollout_path: std::path::PathBuf,
         overrides: NewConversationParams,
     ) -> Result<ResumeConversationResponse, ExecutorError> {
+        let cwd = overrides.cwd.clone();
         let request = ClientRequest::ResumeConversation {
             request_id: self.next_request_id(),
             params: ResumeConversationParams {
@@ -130,7 +185,75 @@ impl AppServerClient {
                 history: None,
             },
         };
-        self.send_request(request, "resumeConversation").await
+        let response: ResumeConversationResponse =
+            self.send_request(request, "resumeConversation").await?;
+        self.remember_turn_settings(cwd, &response.model, None);
+        Ok(response)
+    }
+
+    fn remember_turn_settings(
+        &self,
+        cwd: Option<String>,
+        model: &str,
+        effort: Option<ReasoningEffort>,
+    ) {
+        let cwd = cwd
+            .map(PathBuf::from)
+            .unwrap_or_else(|| self.repo_context.workspace_root.clone());
+        *self.turn_settings.lock().unwrap() = Some(TurnSettings {
+            cwd,
+            model: model.to_string(),
+            effort,
+        });
+    }
+
+    /// Switch the approval and sandbox policies for the following turns. Codex applies them
+    /// per turn, so a running turn finishes under its current policies.
+    pub fn set_mode(&self, mode: SessionMode) -> Result<(), ExecutorError> {
+        if self.turn_settings.lock().unwrap().is_none() {
+            return Err(ExecutorError::Io(io::Error::other(
+                "Codex conversation has not started",
+            )));
+        }
+        *self.mode.lock().unwrap() = Some(mode);
+        Ok(())
+    }
+
+    /// `sendUserMessage`, or `sendUserTurn` with the policies of a switched mode
+    fn user_input_request(
+        &self,
+        request_id: RequestId,
+        conversation_id: ThreadId,
+        items: Vec<InputItem>,
+    ) -> ClientRequest {
+        let mode = *self.mode.lock().unwrap();
+        let settings = self.turn_settings.lock().unwrap();
+        match (mode, settings.as_ref()) {
+            (Some(mode), Some(settings)) => {
+                let (approval_policy, sandbox_policy) = mode_policies(mode);
+                ClientRequest::SendUserTurn {
+                    request_id,
+                    params: SendUserTurnParams {
+                        conversation_id,
+                        items,
+                        cwd: settings.cwd.clone(),
+                        approval_policy,
+                        sandbox_policy,
+                        model: settings.model.clone(),
+                        effort: settings.effort,
+                        summary: ReasoningSummary::Auto,
+                        output_schema: None,
+                    },
+                }
+            }
+            _ => ClientRequest::SendUserMessage {
+                request_id,
+                params: SendUserMessageParams {
+                    conversation_id,
+                    items,
+                },
+            },
+        }
     }
 
     pub async fn add_conversation_listener(
@@ -147,21 +270,60 @@ impl AppServerClient {
         self.send_request(request, "addConversationListener").await
     }
 
+    /// Interrupt the running turn; with an instruction, it is sent once the turn has aborted
+    pub async fn interrupt(&self, instruction: Option<String>) -> Result<(), ExecutorError> {
+        let conversation_id = self.conversation_id.lock().await.ok_or_else(|| {
+            ExecutorError::Io(io::Error::other("Codex conversation has not started"))
+        })?;
+        *self.pending_steer.lock().await = instruction;
+        self.interrupt_requested.store(true, Ordering::SeqCst);
+
+        // The response only arrives once the turn has been aborted
+        let peer = self.rpc().clone();
+        let cancel = self.cancel.clone();
+        let request = ClientRequest::InterruptConversation {
+            request_id: peer.next_request_id(),
+            params: InterruptConversationParams { conversation_id },
+        };
+        tokio::spawn(async move {
+            if let Err(err) = peer
+                .request::<InterruptConversationResponse, _>(
+                    request_id(&request),
+                    &request,
+                    "interruptConversation",
+                    cancel,
+                )
+                .await
+            {
+                tracing::error!("failed to interrupt conversation: {err}");
+            }
+        });
+        Ok(())
+    }
+
     pub async fn send_user_message(
         &self,
         conversation_id: codex_protocol::ThreadId,
         message: String,
+        images: Vec<std::path::PathBuf>,
     ) -> Result<SendUserMessageResponse, ExecutorError> {
+        let mut items = vec![InputItem::Text {
+            text: message,
+            text_elements: vec![],
+        }];
+        items.extend(
+            images
+                .into_iter()
+                .map(|path| InputItem::LocalImage { path }),
+        );
         let request = ClientRequest::SendUserMessage {
             request_id: self.next_request_id(),
             params: SendUserMessageParams {
                 conversation_id,
-                items: vec![InputItem::Text {
-                    text: message,
-                    text_elements: vec![],
-                }],
+                items,
             },
         };
+        self.turn_active.store(true, Ordering::SeqCst);
         self.send_request(request, "sendUserMessage").await
     }
 
@@ -189,6 +351,7 @@ impl AppServerClient {
                 delivery: None,
             },
         };
+        self.turn_active.store(true, Ordering::SeqCst);
         self.send_request(request, "reviewStart").await
     }
 
@@ -206,6 +369,20 @@ impl AppServerClient {
         self.send_request(request, "mcpServerStatus/list").await
     }
 
+    pub async fn list_models(
+        &self,
+        cursor: Option<String>,
+    ) -> Result<ModelListResponse, ExecutorError> {
+        let request = ClientRequest::ModelList {
+            request_id: self.next_request_id(),
+            params: ModelListParams {
+                cursor,
+                ..Default::default()
+            },
+        };
+        self.send_request(request, "model/list").await
+    }
+
     async fn handle_server_request(
         &self,
         peer: &JsonRpcPeer,
@@ -411,26 +588,21 @@ impl AppServerClient {
     }
 
     fn spawn_user_message(&self, conversation_id: ThreadId, message: String) {
+        self.turn_active.store(true, Ordering::SeqCst);
         let peer = self.rpc().clone();
         let cancel = self.cancel.clone();
-        let request = ClientRequest::SendUserMessage {
-            request_id: peer.next_request_id(),
-            params: SendUserMessageParams {
-                conversation_id,
-                items: vec![InputItem::Text {
-                    text: message,
-                    text_elements: vec![],
-                }],
-            },
-        };
+        let request = self.user_input_request(
+            peer.next_request_id(),
+            conversation_id,
+            vec![InputItem::Text {
+                text: message,
+                text_elements: vec![],
+            }],
+        );
         tokio::spawn(async move {
+            // Both send variants answer with an empty object
             if let Err(err) = peer
-                .request::<SendUserMessageResponse, _>(
-                    request_id(&request),
-                    &request,
-                    "sendUserMessage",
-                    cancel,
-                )
+                .request::<Value, _>(request_id(&request), &request, "sendUserMessage", cancel)
                 .await
             {
                 tracing::error!("failed to send user message: {err}");
@@ -508,13 +680,28 @@ impl JsonRpcCallbacks for AppServerClient {
 
         if method.ends_with("turn_aborted") {
             tracing::debug!("codex turn aborted; flushing feedback queue");
+            self.turn_active.store(false, Ordering::SeqCst);
             self.flush_pending_feedback().await;
+            if self.interrupt_requested.swap(false, Ordering::SeqCst) {
+                if let Some(instruction) = self.pending_steer.lock().await.take()
+                    && let Some(conversation_id) = *self.conversation_id.lock().await
+                {
+                    self.spawn_user_message(conversation_id, instruction);
+                }
+                // A plain interrupt ends a one-shot run
+                return Ok(
+                    !self.live.load(Ordering::SeqCst) && !self.turn_active.load(Ordering::SeqCst)
+                );
+            }
             return Ok(false);
         }
 
         let has_finished = method
             .strip_prefix("codex/event/")
             .is_some_and(|suffix| suffix == "task_complete");
+        if has_finished {
+            self.turn_active.store(false, Ordering::SeqCst);
+        }
 
         if has_finished
             && self.commit_reminder
@@ -528,7 +715,8 @@ impl JsonRpcCallbacks for AppServerClient {
             return Ok(false);
         }
 
-        Ok(has_finished)
+        // Live sessions wait for the next message instead of exiting
+        Ok(has_finished && !self.live.load(Ordering::SeqCst))
     }
 
     async fn on_non_json(&self, raw: &str) -> Result<(), ExecutorError> {
@@ -554,6 +742,21 @@ where
     peer.send(&payload).await
 }
 
+fn mode_policies(mode: SessionMode) -> (AskForApproval, SandboxPolicy) {
+    match mode {
+        SessionMode::Plan => (AskForApproval::Never, SandboxPolicy::new_read_only_policy()),
+        SessionMode::Default => (
+            AskForApproval::UnlessTrusted,
+            SandboxPolicy::new_workspace_write_policy(),
+        ),
+        SessionMode::AcceptEdits => (
+            AskForApproval::OnRequest,
+            SandboxPolicy::new_workspace_write_policy(),
+        ),
+        SessionMode::Bypass => (AskForApproval::Never, SandboxPolicy::DangerFullAccess),
+    }
+}
+
 fn request_id(request: &ClientRequest) -> RequestId {
     match request {
         ClientRequest::Initialize { request_id, .. }
@@ -562,8 +765,11 @@ fn request_id(request: &ClientRequest) -> RequestId {
         | ClientRequest::ResumeConversation { request_id, .. }
         | ClientRequest::AddConversationListener { request_id, .. }
         | ClientRequest::SendUserMessage { request_id, .. }
+        | ClientRequest::SendUserTurn { request_id, .. }
+        | ClientRequest::InterruptConversation { request_id, .. }
         | ClientRequest::ReviewStart { request_id, .. }
-        | ClientRequest::McpServerStatusList { request_id, .. } => request_id.clone(),
+        | ClientRequest::McpServerStatusList { request_id, .. }
+        | ClientRequest::ModelList { request_id, .. } => request_id.clone(),
         _ => unreachable!("request_id called for unsupported request variant"),
     }
 }
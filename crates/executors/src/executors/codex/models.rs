@@ -0,0 +1,51 @@
+use std::path::Path;
+
+use super::{client::AppServerClient, codex_home};
+use crate::{executors::ExecutorError, models::ModelInfo};
+
+/// All models the app-server offers, following its pagination
+pub async fn fetch_models(client: &AppServerClient) -> Result<Vec<ModelInfo>, ExecutorError> {
+    let mut cursor = None;
+    let mut models = Vec::new();
+    loop {
+        let response = client.list_models(cursor).await?;
+        models.extend(response.data.into_iter().map(|model| ModelInfo {
+            id: model.model,
+            display_name: Some(model.display_name),
+            context_window: None,
+            is_default: model.is_default,
+        }));
+        cursor = response.next_cursor;
+        if cursor.is_none() {
+            break;
+        }
+    }
+    Ok(models)
+}
+
+/// `model` and `model_provider` from Codex's `config.toml`, read from `[profiles.<profile>]`
+/// when a profile is selected, falling back to the top-level keys
+pub fn configured_model(profile: Option<&str>) -> (Option<String>, Option<String>) {
+    let Some(config) = codex_home()
+        .map(|home| home.join("config.toml"))
+        .and_then(|path| read_config(&path))
+    else {
+        return (None, None);
+    };
+    let scoped = profile.and_then(|name| config.get("profiles")?.get(name));
+    let lookup = |key: &str| {
+        scoped
+            .and_then(|table| table.get(key))
+            .or_else(|| config.get(key))
+            .and_then(toml::Value::as_str)
+            .map(str::to_string)
+    };
+    (lookup("model"), lookup("model_provider"))
+}
+
+fn read_config(path: &Path) -> Option<toml::Value> {
+    let raw = std::fs::read_to_string(path).ok()?;
+    toml::from_str(&raw)
+        .inspect_err(|err| tracing::debug!("Failed to parse {}: {err}", path.display()))
+        .ok()
+}
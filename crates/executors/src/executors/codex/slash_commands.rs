@@ -132,6 +132,7 @@ impl Codex {
         let combined_prompt = self.append_prompt.combine_prompt(prompt);
         let action = super::CodexSessionAction::Chat {
             prompt: combined_prompt,
+            images: env.images.iter().map(|image| image.path.clone()).collect(),
         };
         self.spawn_inner(current_dir, command_parts, action, session_id, env)
             .await
@@ -43,8 +43,12 @@ pub struct Copilot {
 }
 
 impl Copilot {
+    pub fn base_command() -> &'static str {
+        "npx -y @github/copilot@0.0.403"
+    }
+
     fn build_command_builder(&self) -> Result<CommandBuilder, CommandBuildError> {
-        let mut builder = CommandBuilder::new("npx -y @github/copilot@0.0.403");
+        let mut builder = CommandBuilder::new(Self::base_command());
 
         if self.allow_all_tools.unwrap_or(false) {
             builder = builder.extend_params(["--allow-all-tools"]);
@@ -86,6 +90,11 @@ impl StandardCodingAgentExecutor for Copilot {
         self.approvals = Some(approvals);
     }
 
+    fn require_approvals(&mut self) -> bool {
+        self.allow_all_tools = Some(false);
+        true
+    }
+
     async fn spawn(
         &self,
         current_dir: &Path,
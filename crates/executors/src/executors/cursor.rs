@@ -16,10 +16,12 @@ use workspace_utils::{
 };
 
 use crate::{
+    actions::{ExecutorAction, ExecutorActionType, script::ScriptRequest},
     command::{CmdOverrides, CommandBuildError, CommandBuilder, apply_overrides},
     env::ExecutionEnv,
     executors::{
-        AppendPrompt, AvailabilityInfo, ExecutorError, SpawnedChild, StandardCodingAgentExecutor,
+        AppendPrompt, AvailabilityInfo, BaseCodingAgent, ExecutorError, SpawnedChild,
+        StandardCodingAgentExecutor,
     },
     logs::{
         ActionType, FileChange, NormalizedEntry, NormalizedEntryError, NormalizedEntryType,
@@ -27,9 +29,13 @@ use crate::{
         plain_text_processor::PlainTextLogProcessor,
         utils::{ConversationPatch, EntryIndexProvider},
     },
+    models::{ModelCatalog, ModelInfo, known_models},
 };
 
 mod mcp;
+
+/// Cursor's installer puts `cursor-agent` in `~/.local/bin`
+const CURSOR_INSTALL_SCRIPT: &str = "curl https://cursor.com/install -fsS | bash";
 const CURSOR_AUTH_REQUIRED_MSG: &str = "Authentication required. Please run 'cursor-agent login' first, or set CURSOR_API_KEY environment variable.";
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, TS, JsonSchema)]
@@ -67,6 +73,68 @@ impl CursorAgent {
 
         apply_overrides(builder, &self.cmd)
     }
+
+    /// Models reported by `cursor-agent models`
+    async fn query_models(&self, current_dir: &Path) -> Result<Vec<ModelInfo>, ExecutorError> {
+        let mut builder = CommandBuilder::new(Self::base_command()).params(["models"]);
+        if let Some(base) = &self.cmd.base_command_override {
+            builder = builder.override_base(base.clone());
+        }
+        let (program_path, args) = builder.build_initial()?.into_resolved().await?;
+
+        let mut command = Command::new(program_path);
+        command
+            .kill_on_drop(true)
+            .stdin(Stdio::null())
+            .current_dir(current_dir)
+            .env("NO_COLOR", "1")
+            .args(args);
+        if let Some(env) = &self.cmd.env {
+            command.envs(env);
+        }
+
+        let output = tokio::time::timeout(Duration::from_secs(20), command.output())
+            .await
+            .map_err(|_| {
+                ExecutorError::Io(std::io::Error::new(
+                    std::io::ErrorKind::TimedOut,
+                    "cursor-agent models timed out",
+                ))
+            })??;
+        if !output.status.success() {
+            return Err(ExecutorError::Io(std::io::Error::other(format!(
+                "cursor-agent models failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ))));
+        }
+        Ok(parse_models_output(&String::from_utf8_lossy(
+            &output.stdout,
+        )))
+    }
+}
+
+/// Parse `cursor-agent models` output: one `<id> - <name>` line per model, the name optionally
+/// followed by notes such as `(current, default)`
+fn parse_models_output(output: &str) -> Vec<ModelInfo> {
+    output
+        .lines()
+        .filter_map(|line| {
+            let (id, name) = line.trim().split_once(" - ")?;
+            if id.is_empty() || id.contains(char::is_whitespace) {
+                return None;
+            }
+            let (name, notes) = match name.rsplit_once(" (") {
+                Some((name, notes)) if notes.ends_with(')') => (name, notes),
+                _ => (name, ""),
+            };
+            Some(ModelInfo {
+                id: id.to_string(),
+                display_name: Some(name.trim().to_string()),
+                context_window: None,
+                is_default: notes.contains("default") || notes.contains("current"),
+            })
+        })
+        .collect()
 }
 
 #[async_trait]
@@ -493,6 +561,40 @@ impl StandardCodingAgentExecutor for CursorAgent {
         dirs::home_dir().map(|home| home.join(".cursor").join("mcp.json"))
     }
 
+    async fn discover_models(&self, current_dir: &Path) -> Result<ModelCatalog, ExecutorError> {
+        match self.query_models(current_dir).await {
+            Ok(models) if !models.is_empty() => Ok(ModelCatalog::discovered(models)),
+            result => {
+                if let Err(err) = result {
+                    tracing::debug!("Falling back to known Cursor models: {err}");
+                }
+                known_models(BaseCodingAgent::CursorAgent)
+                    .ok_or(ExecutorError::ModelListingNotSupported)
+            }
+        }
+    }
+
+    async fn get_setup_helper_action(&self) -> Result<ExecutorAction, ExecutorError> {
+        let base = self
+            .cmd
+            .base_command_override
+            .as_deref()
+            .unwrap_or(Self::base_command());
+        let login = ScriptRequest::tool_install(format!(
+            "export PATH=\"$HOME/.local/bin:$PATH\"\n{base} login"
+        ));
+        let login = ExecutorAction::new(ExecutorActionType::ScriptRequest(login), None);
+
+        let installed = self.cmd.base_command_override.is_some()
+            || resolve_executable_path_blocking(Self::base_command()).is_some();
+        if installed {
+            return Ok(login);
+        }
+        let install = ScriptRequest::tool_install(CURSOR_INSTALL_SCRIPT);
+        let install = ExecutorAction::new(ExecutorActionType::ScriptRequest(install), None);
+        Ok(install.append_action(login))
+    }
+
     fn get_availability_info(&self) -> AvailabilityInfo {
         let binary_found = resolve_executable_path_blocking(Self::base_command()).is_some();
         if !binary_found {
@@ -1297,4 +1399,15 @@ mod tests {
             _ => panic!("Expected Unknown variant"),
         }
     }
+
+    #[test]
+    fn test_parse_models_output() {
+        let output = "Available models\n\nauto - Auto\ncomposer-1 - Composer 1 (current, default)\n\
+                      sonnet-4.5 - Claude 4.5 Sonnet\n\nTip: use --model <id> to switch\n";
+        let models = parse_models_output(output);
+        let ids: Vec<_> = models.iter().map(|model| model.id.as_str()).collect();
+        assert_eq!(ids, ["auto", "composer-1", "sonnet-4.5"]);
+        assert_eq!(models[1].display_name.as_deref(), Some("Composer 1"));
+        assert!(models[1].is_default && !models[0].is_default);
+    }
 }
@@ -0,0 +1,735 @@
+//! Generic executor for in-house or niche CLI agents that print plain text or ad-hoc JSON lines,
+//! configured entirely in a profile: how the prompt is passed, how follow-ups resume a session,
+//! where the session id is printed, and which output lines become which normalized entries.
+//!
+//! ```json
+//! { "executors": { "CUSTOM": { "DEFAULT": { "CUSTOM": {
+//!     "command": "my-agent run",
+//!     "prompt_input": "stdin",
+//!     "follow_up_args": ["--resume", "{session_id}"],
+//!     "session_id_regex": "^session: (\\S+)",
+//!     "output_rules": [
+//!         { "pattern": "^\\$ (?P<command>.+)", "entry": "command_run" },
+//!         { "pattern": "^> (?P<content>.*)", "entry": "thinking" }
+//!     ]
+//! } } } } }
+//! ```
+
+use std::{
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use command_group::AsyncCommandGroup;
+use futures::StreamExt;
+use regex::{Captures, Regex};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::{io::AsyncWriteExt, process::Command};
+use ts_rs::TS;
+use workspace_utils::msg_store::MsgStore;
+
+use crate::{
+    command::{CmdOverrides, CommandBuilder, apply_overrides},
+    env::ExecutionEnv,
+    executors::{
+        AppendPrompt, AvailabilityInfo, BaseAgentCapability, ExecutorError, SpawnedChild,
+        StandardCodingAgentExecutor,
+    },
+    logs::{
+        ActionType, NormalizedEntry, NormalizedEntryError, NormalizedEntryType, ToolStatus,
+        plain_text_processor::{MessageBoundary, PlainTextLogProcessor},
+        stderr_processor::normalize_stderr_logs,
+        utils::EntryIndexProvider,
+    },
+    stdout_dup::duplicate_stdout,
+};
+
+pub const PROMPT_PLACEHOLDER: &str = "{prompt}";
+pub const PROMPT_FILE_PLACEHOLDER: &str = "{prompt_file}";
+pub const SESSION_ID_PLACEHOLDER: &str = "{session_id}";
+
+/// How the prompt reaches the agent
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, TS, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PromptInput {
+    /// In place of a `{prompt}` argument, or appended as the last argument
+    #[default]
+    Arg,
+    /// Written to stdin, which is then closed
+    Stdin,
+    /// Written to a private temporary file whose path replaces a `{prompt_file}` argument, or is
+    /// appended as the last argument; the file is deleted when the agent exits
+    File,
+}
+
+/// What a matching output line becomes
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq, TS, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputEntryKind {
+    #[default]
+    AssistantMessage,
+    Thinking,
+    SystemMessage,
+    ErrorMessage,
+    /// A generic tool call, named by the `tool` group or the rule's `tool_name`
+    ToolUse,
+    /// A command run, taken from the `command` group
+    CommandRun,
+    /// A file read, taken from the `path` group
+    FileRead,
+    /// A file edit, taken from the `path` group
+    FileEdit,
+    /// Dropped from the normalized log
+    Ignore,
+}
+
+impl OutputEntryKind {
+    /// Tool calls are one entry per line; other kinds group consecutive lines
+    fn is_single_line(self) -> bool {
+        matches!(
+            self,
+            Self::ToolUse | Self::CommandRun | Self::FileRead | Self::FileEdit
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, TS, JsonSchema)]
+pub struct OutputRule {
+    #[schemars(
+        title = "Pattern",
+        description = "Regex matched against each stdout line; named groups `content`, `tool`, \
+                       `command` and `path` pick out the parts of the line to keep"
+    )]
+    pub pattern: String,
+    #[schemars(title = "Entry", description = "What a matching line becomes")]
+    pub entry: OutputEntryKind,
+    #[schemars(
+        title = "Tool Name",
+        description = "Tool name shown for tool entries without a `tool` group"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_name: Option<String>,
+}
+
+#[derive(Debug, Error)]
+pub enum CustomConfigError {
+    #[error("invalid {field} regex `{pattern}`: {error}")]
+    Regex {
+        field: &'static str,
+        pattern: String,
+        error: regex::Error,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, TS, JsonSchema)]
+pub struct Custom {
+    #[serde(default)]
+    pub append_prompt: AppendPrompt,
+    #[schemars(
+        title = "Command",
+        description = "Command that runs the agent non-interactively (e.g. \"my-agent run\")"
+    )]
+    pub command: String,
+    #[schemars(
+        title = "Arguments",
+        description = "Arguments appended to the command; may contain {prompt} or {prompt_file}"
+    )]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    #[schemars(
+        title = "Prompt Input",
+        description = "How the prompt is passed: arg (default), stdin or file"
+    )]
+    #[serde(default)]
+    pub prompt_input: PromptInput,
+    #[schemars(
+        title = "Follow-up Arguments",
+        description = "Arguments added for follow-ups, with {session_id} replaced by the \
+                       session to resume; follow-ups are not supported without them"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub follow_up_args: Option<Vec<String>>,
+    #[schemars(
+        title = "Session ID Regex",
+        description = "Regex finding the session id in stdout: its `session_id` group, else \
+                       its first group, else the whole match"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id_regex: Option<String>,
+    #[schemars(
+        title = "Output Rules",
+        description = "Rules mapping stdout lines to log entries; the first matching rule wins"
+    )]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub output_rules: Vec<OutputRule>,
+    #[schemars(
+        title = "Default Entry",
+        description = "What lines matching no rule become (default: assistant_message)"
+    )]
+    #[serde(default)]
+    pub default_entry: OutputEntryKind,
+    #[schemars(
+        title = "MCP Config Path",
+        description = "The agent's config file holding its MCP servers, if any"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub mcp_config_path: Option<PathBuf>,
+    #[serde(flatten)]
+    pub cmd: CmdOverrides,
+}
+
+impl Custom {
+    /// Check the profile's regexes
+    pub fn validate(&self) -> Result<(), CustomConfigError> {
+        self.output_rules()?;
+        self.session_id_regex()?;
+        Ok(())
+    }
+
+    pub fn capabilities(&self) -> Vec<BaseAgentCapability> {
+        if self.follow_up_args.is_some() {
+            vec![BaseAgentCapability::SessionFork]
+        } else {
+            vec![]
+        }
+    }
+
+    fn output_rules(&self) -> Result<OutputRules, CustomConfigError> {
+        let rules = self
+            .output_rules
+            .iter()
+            .map(|rule| {
+                Ok::<_, CustomConfigError>(CompiledRule {
+                    regex: compile("output rule", &rule.pattern)?,
+                    entry: rule.entry,
+                    tool_name: rule.tool_name.clone(),
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(OutputRules {
+            rules,
+            default_entry: self.default_entry,
+        })
+    }
+
+    fn session_id_regex(&self) -> Result<Option<Regex>, CustomConfigError> {
+        self.session_id_regex
+            .as_deref()
+            .map(|pattern| compile("session id", pattern))
+            .transpose()
+    }
+
+    fn build_command_builder(
+        &self,
+        prompt: &str,
+        prompt_file: Option<&Path>,
+        session_id: Option<&str>,
+    ) -> Result<CommandBuilder, ExecutorError> {
+        let mut params = self.args.clone();
+        if let Some(session_id) = session_id {
+            let follow_up_args = self.follow_up_args.as_ref().ok_or_else(|| {
+                ExecutorError::FollowUpNotSupported(format!(
+                    "the CUSTOM profile for `{}` has no follow_up_args",
+                    self.command
+                ))
+            })?;
+            params.extend(
+                follow_up_args
+                    .iter()
+                    .map(|arg| arg.replace(SESSION_ID_PLACEHOLDER, session_id)),
+            );
+        }
+
+        let prompt_arg = match (self.prompt_input, prompt_file) {
+            (PromptInput::Arg, _) => Some((PROMPT_PLACEHOLDER, prompt.to_string())),
+            (PromptInput::File, Some(file)) => {
+                Some((PROMPT_FILE_PLACEHOLDER, file.to_string_lossy().into_owned()))
+            }
+            _ => None,
+        };
+        let mut append = None;
+        if let Some((placeholder, value)) = prompt_arg {
+            if params.iter().any(|param| param.contains(placeholder)) {
+                for param in &mut params {
+                    *param = param.replace(placeholder, &value);
+                }
+            } else {
+                append = Some(value);
+            }
+        }
+
+        let builder = apply_overrides(
+            CommandBuilder::new(self.command.as_str()).params(params),
+            &self.cmd,
+        )?;
+        Ok(builder.extend_params(append))
+    }
+
+    async fn spawn_custom(
+        &self,
+        current_dir: &Path,
+        prompt: &str,
+        session_id: Option<&str>,
+        env: &ExecutionEnv,
+    ) -> Result<SpawnedChild, ExecutorError> {
+        self.validate()?;
+        let combined_prompt = self.append_prompt.combine_prompt(prompt);
+        let prompt_file = match self.prompt_input {
+            PromptInput::File => Some(write_prompt_file(&combined_prompt).await?),
+            PromptInput::Arg | PromptInput::Stdin => None,
+        };
+        let spawned = self
+            .spawn_process(
+                current_dir,
+                &combined_prompt,
+                prompt_file.as_deref(),
+                session_id,
+                env,
+            )
+            .await;
+        let Some(prompt_file) = prompt_file else {
+            return spawned;
+        };
+        let mut spawned = match spawned {
+            Ok(spawned) => spawned,
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&prompt_file).await;
+                return Err(e);
+            }
+        };
+        // The prompt file is removed once the agent closes its output, i.e. when it exits
+        let mut output = duplicate_stdout(&mut spawned.child)?;
+        tokio::spawn(async move {
+            while output.next().await.is_some() {}
+            if let Err(e) = tokio::fs::remove_file(&prompt_file).await {
+                tracing::debug!(
+                    "Failed to remove prompt file {}: {e}",
+                    prompt_file.display()
+                );
+            }
+        });
+        Ok(spawned)
+    }
+
+    async fn spawn_process(
+        &self,
+        current_dir: &Path,
+        combined_prompt: &str,
+        prompt_file: Option<&Path>,
+        session_id: Option<&str>,
+        env: &ExecutionEnv,
+    ) -> Result<SpawnedChild, ExecutorError> {
+        let command_parts = self
+            .build_command_builder(combined_prompt, prompt_file, session_id)?
+            .build_initial()?;
+        let (program_path, args) = command_parts.into_resolved().await?;
+
+        let stdin = if self.prompt_input == PromptInput::Stdin {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        };
+        let mut command = Command::new(program_path);
+        command
+            .kill_on_drop(true)
+            .stdin(stdin)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .current_dir(current_dir)
+            .args(args);
+
+        env.clone()
+            .with_profile(&self.cmd)
+            .apply_to_command(&mut command);
+
+        let mut child = command.group_spawn()?;
+
+        if let Some(mut stdin) = child.inner().stdin.take() {
+            stdin.write_all(combined_prompt.as_bytes()).await?;
+            stdin.shutdown().await?;
+        }
+
+        Ok(child.into())
+    }
+}
+
+fn compile(field: &'static str, pattern: &str) -> Result<Regex, CustomConfigError> {
+    Regex::new(pattern).map_err(|error| CustomConfigError::Regex {
+        field,
+        pattern: pattern.to_string(),
+        error,
+    })
+}
+
+/// Write the prompt where the agent can read it: a new file in the temp directory that only the
+/// current user can read
+async fn write_prompt_file(prompt: &str) -> Result<PathBuf, ExecutorError> {
+    let path =
+        std::env::temp_dir().join(format!("code-marshal-prompt-{}.md", uuid::Uuid::new_v4()));
+    let mut options = tokio::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    options.mode(0o600);
+    let mut file = options.open(&path).await.map_err(ExecutorError::Io)?;
+    file.write_all(prompt.as_bytes())
+        .await
+        .map_err(ExecutorError::Io)?;
+    file.flush().await.map_err(ExecutorError::Io)?;
+    Ok(path)
+}
+
+struct CompiledRule {
+    regex: Regex,
+    entry: OutputEntryKind,
+    tool_name: Option<String>,
+}
+
+/// The profile's output rules, applied to stdout through [`PlainTextLogProcessor`]
+struct OutputRules {
+    rules: Vec<CompiledRule>,
+    default_entry: OutputEntryKind,
+}
+
+impl OutputRules {
+    fn matching<'a, 'l>(&'a self, line: &'l str) -> Option<(&'a CompiledRule, Captures<'l>)> {
+        let line = line.trim_end_matches(['\n', '\r']);
+        self.rules
+            .iter()
+            .find_map(|rule| rule.regex.captures(line).map(|captures| (rule, captures)))
+    }
+
+    fn kind(&self, line: &str) -> OutputEntryKind {
+        self.matching(line)
+            .map_or(self.default_entry, |(rule, _)| rule.entry)
+    }
+
+    /// Drop complete lines that map to [`OutputEntryKind::Ignore`]
+    fn drop_ignored(&self, lines: &mut Vec<String>) {
+        lines.retain(|line| !line.ends_with('\n') || self.kind(line) != OutputEntryKind::Ignore);
+    }
+
+    /// Split entries where the kind of line changes, and after every tool line
+    fn boundary(&self, lines: &[String]) -> Option<MessageBoundary> {
+        let first = lines.first()?;
+        let kind = self.kind(first);
+        if !first.ends_with('\n') {
+            // A partial line may still turn out to be a tool call or ignored
+            return (kind.is_single_line() || kind == OutputEntryKind::Ignore)
+                .then_some(MessageBoundary::IncompleteContent);
+        }
+        if kind.is_single_line() {
+            return (lines.len() > 1).then_some(MessageBoundary::Split(1));
+        }
+        lines
+            .iter()
+            .enumerate()
+            .skip(1)
+            .take_while(|(_, line)| line.ends_with('\n'))
+            .find(|(_, line)| self.kind(line) != kind)
+            .map(|(index, _)| MessageBoundary::Split(index))
+    }
+
+    /// The entry for a group of lines, typed by its first line
+    fn entry(&self, content: String) -> NormalizedEntry {
+        let first = content.lines().next().unwrap_or_default();
+        let matched = self.matching(first);
+        let kind = matched
+            .as_ref()
+            .map_or(self.default_entry, |(rule, _)| rule.entry);
+        let group = |name: &str| {
+            matched
+                .as_ref()
+                .and_then(|(_, captures)| captures.name(name))
+                .map(|m| m.as_str().to_string())
+        };
+        let text = content
+            .lines()
+            .map(|line| match self.matching(line) {
+                Some((_, captures)) => captures
+                    .name("content")
+                    .map_or(line, |m| m.as_str())
+                    .to_string(),
+                None => line.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let tool_name = |default: &str| {
+            matched
+                .as_ref()
+                .and_then(|(rule, _)| rule.tool_name.clone())
+                .unwrap_or_else(|| default.to_string())
+        };
+        let tool_use = |tool_name: String, action_type: ActionType| NormalizedEntryType::ToolUse {
+            tool_name,
+            action_type,
+            status: ToolStatus::Success,
+        };
+
+        let entry_type = match kind {
+            OutputEntryKind::AssistantMessage | OutputEntryKind::Ignore => {
+                NormalizedEntryType::AssistantMessage
+            }
+            OutputEntryKind::Thinking => NormalizedEntryType::Thinking,
+            OutputEntryKind::SystemMessage => NormalizedEntryType::SystemMessage,
+            OutputEntryKind::ErrorMessage => NormalizedEntryType::ErrorMessage {
+                error_type: NormalizedEntryError::Other,
+            },
+            OutputEntryKind::ToolUse => {
+                let name = group("tool").unwrap_or_else(|| tool_name("tool"));
+                tool_use(
+                    name.clone(),
+                    ActionType::Tool {
+                        tool_name: name,
+                        arguments: None,
+                        result: None,
+                    },
+                )
+            }
+            OutputEntryKind::CommandRun => tool_use(
+                tool_name("bash"),
+                ActionType::CommandRun {
+                    command: group("command").unwrap_or_else(|| text.clone()),
+                    result: None,
+                },
+            ),
+            OutputEntryKind::FileRead => tool_use(
+                tool_name("read"),
+                ActionType::FileRead {
+                    path: group("path").unwrap_or_else(|| text.clone()),
+                },
+            ),
+            OutputEntryKind::FileEdit => tool_use(
+                tool_name("edit"),
+                ActionType::FileEdit {
+                    path: group("path").unwrap_or_else(|| text.clone()),
+                    changes: vec![],
+                },
+            ),
+        };
+        NormalizedEntry {
+            timestamp: None,
+            entry_type,
+            content: text,
+            metadata: None,
+        }
+    }
+}
+
+fn session_id_from(regex: &Regex, line: &str) -> Option<String> {
+    let captures = regex.captures(line)?;
+    captures
+        .name("session_id")
+        .or_else(|| captures.get(1))
+        .or_else(|| captures.get(0))
+        .map(|m| m.as_str().to_string())
+}
+
+#[async_trait]
+impl StandardCodingAgentExecutor for Custom {
+    async fn spawn(
+        &self,
+        current_dir: &Path,
+        prompt: &str,
+        env: &ExecutionEnv,
+    ) -> Result<SpawnedChild, ExecutorError> {
+        self.spawn_custom(current_dir, prompt, None, env).await
+    }
+
+    async fn spawn_follow_up(
+        &self,
+        current_dir: &Path,
+        prompt: &str,
+        session_id: &str,
+        _reset_to_message_id: Option<&str>,
+        env: &ExecutionEnv,
+    ) -> Result<SpawnedChild, ExecutorError> {
+        self.spawn_custom(current_dir, prompt, Some(session_id), env)
+            .await
+    }
+
+    fn normalize_logs(&self, msg_store: Arc<MsgStore>, _worktree_path: &Path) {
+        let entry_index_provider = EntryIndexProvider::start_from(&msg_store);
+        normalize_stderr_logs(msg_store.clone(), entry_index_provider.clone());
+
+        // Both were checked at spawn; fall back to plain messages rather than losing output
+        let rules = Arc::new(self.output_rules().unwrap_or_else(|err| {
+            tracing::warn!("{err}");
+            OutputRules {
+                rules: vec![],
+                default_entry: self.default_entry,
+            }
+        }));
+        if let Ok(Some(regex)) = self.session_id_regex() {
+            let msg_store = msg_store.clone();
+            tokio::spawn(async move {
+                let mut lines = msg_store.stdout_lines_stream();
+                while let Some(Ok(line)) = lines.next().await {
+                    let line = strip_ansi_escapes::strip_str(&line);
+                    if let Some(session_id) = session_id_from(&regex, &line) {
+                        msg_store.push_session_id(session_id);
+                        break;
+                    }
+                }
+            });
+        }
+
+        tokio::spawn(async move {
+            let mut stdout = msg_store.stdout_chunked_stream();
+            let (boundary_rules, transform_rules) = (rules.clone(), rules.clone());
+            let mut processor = PlainTextLogProcessor::builder()
+                .normalized_entry_producer(move |content: String| rules.entry(content))
+                .format_chunk(Box::new(|_: Option<&str>, chunk: String| {
+                    strip_ansi_escapes::strip_str(&chunk)
+                }))
+                .transform_lines(Box::new(move |lines: &mut Vec<String>| {
+                    transform_rules.drop_ignored(lines)
+                }))
+                .message_boundary_predicate(Box::new(move |lines: &[String]| {
+                    boundary_rules.boundary(lines)
+                }))
+                .index_provider(entry_index_provider)
+                .build();
+
+            while let Some(Ok(chunk)) = stdout.next().await {
+                for patch in processor.process(chunk) {
+                    msg_store.push_patch(patch);
+                }
+            }
+        });
+    }
+
+    fn default_mcp_config_path(&self) -> Option<PathBuf> {
+        self.mcp_config_path.clone()
+    }
+
+    fn get_availability_info(&self) -> AvailabilityInfo {
+        let base = self
+            .cmd
+            .base_command_override
+            .as_deref()
+            .unwrap_or(&self.command);
+        let program = shlex::split(base).and_then(|words| words.into_iter().next());
+        match program {
+            Some(program)
+                if workspace_utils::shell::resolve_executable_path_blocking(&program).is_some() =>
+            {
+                AvailabilityInfo::InstallationFound
+            }
+            _ => AvailabilityInfo::NotFound,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn custom(config: serde_json::Value) -> Custom {
+        serde_json::from_value(config).unwrap()
+    }
+
+    #[test]
+    fn groups_lines_by_rule_and_splits_tool_calls() {
+        let agent = custom(serde_json::json!({
+            "command": "my-agent",
+            "output_rules": [
+                { "pattern": "^\\$ (?P<command>.+)", "entry": "command_run" },
+                { "pattern": "^> (?P<content>.*)", "entry": "thinking" },
+                { "pattern": "^DEBUG", "entry": "ignore" }
+            ]
+        }));
+        let rules = agent.output_rules().unwrap();
+        let mut lines: Vec<String> = [
+            "> looking\n",
+            "DEBUG x\n",
+            "> around\n",
+            "$ ls -la\n",
+            "done",
+        ]
+        .map(String::from)
+        .to_vec();
+        rules.drop_ignored(&mut lines);
+        assert_eq!(lines.len(), 4);
+        assert_eq!(rules.boundary(&lines), Some(MessageBoundary::Split(2)));
+
+        let thinking = rules.entry(lines[..2].concat());
+        assert!(matches!(thinking.entry_type, NormalizedEntryType::Thinking));
+        assert_eq!(thinking.content, "looking\naround");
+
+        assert_eq!(rules.boundary(&lines[2..]), Some(MessageBoundary::Split(1)));
+        let command = rules.entry(lines[2].clone());
+        assert!(matches!(
+            command.entry_type,
+            NormalizedEntryType::ToolUse {
+                action_type: ActionType::CommandRun { ref command, .. },
+                ..
+            } if command == "ls -la"
+        ));
+        assert_eq!(rules.boundary(&lines[3..]), None);
+    }
+
+    #[test]
+    fn builds_prompt_and_follow_up_arguments() {
+        let agent = custom(serde_json::json!({
+            "command": "my-agent run",
+            "args": ["--message={prompt}"],
+            "follow_up_args": ["--resume", "{session_id}"],
+            "session_id_regex": "session: (\\S+)"
+        }));
+        let builder = agent
+            .build_command_builder("fix it", None, Some("abc123"))
+            .unwrap();
+        assert_eq!(
+            builder.params.unwrap(),
+            vec!["--message=fix it", "--resume", "abc123"]
+        );
+        let regex = agent.session_id_regex().unwrap().unwrap();
+        assert_eq!(
+            session_id_from(&regex, "[info] session: abc123 started"),
+            Some("abc123".to_string())
+        );
+
+        let no_follow_ups = custom(serde_json::json!({ "command": "my-agent" }));
+        assert!(matches!(
+            no_follow_ups.build_command_builder("fix it", None, Some("abc123")),
+            Err(ExecutorError::FollowUpNotSupported(_))
+        ));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn prompt_file_is_private_and_removed_after_exit() {
+        use tokio::io::AsyncReadExt;
+
+        use crate::env::RepoContext;
+
+        let agent = custom(serde_json::json!({
+            "command": "sh",
+            "args": ["-c", "ls -l {prompt_file} && echo {prompt_file}"],
+            "prompt_input": "file"
+        }));
+        let env = ExecutionEnv::new(RepoContext::default(), false, String::new());
+        let mut spawned = agent
+            .spawn(&std::env::temp_dir(), "secret plan", &env)
+            .await
+            .unwrap();
+        let mut output = String::new();
+        let mut stdout = spawned.child.inner().stdout.take().unwrap();
+        stdout.read_to_string(&mut output).await.unwrap();
+        spawned.child.wait().await.unwrap();
+
+        assert!(output.starts_with("-rw-------"), "{output}");
+        let path = PathBuf::from(output.lines().last().unwrap().trim());
+        for _ in 0..50 {
+            if !path.exists() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert!(!path.exists());
+    }
+}
@@ -80,10 +80,14 @@ pub struct Droid {
 }
 
 impl Droid {
+    pub fn base_command() -> &'static str {
+        "droid exec"
+    }
+
     pub fn build_command_builder(&self) -> Result<CommandBuilder, CommandBuildError> {
         use crate::command::{CommandBuilder, apply_overrides};
         let mut builder =
-            CommandBuilder::new("droid exec").params(["--output-format", "stream-json"]);
+            CommandBuilder::new(Self::base_command()).params(["--output-format", "stream-json"]);
         builder = match &self.autonomy {
             Autonomy::Normal => builder,
             Autonomy::Low => builder.extend_params(["--auto", "low"]),
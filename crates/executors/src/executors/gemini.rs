@@ -13,8 +13,10 @@ use crate::{
     command::{CmdOverrides, CommandBuildError, CommandBuilder, apply_overrides},
     env::ExecutionEnv,
     executors::{
-        AppendPrompt, AvailabilityInfo, ExecutorError, SpawnedChild, StandardCodingAgentExecutor,
+        AppendPrompt, AvailabilityInfo, BaseCodingAgent, ExecutorError, SpawnedChild,
+        StandardCodingAgentExecutor,
     },
+    models::{ModelCatalog, known_models},
 };
 
 #[derive(Derivative, Clone, Serialize, Deserialize, TS, JsonSchema)]
@@ -35,8 +37,12 @@ pub struct Gemini {
 }
 
 impl Gemini {
+    pub fn base_command() -> &'static str {
+        "npx -y @google/gemini-cli@0.27.0"
+    }
+
     fn build_command_builder(&self) -> Result<CommandBuilder, CommandBuildError> {
-        let mut builder = CommandBuilder::new("npx -y @google/gemini-cli@0.27.0");
+        let mut builder = CommandBuilder::new(Self::base_command());
 
         if let Some(model) = &self.model {
             builder = builder.extend_params(["--model", model.as_str()]);
@@ -59,6 +65,11 @@ impl StandardCodingAgentExecutor for Gemini {
         self.approvals = Some(approvals);
     }
 
+    fn require_approvals(&mut self) -> bool {
+        self.yolo = Some(false);
+        true
+    }
+
     async fn spawn(
         &self,
         current_dir: &Path,
@@ -122,6 +133,10 @@ impl StandardCodingAgentExecutor for Gemini {
         dirs::home_dir().map(|home| home.join(".gemini").join("settings.json"))
     }
 
+    async fn discover_models(&self, _current_dir: &Path) -> Result<ModelCatalog, ExecutorError> {
+        known_models(BaseCodingAgent::Gemini).ok_or(ExecutorError::ModelListingNotSupported)
+    }
+
     fn get_availability_info(&self) -> AvailabilityInfo {
         if let Some(timestamp) = dirs::home_dir()
             .and_then(|home| std::fs::metadata(home.join(".gemini").join("oauth_creds.json")).ok())
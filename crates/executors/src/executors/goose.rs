@@ -0,0 +1,214 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::Arc,
+};
+
+use async_trait::async_trait;
+use command_group::AsyncCommandGroup;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use strum_macros::AsRefStr;
+use tokio::{io::AsyncWriteExt, process::Command};
+use ts_rs::TS;
+use workspace_utils::msg_store::MsgStore;
+
+use crate::{
+    command::{CmdOverrides, CommandBuildError, CommandBuilder, CommandParts, apply_overrides},
+    env::ExecutionEnv,
+    executors::{
+        AppendPrompt, AvailabilityInfo, ExecutorError, SpawnedChild, StandardCodingAgentExecutor,
+    },
+    logs::utils::EntryIndexProvider,
+    stdout_dup::tee_stdout_with_appender,
+};
+
+pub mod normalize_logs;
+
+use normalize_logs::{SESSION_MARKER, normalize_logs};
+
+/// Goose's permission mode, passed as `GOOSE_MODE`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, TS, JsonSchema, AsRefStr)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+#[ts(rename = "GooseMode")]
+pub enum GooseMode {
+    Auto,
+    Approve,
+    SmartApprove,
+    Chat,
+}
+
+fn default_mode() -> GooseMode {
+    GooseMode::Auto
+}
+
+/// Goose executor configuration
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, TS, JsonSchema)]
+pub struct Goose {
+    #[serde(default)]
+    pub append_prompt: AppendPrompt,
+
+    #[serde(default = "default_mode")]
+    #[schemars(
+        title = "Mode",
+        description = "auto, approve, smart_approve or chat (no tools); non-interactive runs \
+                       cannot answer approval prompts"
+    )]
+    pub mode: GooseMode,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(
+        title = "Provider",
+        description = "Provider to use (e.g., anthropic, openai, google, ollama, openrouter)"
+    )]
+    pub provider: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(title = "Model", description = "Model to use with the provider")]
+    pub model: Option<String>,
+
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[schemars(
+        title = "Max Turns",
+        description = "Maximum turns without user input before Goose stops"
+    )]
+    pub max_turns: Option<u32>,
+
+    #[serde(flatten)]
+    pub cmd: CmdOverrides,
+}
+
+/// Goose's config dir, holding `config.yaml` (provider settings and extensions)
+pub fn goose_config_dir() -> Option<PathBuf> {
+    #[cfg(not(windows))]
+    {
+        xdg::BaseDirectories::with_prefix("goose").get_config_home()
+    }
+    #[cfg(windows)]
+    {
+        dirs::config_dir().map(|dir| dir.join("Block").join("goose").join("config"))
+    }
+}
+
+impl Goose {
+    pub fn base_command() -> &'static str {
+        "goose run"
+    }
+
+    fn build_command_builder(&self) -> Result<CommandBuilder, CommandBuildError> {
+        let mut builder = CommandBuilder::new(Self::base_command()).params([
+            "--output-format",
+            "stream-json",
+            "--instructions",
+            "-",
+        ]);
+        if let Some(provider) = &self.provider {
+            builder = builder.extend_params(["--provider", provider.as_str()]);
+        }
+        if let Some(model) = &self.model {
+            builder = builder.extend_params(["--model", model.as_str()]);
+        }
+        if let Some(max_turns) = self.max_turns {
+            builder = builder.extend_params(["--max-turns".to_string(), max_turns.to_string()]);
+        }
+
+        apply_overrides(builder, &self.cmd)
+    }
+
+    async fn spawn_goose(
+        &self,
+        command_parts: CommandParts,
+        prompt: &str,
+        session_id: &str,
+        current_dir: &Path,
+        env: &ExecutionEnv,
+    ) -> Result<SpawnedChild, ExecutorError> {
+        let (program_path, args) = command_parts.into_resolved().await?;
+
+        let mut command = Command::new(program_path);
+        command
+            .kill_on_drop(true)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .current_dir(current_dir)
+            .env("GOOSE_MODE", self.mode.as_ref())
+            .args(args);
+
+        env.clone()
+            .with_profile(&self.cmd)
+            .apply_to_command(&mut command);
+
+        let mut child = command.group_spawn()?;
+
+        // Goose's stream output does not carry the session name; announce ours for follow-ups
+        let (_, appender) = tee_stdout_with_appender(&mut child)?;
+        appender.append_line(format!("{SESSION_MARKER}{session_id}"));
+
+        if let Some(mut stdin) = child.inner().stdin.take() {
+            stdin
+                .write_all(self.append_prompt.combine_prompt(prompt).as_bytes())
+                .await?;
+            stdin.shutdown().await?;
+        }
+
+        Ok(child.into())
+    }
+}
+
+#[async_trait]
+impl StandardCodingAgentExecutor for Goose {
+    async fn spawn(
+        &self,
+        current_dir: &Path,
+        prompt: &str,
+        env: &ExecutionEnv,
+    ) -> Result<SpawnedChild, ExecutorError> {
+        let session_id = format!("code-marshal-{}", uuid::Uuid::new_v4());
+        let command_parts = self
+            .build_command_builder()?
+            .extend_params(["--name", session_id.as_str()])
+            .build_initial()?;
+        self.spawn_goose(command_parts, prompt, &session_id, current_dir, env)
+            .await
+    }
+
+    async fn spawn_follow_up(
+        &self,
+        current_dir: &Path,
+        prompt: &str,
+        session_id: &str,
+        _reset_to_message_id: Option<&str>,
+        env: &ExecutionEnv,
+    ) -> Result<SpawnedChild, ExecutorError> {
+        let command_parts = self.build_command_builder()?.build_follow_up(&[
+            "--name".to_string(),
+            session_id.to_string(),
+            "--resume".to_string(),
+        ])?;
+        self.spawn_goose(command_parts, prompt, session_id, current_dir, env)
+            .await
+    }
+
+    fn normalize_logs(&self, msg_store: Arc<MsgStore>, current_dir: &Path) {
+        normalize_logs(
+            msg_store.clone(),
+            current_dir,
+            EntryIndexProvider::start_from(&msg_store),
+        );
+    }
+
+    fn default_mcp_config_path(&self) -> Option<PathBuf> {
+        goose_config_dir().map(|dir| dir.join("config.yaml"))
+    }
+
+    fn get_availability_info(&self) -> AvailabilityInfo {
+        // `goose configure` creates the config dir on first setup
+        if goose_config_dir().is_some_and(|dir| dir.exists()) {
+            AvailabilityInfo::InstallationFound
+        } else {
+            AvailabilityInfo::NotFound
+        }
+    }
+}
@@ -0,0 +1,506 @@
+//! Normalizes `goose run --output-format stream-json`: one JSON event per line, where `message`
+//! events carry streamed chunks of conversation messages (text, thinking, tool requests, and
+//! tool responses in `user` messages).
+
+use std::{collections::HashMap, path::Path, sync::Arc};
+
+use futures::{StreamExt, future::ready};
+use serde::Deserialize;
+use serde_json::Value;
+use workspace_utils::{diff::create_unified_diff, msg_store::MsgStore, path::make_path_relative};
+
+use crate::logs::{
+    ActionType, CommandExitStatus, CommandRunResult, FileChange, NormalizedEntry,
+    NormalizedEntryError, NormalizedEntryType, ToolResult, ToolStatus,
+    stderr_processor::normalize_stderr_logs,
+    utils::{
+        EntryIndexProvider,
+        patch::{add_normalized_entry, replace_normalized_entry},
+    },
+};
+
+/// Line the executor adds to stdout to announce the session name
+pub const SESSION_MARKER: &str = "[code-marshal] goose session: ";
+
+pub fn normalize_logs(
+    msg_store: Arc<MsgStore>,
+    worktree_path: &Path,
+    entry_index_provider: EntryIndexProvider,
+) {
+    normalize_stderr_logs(msg_store.clone(), entry_index_provider.clone());
+
+    let worktree_path = worktree_path.to_string_lossy().to_string();
+    tokio::spawn(async move {
+        let mut state = GooseLogState::default();
+        let mut lines_stream = msg_store
+            .stdout_lines_stream()
+            .filter_map(|res| ready(res.ok()));
+
+        while let Some(line) = lines_stream.next().await {
+            let trimmed = line.trim();
+            if let Some(session_id) = trimmed.strip_prefix(SESSION_MARKER) {
+                msg_store.push_session_id(session_id.to_string());
+                continue;
+            }
+            let event = match serde_json::from_str::<GooseEvent>(trimmed) {
+                Ok(event) => event,
+                Err(_) => {
+                    if !trimmed.is_empty() {
+                        let entry = NormalizedEntry {
+                            timestamp: None,
+                            entry_type: NormalizedEntryType::SystemMessage,
+                            content: strip_ansi_escapes::strip_str(trimmed).to_string(),
+                            metadata: None,
+                        };
+                        add_normalized_entry(&msg_store, &entry_index_provider, entry);
+                    }
+                    continue;
+                }
+            };
+
+            match event {
+                GooseEvent::Message { message } => {
+                    for content in message.content {
+                        state.handle_content(
+                            &msg_store,
+                            &entry_index_provider,
+                            &worktree_path,
+                            message.id.as_deref(),
+                            &message.role,
+                            content,
+                        );
+                    }
+                }
+                GooseEvent::ModelChange { model, .. } => {
+                    state.streaming = None;
+                    let entry = NormalizedEntry {
+                        timestamp: None,
+                        entry_type: NormalizedEntryType::SystemMessage,
+                        content: format!("model: {model}"),
+                        metadata: None,
+                    };
+                    add_normalized_entry(&msg_store, &entry_index_provider, entry);
+                }
+                GooseEvent::Error { error } => {
+                    state.streaming = None;
+                    let entry = NormalizedEntry {
+                        timestamp: None,
+                        entry_type: NormalizedEntryType::ErrorMessage {
+                            error_type: NormalizedEntryError::Other,
+                        },
+                        content: error,
+                        metadata: None,
+                    };
+                    add_normalized_entry(&msg_store, &entry_index_provider, entry);
+                }
+                GooseEvent::Complete { .. } | GooseEvent::Unknown => {}
+            }
+        }
+    });
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum GooseEvent {
+    Message {
+        message: GooseMessage,
+    },
+    ModelChange {
+        model: String,
+        #[serde(default)]
+        mode: Option<String>,
+    },
+    Error {
+        error: String,
+    },
+    Complete {
+        #[serde(default)]
+        total_tokens: Option<i64>,
+    },
+    /// Extension notifications and anything newer
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GooseMessage {
+    #[serde(default)]
+    pub id: Option<String>,
+    pub role: String,
+    #[serde(default)]
+    pub content: Vec<GooseContent>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum GooseContent {
+    Text {
+        text: String,
+    },
+    Thinking {
+        thinking: String,
+    },
+    ToolRequest {
+        id: String,
+        #[serde(rename = "toolCall")]
+        tool_call: GooseResult<GooseToolCall>,
+    },
+    ToolResponse {
+        id: String,
+        #[serde(rename = "toolResult")]
+        tool_result: GooseResult<Vec<Value>>,
+    },
+    #[serde(other)]
+    Unknown,
+}
+
+/// Goose serializes tool calls and results as `{"status": "success", "value": ...}` or
+/// `{"status": "error", "error": "..."}`
+#[derive(Debug, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum GooseResult<T> {
+    Success { value: T },
+    Error { error: String },
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GooseToolCall {
+    pub name: String,
+    #[serde(default)]
+    pub arguments: Value,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TextKind {
+    Assistant,
+    Thinking,
+}
+
+/// The text entry receiving streamed chunks of the same message
+struct StreamingText {
+    message_id: Option<String>,
+    kind: TextKind,
+    index: usize,
+    content: String,
+}
+
+struct PendingTool {
+    index: usize,
+    entry: NormalizedEntry,
+}
+
+#[derive(Default)]
+struct GooseLogState {
+    streaming: Option<StreamingText>,
+    tools: HashMap<String, PendingTool>,
+}
+
+impl GooseLogState {
+    fn handle_content(
+        &mut self,
+        msg_store: &Arc<MsgStore>,
+        entry_index_provider: &EntryIndexProvider,
+        worktree_path: &str,
+        message_id: Option<&str>,
+        role: &str,
+        content: GooseContent,
+    ) {
+        match content {
+            // The prompt is echoed back as a user message
+            GooseContent::Text { .. } | GooseContent::Thinking { .. } if role != "assistant" => {}
+            GooseContent::Text { text } => self.stream_text(
+                msg_store,
+                entry_index_provider,
+                message_id,
+                TextKind::Assistant,
+                &text,
+            ),
+            GooseContent::Thinking { thinking } => self.stream_text(
+                msg_store,
+                entry_index_provider,
+                message_id,
+                TextKind::Thinking,
+                &thinking,
+            ),
+            GooseContent::ToolRequest { id, tool_call } => {
+                self.streaming = None;
+                let entry = match tool_call {
+                    GooseResult::Success { value } => {
+                        let (tool_name, action_type) =
+                            tool_action(&value.name, &value.arguments, worktree_path);
+                        tool_entry(tool_name, action_type, ToolStatus::Created)
+                    }
+                    GooseResult::Error { error } => NormalizedEntry {
+                        timestamp: None,
+                        entry_type: NormalizedEntryType::ErrorMessage {
+                            error_type: NormalizedEntryError::Other,
+                        },
+                        content: error,
+                        metadata: None,
+                    },
+                };
+                let index = add_normalized_entry(msg_store, entry_index_provider, entry.clone());
+                self.tools.insert(id, PendingTool { index, entry });
+            }
+            GooseContent::ToolResponse { id, tool_result } => {
+                self.streaming = None;
+                if let Some(PendingTool { index, entry }) = self.tools.remove(&id)
+                    && let Some(entry) = complete_tool(entry, tool_result)
+                {
+                    replace_normalized_entry(msg_store, index, entry);
+                }
+            }
+            GooseContent::Unknown => {}
+        }
+    }
+
+    fn stream_text(
+        &mut self,
+        msg_store: &Arc<MsgStore>,
+        entry_index_provider: &EntryIndexProvider,
+        message_id: Option<&str>,
+        kind: TextKind,
+        chunk: &str,
+    ) {
+        let entry_type = match kind {
+            TextKind::Assistant => NormalizedEntryType::AssistantMessage,
+            TextKind::Thinking => NormalizedEntryType::Thinking,
+        };
+        match &mut self.streaming {
+            Some(streaming)
+                if streaming.kind == kind && streaming.message_id.as_deref() == message_id =>
+            {
+                streaming.content.push_str(chunk);
+                let entry = NormalizedEntry {
+                    timestamp: None,
+                    entry_type,
+                    content: streaming.content.clone(),
+                    metadata: None,
+                };
+                replace_normalized_entry(msg_store, streaming.index, entry);
+            }
+            _ => {
+                if chunk.trim().is_empty() {
+                    return;
+                }
+                let entry = NormalizedEntry {
+                    timestamp: None,
+                    entry_type,
+                    content: chunk.to_string(),
+                    metadata: None,
+                };
+                let index = add_normalized_entry(msg_store, entry_index_provider, entry);
+                self.streaming = Some(StreamingText {
+                    message_id: message_id.map(str::to_string),
+                    kind,
+                    index,
+                    content: chunk.to_string(),
+                });
+            }
+        }
+    }
+}
+
+fn tool_entry(tool_name: String, action_type: ActionType, status: ToolStatus) -> NormalizedEntry {
+    let content = match &action_type {
+        ActionType::FileRead { path } | ActionType::FileEdit { path, .. } => path.clone(),
+        ActionType::CommandRun { command, .. } => command.clone(),
+        ActionType::WebFetch { url } => url.clone(),
+        _ => tool_name.clone(),
+    };
+    NormalizedEntry {
+        timestamp: None,
+        entry_type: NormalizedEntryType::ToolUse {
+            tool_name,
+            action_type,
+            status,
+        },
+        content,
+        metadata: None,
+    }
+}
+
+/// Map a Goose tool call (`<extension>__<tool>`) onto the closest `ActionType`
+pub fn tool_action(name: &str, arguments: &Value, worktree_path: &str) -> (String, ActionType) {
+    let arg = |key: &str| {
+        arguments
+            .get(key)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+    };
+    let path = make_path_relative(arg("path"), worktree_path);
+    let tool = name.rsplit("__").next().unwrap_or(name);
+
+    match (tool, arg("command")) {
+        ("shell", command) => (
+            "bash".to_string(),
+            ActionType::CommandRun {
+                command: command.to_string(),
+                result: None,
+            },
+        ),
+        ("text_editor", "view") | ("analyze", _) => {
+            ("read".to_string(), ActionType::FileRead { path })
+        }
+        ("text_editor", command) => {
+            let changes = match command {
+                "write" => vec![FileChange::Write {
+                    content: arg("file_text").to_string(),
+                }],
+                "str_replace" => vec![FileChange::Edit {
+                    unified_diff: create_unified_diff(&path, arg("old_str"), arg("new_str")),
+                    has_line_numbers: false,
+                }],
+                "insert" => vec![FileChange::Edit {
+                    unified_diff: create_unified_diff(&path, "", arg("new_str")),
+                    has_line_numbers: false,
+                }],
+                _ => vec![],
+            };
+            ("edit".to_string(), ActionType::FileEdit { path, changes })
+        }
+        ("web_scrape" | "fetch", _) => (
+            "fetch".to_string(),
+            ActionType::WebFetch {
+                url: arg("url").to_string(),
+            },
+        ),
+        _ => (
+            name.to_string(),
+            ActionType::Tool {
+                tool_name: name.to_string(),
+                arguments: Some(arguments.clone()),
+                result: None,
+            },
+        ),
+    }
+}
+
+/// Apply a tool response to the entry created for its request
+fn complete_tool(
+    mut entry: NormalizedEntry,
+    tool_result: GooseResult<Vec<Value>>,
+) -> Option<NormalizedEntry> {
+    let NormalizedEntryType::ToolUse {
+        action_type,
+        status,
+        ..
+    } = &mut entry.entry_type
+    else {
+        return None;
+    };
+    let (success, output) = match tool_result {
+        GooseResult::Success { value } => {
+            let text: Vec<&str> = value
+                .iter()
+                .filter_map(|content| content.get("text").and_then(Value::as_str))
+                .collect();
+            (true, text.join("\n"))
+        }
+        GooseResult::Error { error } => (false, error),
+    };
+    *status = if success {
+        ToolStatus::Success
+    } else {
+        ToolStatus::Failed
+    };
+    match action_type {
+        ActionType::CommandRun { result, .. } => {
+            *result = Some(CommandRunResult {
+                exit_status: Some(CommandExitStatus::Success { success }),
+                output: (!output.is_empty()).then_some(output),
+            });
+        }
+        ActionType::Tool { result, .. } if !output.is_empty() => {
+            *result = Some(ToolResult::markdown(output));
+        }
+        _ => {}
+    }
+    Some(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn parses_stream_events() {
+        let line = json!({"type": "message", "message": {
+            "id": "msg_1", "role": "assistant", "created": 1,
+            "content": [
+                {"type": "text", "text": "Looking"},
+                {"type": "toolRequest", "id": "call_1", "toolCall": {
+                    "status": "success",
+                    "value": {"name": "developer__shell", "arguments": {"command": "ls"}}
+                }}
+            ]
+        }});
+        let GooseEvent::Message { message } = serde_json::from_value(line).unwrap() else {
+            panic!("expected a message event");
+        };
+        assert_eq!(message.id.as_deref(), Some("msg_1"));
+        assert!(matches!(&message.content[0], GooseContent::Text { text } if text == "Looking"));
+        assert!(matches!(
+            &message.content[1],
+            GooseContent::ToolRequest {
+                tool_call: GooseResult::Success { value },
+                ..
+            } if value.name == "developer__shell"
+        ));
+
+        let line = r#"{"type":"notification","extension_id":"developer","log":{"message":"x"}}"#;
+        assert!(matches!(
+            serde_json::from_str::<GooseEvent>(line).unwrap(),
+            GooseEvent::Unknown
+        ));
+    }
+
+    #[test]
+    fn maps_tool_calls_to_actions() {
+        let (name, action) = tool_action(
+            "developer__text_editor",
+            &json!({"command": "str_replace", "path": "/repo/src/lib.rs",
+                    "old_str": "a", "new_str": "b"}),
+            "/repo",
+        );
+        assert_eq!(name, "edit");
+        assert!(matches!(
+            &action,
+            ActionType::FileEdit { path, changes } if path == "src/lib.rs" && changes.len() == 1
+        ));
+
+        let (_, action) = tool_action(
+            "developer__text_editor",
+            &json!({"command": "view", "path": "/repo/README.md"}),
+            "/repo",
+        );
+        assert!(matches!(action, ActionType::FileRead { path } if path == "README.md"));
+
+        let entry = tool_entry(
+            "bash".to_string(),
+            tool_action("developer__shell", &json!({"command": "ls"}), "/repo").1,
+            ToolStatus::Created,
+        );
+        let result = GooseResult::Success {
+            value: vec![json!({"type": "text", "text": "Cargo.toml"})],
+        };
+        let entry = complete_tool(entry, result).unwrap();
+        assert!(matches!(
+            entry.entry_type,
+            NormalizedEntryType::ToolUse {
+                action_type: ActionType::CommandRun {
+                    result: Some(CommandRunResult { output: Some(ref output), .. }),
+                    ..
+                },
+                status: ToolStatus::Success,
+                ..
+            } if output == "Cargo.toml"
+        ));
+
+        let (name, action) = tool_action("github__create_issue", &json!({"title": "t"}), "/r");
+        assert_eq!(name, "github__create_issue");
+        assert!(matches!(action, ActionType::Tool { .. }));
+    }
+}
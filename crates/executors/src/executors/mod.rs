@@ -8,7 +8,8 @@ use futures_io::Error as FuturesIoError;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use sqlx::Type;
-use strum_macros::{Display, EnumDiscriminants, EnumString, VariantNames};
+use strum::IntoEnumIterator;
+use strum_macros::{Display, EnumDiscriminants, EnumIter, EnumString, VariantNames};
 use thiserror::Error;
 use ts_rs::TS;
 use workspace_utils::msg_store::MsgStore;
@@ -18,25 +19,33 @@ use crate::executors::qa_mock::QaMockExecutor;
 use crate::{
     actions::{ExecutorAction, review::RepoReviewContext},
     approvals::ExecutorApprovalService,
-    command::CommandBuildError,
+    command::{CmdOverrides, CommandBuildError},
+    doctor::AuthStatus,
     env::ExecutionEnv,
     executors::{
-        amp::Amp, claude::ClaudeCode, codex::Codex, copilot::Copilot, cursor::CursorAgent,
-        droid::Droid, gemini::Gemini, opencode::Opencode, qwen::QwenCode,
+        acp_agent::Acp, aider::Aider, amp::Amp, claude::ClaudeCode, codex::Codex, copilot::Copilot,
+        cursor::CursorAgent, custom::Custom, droid::Droid, gemini::Gemini, goose::Goose,
+        opencode::Opencode, qwen::QwenCode,
     },
     logs::utils::patch,
     mcp_config::McpConfig,
+    models::ModelCatalog,
 };
 
 pub mod acp;
+pub mod acp_agent;
+pub mod aider;
 pub mod amp;
 pub mod claude;
 pub mod codex;
 pub mod copilot;
 pub mod cursor;
+pub mod custom;
 pub mod droid;
 pub mod gemini;
+pub mod goose;
 pub mod opencode;
+pub mod plugin;
 #[cfg(feature = "qa-mode")]
 pub mod qa_mock;
 pub mod qwen;
@@ -50,15 +59,81 @@ pub struct SlashCommandDescription {
     pub description: Option<String>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, TS)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    Hash,
+    TS,
+    JsonSchema,
+    Display,
+    EnumIter,
+)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+#[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
 #[ts(use_ts_enum)]
 pub enum BaseAgentCapability {
+    /// Follow-ups continue an earlier session (`spawn_follow_up`)
     SessionFork,
     /// Agent requires a setup script before it can run (e.g., login, installation)
     SetupHelper,
-    /// Agent reports context/token usage information
+    /// Agent reports context/token usage information (`TokenUsageInfo` entries)
     ContextUsage,
+    /// Agent accepts images attached to the prompt
+    ImageInput,
+    /// Agent process can stay alive between turns and accept further messages
+    LiveInput,
+    /// Running turn can be interrupted or steered without losing the session
+    Interrupt,
+    /// Permission / plan mode can be switched while the session is running
+    ModeSwitch,
+    /// Agent can run in a non-mutating configuration, see [`crate::plan::plan_only_agent`]
+    PlanOnly,
+    /// Follow-ups can resume from an earlier message id (`reset_to_message_id`)
+    MessageReset,
+    /// Tool calls are submitted to the service passed to `use_approvals`
+    Approvals,
+    /// MCP servers can be configured for the agent (it has an MCP config file)
+    Mcp,
+    /// MCP servers reached over HTTP are supported, not only stdio ones
+    HttpMcp,
+    /// Agent can review changes (`spawn_review`)
+    Review,
+    /// Agent reports its slash commands (`available_slash_commands`)
+    SlashCommands,
+    /// The models available to the agent can be listed
+    ModelListing,
+}
+
+impl BaseAgentCapability {
+    pub fn all() -> impl Iterator<Item = Self> {
+        Self::iter()
+    }
+
+    /// What the capability means to a user, for errors and `--list-agents`
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::SessionFork => "follow-ups",
+            Self::SetupHelper => "setup helper",
+            Self::ContextUsage => "token usage",
+            Self::ImageInput => "image input",
+            Self::LiveInput => "live input",
+            Self::Interrupt => "interrupt",
+            Self::ModeSwitch => "mode switch",
+            Self::PlanOnly => "plan-only runs",
+            Self::MessageReset => "message reset",
+            Self::Approvals => "tool approvals",
+            Self::Mcp => "MCP servers",
+            Self::HttpMcp => "HTTP MCP servers",
+            Self::Review => "review",
+            Self::SlashCommands => "slash commands",
+            Self::ModelListing => "model listing",
+        }
+    }
 }
 
 #[derive(Debug, Error)]
@@ -78,27 +153,77 @@ pub enum ExecutorError {
     #[error(transparent)]
     TomlDeserialize(#[from] toml::de::Error),
     #[error(transparent)]
+    Yaml(#[from] serde_norway::Error),
+    #[error(transparent)]
     ExecutorApprovalError(#[from] crate::approvals::ExecutorApprovalError),
     #[error(transparent)]
     CommandBuild(#[from] CommandBuildError),
+    #[error(transparent)]
+    CustomConfig(#[from] custom::CustomConfigError),
+    #[error(transparent)]
+    Plugin(#[from] plugin::PluginError),
+    #[error(
+        "{feature} needs {} ({capability}), which {agent} does not support",
+        .capability.description()
+    )]
+    CapabilityNotSupported {
+        agent: BaseCodingAgent,
+        capability: BaseAgentCapability,
+        feature: String,
+    },
+    #[error(
+        "Unknown model `{model}` for {agent}{}",
+        crate::models::suggestion_hint(.suggestions)
+    )]
+    UnknownModel {
+        agent: BaseCodingAgent,
+        model: String,
+        suggestions: Vec<String>,
+    },
     #[error("Executable `{program}` not found in PATH")]
     ExecutableNotFound { program: String },
     #[error("Setup helper not supported")]
     SetupHelperNotSupported,
+    #[error("Model listing not supported")]
+    ModelListingNotSupported,
+    #[error("Interrupting a running turn is not supported by this executor")]
+    InterruptNotSupported,
+    #[error("Switching the session mode is not supported by this executor")]
+    ModeSwitchNotSupported,
+    #[error("Execution has already finished")]
+    ExecutionFinished,
     #[error("Auth required: {0}")]
     AuthRequired(String),
 }
 
 #[enum_dispatch]
 #[derive(
-    Debug, Clone, Serialize, Deserialize, PartialEq, TS, Display, EnumDiscriminants, VariantNames,
+    Debug,
+    Clone,
+    Serialize,
+    Deserialize,
+    PartialEq,
+    TS,
+    JsonSchema,
+    Display,
+    EnumDiscriminants,
+    VariantNames,
 )]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 #[strum(serialize_all = "SCREAMING_SNAKE_CASE")]
 #[strum_discriminants(
     name(BaseCodingAgent),
     // Only add Hash; Eq/PartialEq are already provided by EnumDiscriminants.
-    derive(EnumString, Hash, strum_macros::Display, Serialize, Deserialize, TS, Type),
+    derive(
+        EnumString,
+        Hash,
+        strum_macros::Display,
+        Serialize,
+        Deserialize,
+        TS,
+        JsonSchema,
+        Type
+    ),
     strum(serialize_all = "SCREAMING_SNAKE_CASE"),
     ts(use_ts_enum),
     serde(rename_all = "SCREAMING_SNAKE_CASE"),
@@ -117,6 +242,11 @@ pub enum CodingAgent {
     QwenCode,
     Copilot,
     Droid,
+    Aider,
+    Goose,
+    Acp,
+    Custom,
+    Plugin,
     #[cfg(feature = "qa-mode")]
     QaMock(QaMockExecutor),
 }
@@ -157,6 +287,14 @@ impl CodingAgent {
                 self.preconfigured_mcp(),
                 false,
             ),
+            Self::Goose(_) => McpConfig::new(
+                vec!["extensions".to_string()],
+                serde_json::json!({
+                    "extensions": {}
+                }),
+                self.preconfigured_mcp(),
+                false,
+            ),
             _ => McpConfig::new(
                 vec!["mcpServers".to_string()],
                 serde_json::json!({
@@ -168,32 +306,136 @@ impl CodingAgent {
         }
     }
 
+    /// The profile's command overrides, including the `env` passed to the agent
+    pub fn cmd_overrides(&self) -> Option<&CmdOverrides> {
+        match self {
+            Self::ClaudeCode(agent) => Some(&agent.cmd),
+            Self::Amp(agent) => Some(&agent.cmd),
+            Self::Gemini(agent) => Some(&agent.cmd),
+            Self::Codex(agent) => Some(&agent.cmd),
+            Self::Opencode(agent) => Some(&agent.cmd),
+            Self::CursorAgent(agent) => Some(&agent.cmd),
+            Self::QwenCode(agent) => Some(&agent.cmd),
+            Self::Copilot(agent) => Some(&agent.cmd),
+            Self::Droid(agent) => Some(&agent.cmd),
+            Self::Aider(agent) => Some(&agent.cmd),
+            Self::Goose(agent) => Some(&agent.cmd),
+            Self::Acp(agent) => Some(&agent.cmd),
+            Self::Custom(agent) => Some(&agent.cmd),
+            Self::Plugin(agent) => Some(&agent.cmd),
+            #[cfg(feature = "qa-mode")]
+            Self::QaMock(_) => None,
+        }
+    }
+
     pub fn supports_mcp(&self) -> bool {
         self.default_mcp_config_path().is_some()
     }
 
     pub fn capabilities(&self) -> Vec<BaseAgentCapability> {
-        match self {
+        let mut capabilities = match self {
             Self::ClaudeCode(_) => vec![
                 BaseAgentCapability::SessionFork,
                 BaseAgentCapability::ContextUsage,
+                BaseAgentCapability::ImageInput,
+                BaseAgentCapability::LiveInput,
+                BaseAgentCapability::Interrupt,
+                BaseAgentCapability::ModeSwitch,
+                BaseAgentCapability::PlanOnly,
+                BaseAgentCapability::MessageReset,
+                BaseAgentCapability::Approvals,
+                BaseAgentCapability::SlashCommands,
+                BaseAgentCapability::ModelListing,
             ],
             Self::Opencode(_) => vec![
                 BaseAgentCapability::SessionFork,
                 BaseAgentCapability::ContextUsage,
+                BaseAgentCapability::Interrupt,
+                BaseAgentCapability::PlanOnly,
+                BaseAgentCapability::Approvals,
+                BaseAgentCapability::SlashCommands,
+                BaseAgentCapability::ModelListing,
             ],
             Self::Codex(_) => vec![
                 BaseAgentCapability::SessionFork,
                 BaseAgentCapability::SetupHelper,
                 BaseAgentCapability::ContextUsage,
+                BaseAgentCapability::ImageInput,
+                BaseAgentCapability::LiveInput,
+                BaseAgentCapability::Interrupt,
+                BaseAgentCapability::ModeSwitch,
+                BaseAgentCapability::PlanOnly,
+                BaseAgentCapability::Approvals,
+                BaseAgentCapability::Review,
+                BaseAgentCapability::SlashCommands,
+                BaseAgentCapability::ModelListing,
             ],
-            Self::Amp(_) | Self::Gemini(_) | Self::QwenCode(_) => {
-                vec![BaseAgentCapability::SessionFork]
-            }
-            Self::CursorAgent(_) => vec![BaseAgentCapability::SetupHelper],
-            Self::Copilot(_) | Self::Droid(_) => vec![],
+            Self::Gemini(_) => vec![
+                BaseAgentCapability::SessionFork,
+                BaseAgentCapability::LiveInput,
+                BaseAgentCapability::Interrupt,
+                BaseAgentCapability::ModeSwitch,
+                BaseAgentCapability::Approvals,
+                BaseAgentCapability::ModelListing,
+            ],
+            Self::QwenCode(_) => vec![
+                BaseAgentCapability::SessionFork,
+                BaseAgentCapability::LiveInput,
+                BaseAgentCapability::Interrupt,
+                BaseAgentCapability::ModeSwitch,
+                BaseAgentCapability::Approvals,
+            ],
+            Self::Amp(_) => vec![BaseAgentCapability::SessionFork],
+            Self::CursorAgent(_) => vec![
+                BaseAgentCapability::SessionFork,
+                BaseAgentCapability::SetupHelper,
+                BaseAgentCapability::ModelListing,
+            ],
+            Self::Copilot(_) => vec![
+                BaseAgentCapability::SessionFork,
+                BaseAgentCapability::LiveInput,
+                BaseAgentCapability::Interrupt,
+                BaseAgentCapability::ModeSwitch,
+                BaseAgentCapability::Approvals,
+            ],
+            Self::Droid(_) => vec![
+                BaseAgentCapability::SessionFork,
+                BaseAgentCapability::PlanOnly,
+            ],
+            Self::Aider(_) | Self::Goose(_) => vec![BaseAgentCapability::SessionFork],
+            Self::Acp(agent) => agent.capabilities(),
+            Self::Custom(agent) => agent.capabilities(),
+            Self::Plugin(agent) => agent.capabilities(),
             #[cfg(feature = "qa-mode")]
             Self::QaMock(_) => vec![], // QA mock doesn't need special capabilities
+        };
+        if self.supports_mcp() {
+            capabilities.push(BaseAgentCapability::Mcp);
+            // Codex's config only takes stdio servers, see `adapt_codex`
+            if !matches!(self, Self::Codex(_)) {
+                capabilities.push(BaseAgentCapability::HttpMcp);
+            }
+        }
+        capabilities
+    }
+
+    /// Check features requested for a run against [`Self::capabilities`] before spawning;
+    /// each is a capability and the flag or feature that asked for it
+    pub fn require_capabilities(
+        &self,
+        requested: &[(BaseAgentCapability, &str)],
+    ) -> Result<(), ExecutorError> {
+        let capabilities = self.capabilities();
+        match requested
+            .iter()
+            .find(|(capability, _)| !capabilities.contains(capability))
+        {
+            Some((capability, feature)) => Err(ExecutorError::CapabilityNotSupported {
+                agent: BaseCodingAgent::from(self),
+                capability: *capability,
+                feature: feature.to_string(),
+            }),
+            None => Ok(()),
         }
     }
 }
@@ -221,6 +463,13 @@ impl AvailabilityInfo {
 pub trait StandardCodingAgentExecutor {
     fn use_approvals(&mut self, _approvals: Arc<dyn ExecutorApprovalService>) {}
 
+    /// Ask the approval service before every file edit and command, overriding profile settings
+    /// that skip approvals (used for protected paths). Returns false when the agent cannot be
+    /// made to ask, so changes can only be caught after the run.
+    fn require_approvals(&mut self) -> bool {
+        false
+    }
+
     async fn available_slash_commands(
         &self,
         _workdir: &Path,
@@ -263,6 +512,39 @@ pub trait StandardCodingAgentExecutor {
         }
     }
 
+    /// Interrupt the running turn of an execution spawned by this executor, optionally steering
+    /// it with a new instruction. The session is kept; the steer is recorded in the normalized
+    /// logs as a `SystemMessage` (and a `UserMessage` for the instruction).
+    async fn interrupt(
+        &self,
+        spawned: &SpawnedChild,
+        instruction: Option<String>,
+    ) -> Result<(), ExecutorError> {
+        let input = spawned
+            .live_input
+            .as_ref()
+            .ok_or(ExecutorError::InterruptNotSupported)?;
+        input
+            .send(LiveInput::Interrupt { instruction })
+            .map_err(|_| ExecutorError::ExecutionFinished)
+    }
+
+    /// Switch the permission / plan mode of a running execution spawned by this executor.
+    /// Agents apply it to the running turn where they can, otherwise from the next turn.
+    async fn set_mode(
+        &self,
+        spawned: &SpawnedChild,
+        mode: SessionMode,
+    ) -> Result<(), ExecutorError> {
+        let input = spawned
+            .live_input
+            .as_ref()
+            .ok_or(ExecutorError::ModeSwitchNotSupported)?;
+        input
+            .send(LiveInput::SetMode(mode))
+            .map_err(|_| ExecutorError::ExecutionFinished)
+    }
+
     fn normalize_logs(&self, _raw_logs_event_store: Arc<MsgStore>, _worktree_path: &Path);
 
     // MCP configuration methods
@@ -272,6 +554,20 @@ pub trait StandardCodingAgentExecutor {
         Err(ExecutorError::SetupHelperNotSupported)
     }
 
+    /// Models this agent can run, queried from the agent where it can list them
+    async fn discover_models(&self, _current_dir: &Path) -> Result<ModelCatalog, ExecutorError> {
+        Err(ExecutorError::ModelListingNotSupported)
+    }
+
+    /// Whether the agent's CLI is authenticated (`doctor`). By default only a stored login
+    /// found by [`Self::get_availability_info`] counts; agents that can ask override this
+    async fn auth_status(&self, _current_dir: &Path) -> Result<AuthStatus, ExecutorError> {
+        Ok(match self.get_availability_info() {
+            AvailabilityInfo::LoginDetected { .. } => AuthStatus::LoggedIn { method: None },
+            _ => AuthStatus::Unknown,
+        })
+    }
+
     fn get_availability_info(&self) -> AvailabilityInfo {
         let config_files_found = self
             .default_mcp_config_path()
@@ -304,6 +600,58 @@ pub type ExecutorExitSignal = tokio::sync::oneshot::Receiver<ExecutorExitResult>
 /// When cancelled, the executor should attempt to cancel gracefully before being killed.
 pub type CancellationToken = tokio_util::sync::CancellationToken;
 
+/// Input sent to a running execution
+#[derive(Debug, Clone)]
+pub enum LiveInput {
+    /// A further user turn for a live (interactive) session; queued if a turn is still running
+    UserMessage(String),
+    /// Interrupt the running turn, optionally steering the agent with a new instruction.
+    /// The session is kept.
+    Interrupt { instruction: Option<String> },
+    /// Switch the permission / plan mode without restarting the session
+    SetMode(SessionMode),
+}
+
+/// Agent-neutral permission mode that can be switched mid-session, e.g. plan first and then
+/// switch to `Bypass` to execute the approved plan in the same session
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS, JsonSchema, Display, EnumString,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case", ascii_case_insensitive)]
+pub enum SessionMode {
+    /// Read-only: the agent explores and proposes a plan without changing the workspace
+    Plan,
+    /// The agent asks for approval before edits and commands
+    Default,
+    /// File edits are applied without asking; other actions still need approval
+    AcceptEdits,
+    /// Everything runs without asking
+    Bypass,
+}
+
+/// Container → Executor: input for a running execution.
+/// For live sessions, dropping the sender ends the session once the running turn completes.
+pub type LiveInputSender = tokio::sync::mpsc::UnboundedSender<LiveInput>;
+pub type LiveInputReceiver = tokio::sync::mpsc::UnboundedReceiver<LiveInput>;
+
+pub fn live_input_channel() -> (LiveInputSender, LiveInputReceiver) {
+    tokio::sync::mpsc::unbounded_channel()
+}
+
+/// Receive the next live input; once the sender is dropped this clears `rx` and pends forever,
+/// so it can be used as a `tokio::select!` branch alongside the running turn
+pub async fn recv_live_input(rx: &mut Option<LiveInputReceiver>) -> Option<LiveInput> {
+    let Some(receiver) = rx.as_mut() else {
+        return std::future::pending().await;
+    };
+    let input = receiver.recv().await;
+    if input.is_none() {
+        *rx = None;
+    }
+    input
+}
+
 #[derive(Debug)]
 pub struct SpawnedChild {
     pub child: AsyncGroupChild,
@@ -311,6 +659,9 @@ pub struct SpawnedChild {
     pub exit_signal: Option<ExecutorExitSignal>,
     /// Container → Executor: signals when container wants to cancel the execution
     pub cancel: Option<CancellationToken>,
+    /// Container → Executor: interrupts and, for [`ExecutionEnv::interactive`] sessions,
+    /// further user turns (`None` if the executor supports neither)
+    pub live_input: Option<LiveInputSender>,
 }
 
 impl From<AsyncGroupChild> for SpawnedChild {
@@ -319,6 +670,7 @@ impl From<AsyncGroupChild> for SpawnedChild {
             child,
             exit_signal: None,
             cancel: None,
+            live_input: None,
         }
     }
 }
@@ -405,4 +757,41 @@ mod tests {
         assert!(result.is_ok(), "CURSOR should deserialize via serde");
         assert_eq!(result.unwrap(), BaseCodingAgent::CursorAgent);
     }
+
+    #[test]
+    fn test_session_mode_parsing() {
+        assert_eq!(
+            SessionMode::from_str("accept_edits").unwrap(),
+            SessionMode::AcceptEdits
+        );
+        assert_eq!(SessionMode::from_str("PLAN").unwrap(), SessionMode::Plan);
+        assert!(SessionMode::from_str("yolo").is_err());
+
+        let mode: SessionMode = serde_json::from_str(r#""bypass""#).unwrap();
+        assert_eq!(mode, SessionMode::Bypass);
+        assert_eq!(SessionMode::AcceptEdits.to_string(), "accept_edits");
+    }
+
+    #[test]
+    fn test_require_capabilities() {
+        let agent: CodingAgent = serde_json::from_value(serde_json::json!({"AIDER": {}})).unwrap();
+        assert!(
+            agent
+                .require_capabilities(&[(BaseAgentCapability::SessionFork, "--follow-up")])
+                .is_ok()
+        );
+
+        let err = agent
+            .require_capabilities(&[
+                (BaseAgentCapability::SessionFork, "--follow-up"),
+                (BaseAgentCapability::MessageReset, "--rewind-to"),
+            ])
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "--rewind-to needs message reset (MESSAGE_RESET), which AIDER does not support"
+        );
+        // Aider has no MCP config file
+        assert!(!agent.capabilities().contains(&BaseAgentCapability::Mcp));
+    }
 }
@@ -14,12 +14,13 @@ use workspace_utils::msg_store::MsgStore;
 use crate::{
     approvals::ExecutorApprovalService,
     command::{CmdOverrides, CommandBuildError, CommandBuilder, apply_overrides},
-    env::ExecutionEnv,
+    env::{ExecutionEnv, RepoContext},
     executors::{
         AppendPrompt, AvailabilityInfo, ExecutorError, ExecutorExitResult, SpawnedChild,
-        StandardCodingAgentExecutor, opencode::types::OpencodeExecutorEvent,
+        StandardCodingAgentExecutor, live_input_channel, opencode::types::OpencodeExecutorEvent,
     },
     logs::utils::patch,
+    models::ModelCatalog,
     stdout_dup::create_stdout_pipe_writer,
 };
 
@@ -79,8 +80,12 @@ impl Drop for OpencodeServer {
 type ServerPassword = String;
 
 impl Opencode {
+    pub fn base_command() -> &'static str {
+        "npx -y opencode-ai@1.1.59"
+    }
+
     fn build_command_builder(&self) -> Result<CommandBuilder, CommandBuildError> {
-        let builder = CommandBuilder::new("npx -y opencode-ai@1.1.59")
+        let builder = CommandBuilder::new(Self::base_command())
             // Pass hostname/port as separate args so OpenCode treats them as explicitly set
             // (it checks `process.argv.includes(\"--port\")` / `\"--hostname\"`).
             .extend_params(["serve", "--hostname", "127.0.0.1", "--port", "0"]);
@@ -170,6 +175,7 @@ impl Opencode {
 
         let (exit_signal_tx, exit_signal_rx) = tokio::sync::oneshot::channel();
         let cancel = tokio_util::sync::CancellationToken::new();
+        let (live_input, live_rx) = live_input_channel();
 
         // Prepare config values that will be moved into the spawned task
         let directory = current_dir.to_string_lossy().to_string();
@@ -224,7 +230,7 @@ impl Opencode {
                 Some(command) => {
                     run_slash_command(config, log_writer.clone(), command, cancel_for_task).await
                 }
-                None => run_session(config, log_writer.clone(), cancel_for_task).await,
+                None => run_session(config, log_writer.clone(), live_rx, cancel_for_task).await,
             };
             let exit_result = match result {
                 Ok(()) => ExecutorExitResult::Success,
@@ -242,6 +248,7 @@ impl Opencode {
             child,
             exit_signal: Some(exit_signal_rx),
             cancel: Some(cancel),
+            live_input: Some(live_input),
         })
     }
 }
@@ -314,6 +321,11 @@ impl StandardCodingAgentExecutor for Opencode {
         self.approvals = Some(approvals);
     }
 
+    fn require_approvals(&mut self) -> bool {
+        self.auto_approve = false;
+        true
+    }
+
     async fn available_slash_commands(
         &self,
         current_dir: &Path,
@@ -393,6 +405,14 @@ impl StandardCodingAgentExecutor for Opencode {
         }
     }
 
+    async fn discover_models(&self, current_dir: &Path) -> Result<ModelCatalog, ExecutorError> {
+        let env = ExecutionEnv::new(RepoContext::default(), false, String::new());
+        let server = self.spawn_server(current_dir, &env).await?;
+        let providers = sdk::discover_providers(&server, current_dir).await?;
+        let models = models::configured_models(&providers);
+        Ok(ModelCatalog::discovered(models))
+    }
+
     fn get_availability_info(&self) -> AvailabilityInfo {
         let mcp_config_found = self
             .default_mcp_config_path()
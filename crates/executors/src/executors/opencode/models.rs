@@ -5,9 +5,12 @@ use std::{
 
 use serde_json::Value;
 
-use crate::executors::opencode::{
-    sdk::EventStreamContext,
-    types::{MessageRole, OpencodeExecutorEvent, ProviderListResponse, SdkEvent},
+use crate::{
+    executors::opencode::{
+        sdk::{ConfigProvidersResponse, EventStreamContext},
+        types::{MessageRole, OpencodeExecutorEvent, ProviderListResponse, SdkEvent},
+    },
+    models::ModelInfo,
 };
 
 type ProviderId = String;
@@ -201,3 +204,64 @@ async fn fetch_model_context_windows(
 
     Some(windows)
 }
+
+/// Configured providers' models as `provider/model` ids, with their context windows
+pub(super) fn configured_models(providers: &ConfigProvidersResponse) -> Vec<ModelInfo> {
+    let mut models: Vec<_> = providers
+        .providers
+        .iter()
+        .flat_map(|provider| {
+            let default_model = providers.default.get(&provider.id);
+            provider
+                .models
+                .iter()
+                .map(move |(model_id, info)| ModelInfo {
+                    id: format!("{}/{model_id}", provider.id),
+                    display_name: info.get("name").and_then(Value::as_str).map(str::to_string),
+                    context_window: info
+                        .pointer("/limit/context")
+                        .and_then(Value::as_u64)
+                        .filter(|context| *context > 0)
+                        .and_then(|context| u32::try_from(context).ok()),
+                    is_default: default_model == Some(model_id),
+                })
+        })
+        .collect();
+    models.sort_by(|a, b| a.id.cmp(&b.id));
+    models
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn configured_models_use_provider_ids_and_limits() {
+        let providers: ConfigProvidersResponse = serde_json::from_value(json!({
+            "providers": [{
+                "id": "anthropic",
+                "name": "Anthropic",
+                "models": {
+                    "claude-sonnet-4-5": {
+                        "name": "Claude Sonnet 4.5",
+                        "limit": { "context": 200000, "output": 64000 }
+                    },
+                    "claude-haiku-4-5": { "name": "Claude Haiku 4.5", "limit": { "context": 0 } }
+                }
+            }],
+            "default": { "anthropic": "claude-sonnet-4-5" }
+        }))
+        .unwrap();
+
+        let models = configured_models(&providers);
+        assert_eq!(models[0].id, "anthropic/claude-haiku-4-5");
+        assert_eq!(models[0].context_window, None);
+        assert!(!models[0].is_default);
+        assert_eq!(models[1].id, "anthropic/claude-sonnet-4-5");
+        assert_eq!(models[1].display_name.as_deref(), Some("Claude Sonnet 4.5"));
+        assert_eq!(models[1].context_window, Some(200_000));
+        assert!(models[1].is_default);
+    }
+}
@@ -14,7 +14,7 @@ use crate::{
     logs::{
         ActionType, CommandExitStatus, CommandRunResult, FileChange, NormalizedEntry,
         NormalizedEntryError, NormalizedEntryType, TodoItem, TokenUsageInfo, ToolResult,
-        ToolStatus,
+        ToolStatus, control_marker_entries,
         stderr_processor::normalize_stderr_logs,
         utils::{
             EntryIndexProvider,
@@ -43,6 +43,13 @@ pub fn normalize_logs(msg_store: Arc<MsgStore>, worktree_path: &Path) {
 
         let mut stdout_lines = msg_store.stdout_lines_stream();
         while let Some(Ok(line)) = stdout_lines.next().await {
+            if let Some(entries) = control_marker_entries(&line) {
+                for entry in entries {
+                    add_normalized_entry(&msg_store, &entry_index, entry);
+                }
+                continue;
+            }
+
             let Some(event) = parse_event(&line) else {
                 let trimmed = line.trim();
                 if trimmed.is_empty() {
@@ -26,9 +26,11 @@ use crate::{
     approvals::{ExecutorApprovalError, ExecutorApprovalService},
     env::RepoContext,
     executors::{
-        ExecutorError,
+        ExecutorError, LiveInput, LiveInputReceiver,
         opencode::{OpencodeServer, models::maybe_emit_token_usage},
+        recv_live_input,
     },
+    logs::{ModeMarker, SteerMarker},
 };
 
 #[derive(Clone)]
@@ -54,6 +56,10 @@ impl LogWriter {
             .await
     }
 
+    pub async fn log_steer(&self, marker: &SteerMarker) -> Result<(), ExecutorError> {
+        self.log_raw(&marker.raw()).await
+    }
+
     pub async fn log_slash_command_result(&self, message: String) -> Result<(), ExecutorError> {
         self.log_event(&OpencodeExecutorEvent::SlashCommandResult { message })
             .await
@@ -215,6 +221,7 @@ pub enum ControlEvent {
 pub async fn run_session(
     config: RunConfig,
     log_writer: LogWriter,
+    live_rx: LiveInputReceiver,
     cancel: CancellationToken,
 ) -> Result<(), ExecutorError> {
     let client = reqwest::Client::builder()
@@ -225,7 +232,7 @@ pub async fn run_session(
         .build()
         .map_err(|err| ExecutorError::Io(io::Error::other(err)))?;
 
-    run_session_inner(config, log_writer, client, cancel).await
+    run_session_inner(config, log_writer, client, live_rx, cancel).await
 }
 
 pub(super) async fn discover_commands(
@@ -242,6 +249,20 @@ pub(super) async fn discover_commands(
     list_commands(&client, &server.base_url, &directory).await
 }
 
+pub(super) async fn discover_providers(
+    server: &OpencodeServer,
+    directory: &Path,
+) -> Result<ConfigProvidersResponse, ExecutorError> {
+    let directory = directory.to_string_lossy();
+    let client = reqwest::Client::builder()
+        .default_headers(build_default_headers(&directory, &server.server_password))
+        .build()
+        .map_err(|err| ExecutorError::Io(io::Error::other(err)))?;
+
+    wait_for_health(&client, &server.base_url).await?;
+    list_config_providers(&client, &server.base_url, &directory).await
+}
+
 pub async fn run_slash_command(
     config: RunConfig,
     log_writer: LogWriter,
@@ -263,6 +284,7 @@ async fn run_session_inner(
     config: RunConfig,
     log_writer: LogWriter,
     client: reqwest::Client,
+    live_rx: LiveInputReceiver,
     cancel: CancellationToken,
 ) -> Result<(), ExecutorError> {
     tokio::select! {
@@ -313,17 +335,54 @@ async fn run_session_inner(
         event_resp,
     ));
 
-    let prompt_fut = Box::pin(prompt(
-        &client,
-        &config.base_url,
-        &config.directory,
-        &session_id,
-        &config.prompt,
-        model.clone(),
-        config.model_variant.clone(),
-        config.agent.clone(),
-    ));
-    let prompt_result = run_request_with_control(prompt_fut, &mut control_rx, cancel.clone()).await;
+    // An interrupt aborts the running message; a steer instruction becomes the next prompt
+    let mut live_rx = Some(live_rx);
+    let mut next_prompt = Some(config.prompt.clone());
+    let mut prompt_result = Ok(());
+    while let Some(text) = next_prompt.take() {
+        let prompt_fut = Box::pin(prompt(
+            &client,
+            &config.base_url,
+            &config.directory,
+            &session_id,
+            &text,
+            model.clone(),
+            config.model_variant.clone(),
+            config.agent.clone(),
+        ));
+        let request = run_request_with_control(prompt_fut, &mut control_rx, cancel.clone());
+        tokio::pin!(request);
+        let mut interrupted = false;
+        prompt_result = loop {
+            tokio::select! {
+                res = &mut request => break res,
+                Some(input) = recv_live_input(&mut live_rx) => match input {
+                    LiveInput::Interrupt { instruction } => {
+                        let marker = SteerMarker::new(instruction.clone());
+                        let _ = log_writer.log_steer(&marker).await;
+                        send_abort(&client, &config.base_url, &config.directory, &session_id)
+                            .await;
+                        interrupted = true;
+                        next_prompt = instruction;
+                    }
+                    LiveInput::UserMessage(_) => {
+                        tracing::warn!("OpenCode does not accept live messages; ignoring");
+                    }
+                    LiveInput::SetMode(mode) => {
+                        let marker = ModeMarker::failed(mode, "not supported by OpenCode");
+                        let _ = log_writer.log_raw(&marker.raw()).await;
+                    }
+                },
+            }
+        };
+        if interrupted {
+            // The aborted request is expected to fail
+            prompt_result = Ok(());
+        }
+        if prompt_result.is_err() || cancel.is_cancelled() {
+            break;
+        }
+    }
 
     if cancel.is_cancelled() {
         send_abort(&client, &config.base_url, &config.directory, &session_id).await;
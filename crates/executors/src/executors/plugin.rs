@@ -0,0 +1,700 @@
+//! Out-of-process executors. A plugin is an executable in the plugins directory
+//! ([`workspace_utils::assets::plugins_dir`]) that speaks line-delimited JSON-RPC 2.0 over stdio.
+//! Each call starts the plugin, sends one message and closes its stdin:
+//!
+//! - `availability` (request): returns an [`AvailabilityInfo`]
+//! - `spawn` / `spawn_follow_up` (request, params [`SpawnParams`]): returns a [`SpawnPlan`], the
+//!   command code-marshal runs as the agent process
+//! - `normalize` (notification, params [`NormalizeParams`]): followed by one `output`
+//!   notification per line the agent printed ([`OutputParams`]); the plugin answers with
+//!   `entry` ([`EntryParams`]) and `session_id` ([`SessionIdParams`]) notifications until its
+//!   stdin closes
+//!
+//! Entries are checked against the [`NormalizedEntry`] schema; invalid ones are reported as
+//! errors in the log instead. A plugin runs as `-a PLUGIN:<NAME>` without any profile, or
+//! through a `PLUGIN` profile variant that names it and passes it `config`.
+
+use std::{
+    collections::HashMap,
+    io::{Read as _, Write as _},
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::Arc,
+    time::Duration,
+};
+
+use async_trait::async_trait;
+use command_group::AsyncCommandGroup;
+use futures::{StreamExt, stream};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use serde_json::Value;
+use thiserror::Error;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    process::Command,
+};
+use ts_rs::TS;
+use workspace_utils::msg_store::MsgStore;
+
+use crate::{
+    command::{CmdOverrides, CommandBuilder, apply_overrides},
+    env::{ExecutionEnv, RepoContext},
+    executors::{
+        AppendPrompt, AvailabilityInfo, BaseAgentCapability, CodingAgent, ExecutorError,
+        SpawnedChild, StandardCodingAgentExecutor,
+    },
+    logs::{
+        NormalizedEntry, NormalizedEntryError, NormalizedEntryType,
+        utils::{ConversationPatch, EntryIndexProvider},
+    },
+};
+
+const JSONRPC_VERSION: &str = "2.0";
+/// How long `spawn` and `spawn_follow_up` may take before the plugin is killed
+const PLUGIN_CALL_TIMEOUT: Duration = Duration::from_secs(30);
+/// `availability` runs for `--list-agents` and `doctor`, so a stuck plugin is given up on sooner
+const PLUGIN_AVAILABILITY_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Error)]
+pub enum PluginError {
+    #[error("plugin `{0}` not found in the plugins directory")]
+    NotFound(String),
+    #[error("plugin I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("plugin sent invalid JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("plugin returned error {code}: {message}")]
+    Rpc { code: i64, message: String },
+    #[error("plugin exited without answering `{0}`")]
+    NoResponse(String),
+    #[error("plugin spawn plan has an empty command")]
+    EmptyCommand,
+    #[error("plugin did not answer `{method}` within {}s", timeout.as_secs())]
+    Timeout { method: String, timeout: Duration },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, TS, JsonSchema)]
+pub struct Plugin {
+    #[serde(default)]
+    pub append_prompt: AppendPrompt,
+    #[schemars(
+        title = "Plugin",
+        description = "Executable name in the plugins directory, or a path to the plugin"
+    )]
+    pub plugin: String,
+    #[schemars(
+        title = "Config",
+        description = "Plugin-specific settings, passed along with every call"
+    )]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub config: Option<Value>,
+    #[serde(flatten)]
+    pub cmd: CmdOverrides,
+}
+
+/// Params of `spawn` and `spawn_follow_up`
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SpawnParams {
+    pub current_dir: PathBuf,
+    pub prompt: String,
+    /// Only for `spawn_follow_up`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    #[serde(default)]
+    pub config: Option<Value>,
+}
+
+/// Result of `spawn` and `spawn_follow_up`: how to start the agent
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SpawnPlan {
+    /// Program and arguments
+    pub command: Vec<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Written to the agent's stdin, which is then closed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stdin: Option<String>,
+}
+
+/// Params of the `normalize` notification
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct NormalizeParams {
+    pub worktree_path: PathBuf,
+    #[serde(default)]
+    pub config: Option<Value>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum OutputStream {
+    Stdout,
+    Stderr,
+}
+
+/// Params of the `output` notification: one line the agent printed
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct OutputParams {
+    pub stream: OutputStream,
+    pub line: String,
+}
+
+/// Params of the `entry` notification; a repeated `index` replaces that entry
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct EntryParams {
+    pub index: u64,
+    pub entry: NormalizedEntry,
+}
+
+/// Params of the `session_id` notification
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct SessionIdParams {
+    pub session_id: String,
+}
+
+#[derive(Serialize)]
+struct RpcOutgoing<'a, P> {
+    jsonrpc: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<u64>,
+    method: &'a str,
+    params: P,
+}
+
+impl<'a, P: Serialize> RpcOutgoing<'a, P> {
+    fn request(method: &'a str, params: P) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION,
+            id: Some(1),
+            method,
+            params,
+        }
+    }
+
+    fn notification(method: &'a str, params: P) -> Self {
+        Self {
+            jsonrpc: JSONRPC_VERSION,
+            id: None,
+            method,
+            params,
+        }
+    }
+
+    fn to_line(&self) -> Result<String, serde_json::Error> {
+        let mut line = serde_json::to_string(self)?;
+        line.push('\n');
+        Ok(line)
+    }
+}
+
+#[derive(Deserialize)]
+struct RpcIncoming {
+    #[serde(default)]
+    id: Option<Value>,
+    #[serde(default)]
+    method: Option<String>,
+    #[serde(default)]
+    params: Value,
+    #[serde(default)]
+    result: Option<Value>,
+    #[serde(default)]
+    error: Option<RpcErrorObject>,
+}
+
+#[derive(Deserialize)]
+struct RpcErrorObject {
+    code: i64,
+    message: String,
+}
+
+/// The response to the request (id 1) among the lines a plugin printed
+fn parse_response<R: DeserializeOwned>(stdout: &[u8], method: &str) -> Result<R, PluginError> {
+    let response = String::from_utf8_lossy(stdout)
+        .lines()
+        .filter_map(|line| serde_json::from_str::<RpcIncoming>(line).ok())
+        .find(|message| message.id.as_ref().and_then(Value::as_u64) == Some(1))
+        .ok_or_else(|| PluginError::NoResponse(method.to_string()))?;
+    if let Some(error) = response.error {
+        return Err(PluginError::Rpc {
+            code: error.code,
+            message: error.message,
+        });
+    }
+    Ok(serde_json::from_value(
+        response.result.unwrap_or(Value::Null),
+    )?)
+}
+
+/// Plugins in the plugins directory, by name, sorted
+pub fn discover_plugins() -> Vec<(String, PathBuf)> {
+    let Ok(entries) = std::fs::read_dir(workspace_utils::assets::plugins_dir()) else {
+        return Vec::new();
+    };
+    let mut plugins: Vec<(String, PathBuf)> = entries
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| is_executable(path))
+        .filter_map(|path| {
+            let name = path.file_stem()?.to_str()?.to_string();
+            Some((name, path))
+        })
+        .collect();
+    plugins.sort();
+    plugins
+}
+
+#[cfg(unix)]
+fn is_executable(path: &Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+    path.metadata()
+        .is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &Path) -> bool {
+    path.is_file()
+}
+
+/// Profile variant name of a plugin, e.g. `my-agent` -> `MY_AGENT`
+fn variant_name(plugin: &str) -> String {
+    plugin
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// The executor for a discovered plugin without a profile: the plugin whose name matches
+/// `variant`, or the first plugin for the default variant
+pub fn discovered_agent(variant: Option<&str>) -> Option<CodingAgent> {
+    let plugins = discover_plugins();
+    let (name, _) = match variant.filter(|variant| *variant != "DEFAULT") {
+        Some(variant) => plugins
+            .into_iter()
+            .find(|(name, _)| variant_name(name) == variant_name(variant))?,
+        None => plugins.into_iter().next()?,
+    };
+    Some(CodingAgent::Plugin(Plugin::named(name)))
+}
+
+impl Plugin {
+    pub fn named(plugin: impl Into<String>) -> Self {
+        Self {
+            append_prompt: AppendPrompt::default(),
+            plugin: plugin.into(),
+            config: None,
+            cmd: CmdOverrides::default(),
+        }
+    }
+
+    pub fn path(&self) -> Result<PathBuf, PluginError> {
+        let path = Path::new(&self.plugin);
+        if path.components().count() > 1 {
+            return Ok(path.to_path_buf());
+        }
+        discover_plugins()
+            .into_iter()
+            .find(|(name, _)| *name == self.plugin)
+            .map(|(_, path)| path)
+            .ok_or_else(|| PluginError::NotFound(self.plugin.clone()))
+    }
+
+    pub fn capabilities(&self) -> Vec<BaseAgentCapability> {
+        vec![BaseAgentCapability::SessionFork]
+    }
+
+    /// The plugin process, with the profile's `env` applied like for the agent it plans
+    fn plugin_command(
+        &self,
+        current_dir: &Path,
+        env: &ExecutionEnv,
+    ) -> Result<Command, PluginError> {
+        let mut command = Command::new(self.path()?);
+        command
+            .kill_on_drop(true)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .current_dir(current_dir);
+        env.clone()
+            .with_profile(&self.cmd)
+            .apply_to_command(&mut command);
+        Ok(command)
+    }
+
+    /// Send one request and wait for the plugin to answer and exit
+    async fn call<P: Serialize, R: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: P,
+        current_dir: &Path,
+        env: &ExecutionEnv,
+    ) -> Result<R, PluginError> {
+        self.call_with_timeout(method, params, current_dir, env, PLUGIN_CALL_TIMEOUT)
+            .await
+    }
+
+    async fn call_with_timeout<P: Serialize, R: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: P,
+        current_dir: &Path,
+        env: &ExecutionEnv,
+        timeout: Duration,
+    ) -> Result<R, PluginError> {
+        let request = RpcOutgoing::request(method, params).to_line()?;
+        let mut command = self.plugin_command(current_dir, env)?;
+        let exchange = async move {
+            let mut child = command.spawn()?;
+            if let Some(mut stdin) = child.stdin.take() {
+                stdin.write_all(request.as_bytes()).await?;
+                stdin.shutdown().await?;
+            }
+            child.wait_with_output().await
+        };
+        // On timeout the future, and with it the child (kill_on_drop), is dropped
+        let output = tokio::time::timeout(timeout, exchange)
+            .await
+            .map_err(|_| PluginError::Timeout {
+                method: method.to_string(),
+                timeout,
+            })??;
+        parse_response(&output.stdout, method)
+    }
+
+    /// The synchronous `availability` call behind [`Self::get_availability_info`]; the plugin
+    /// is killed if it has not answered within `timeout`
+    fn query_availability(&self, timeout: Duration) -> Result<AvailabilityInfo, PluginError> {
+        let request = RpcOutgoing::request("availability", &self.config).to_line()?;
+        let mut command = std::process::Command::new(self.path()?);
+        command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null());
+        if let Some(env) = &self.cmd.env {
+            command.envs(env);
+        }
+        let mut child = command.spawn()?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(request.as_bytes())?;
+        }
+        let Some(mut stdout) = child.stdout.take() else {
+            return Err(PluginError::NoResponse("availability".to_string()));
+        };
+        let (tx, rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || {
+            let mut output = Vec::new();
+            let _ = stdout.read_to_end(&mut output);
+            let _ = tx.send(output);
+        });
+        let output = rx.recv_timeout(timeout);
+        if output.is_err() {
+            let _ = child.kill();
+        }
+        let _ = child.wait();
+        let output = output.map_err(|_| PluginError::Timeout {
+            method: "availability".to_string(),
+            timeout,
+        })?;
+        parse_response(&output, "availability")
+    }
+
+    async fn spawn_plugin(
+        &self,
+        current_dir: &Path,
+        prompt: &str,
+        session_id: Option<&str>,
+        env: &ExecutionEnv,
+    ) -> Result<SpawnedChild, ExecutorError> {
+        let params = SpawnParams {
+            current_dir: current_dir.to_path_buf(),
+            prompt: self.append_prompt.combine_prompt(prompt),
+            session_id: session_id.map(str::to_string),
+            config: self.config.clone(),
+        };
+        let method = match session_id {
+            Some(_) => "spawn_follow_up",
+            None => "spawn",
+        };
+        let plan: SpawnPlan = self.call(method, params, current_dir, env).await?;
+
+        let base = shlex::try_join(plan.command.iter().map(String::as_str))
+            .map_err(crate::command::CommandBuildError::from)?;
+        if base.is_empty() {
+            return Err(PluginError::EmptyCommand.into());
+        }
+        let (program_path, args) = apply_overrides(CommandBuilder::new(base), &self.cmd)?
+            .build_initial()?
+            .into_resolved()
+            .await?;
+
+        let mut command = Command::new(program_path);
+        command
+            .kill_on_drop(true)
+            .stdin(if plan.stdin.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .current_dir(current_dir)
+            .args(args);
+        env.clone()
+            .with_profile(&self.cmd)
+            .apply_to_command(&mut command);
+        command.envs(&plan.env);
+
+        let mut child = command.group_spawn()?;
+        if let (Some(mut stdin), Some(input)) = (child.inner().stdin.take(), plan.stdin) {
+            stdin.write_all(input.as_bytes()).await?;
+            stdin.shutdown().await?;
+        }
+        Ok(child.into())
+    }
+
+    /// Stream the agent's output through the plugin's `normalize` and push its entries
+    async fn run_normalizer(
+        &self,
+        msg_store: Arc<MsgStore>,
+        worktree_path: PathBuf,
+        entry_index: EntryIndexProvider,
+    ) -> Result<(), PluginError> {
+        // The run's env is not passed to normalizers; the plugin inherits ours plus the profile's
+        let env = ExecutionEnv::new(RepoContext::default(), false, String::new());
+        let mut child = self.plugin_command(&worktree_path, &env)?.spawn()?;
+        let (Some(mut stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            return Err(PluginError::NoResponse("normalize".to_string()));
+        };
+
+        let params = NormalizeParams {
+            worktree_path,
+            config: self.config.clone(),
+        };
+        stdin
+            .write_all(
+                RpcOutgoing::notification("normalize", params)
+                    .to_line()?
+                    .as_bytes(),
+            )
+            .await?;
+        let output = stream::select(
+            msg_store
+                .stdout_lines_stream()
+                .map(|line| (OutputStream::Stdout, line)),
+            msg_store
+                .stderr_lines_stream()
+                .map(|line| (OutputStream::Stderr, line)),
+        );
+        let forward = tokio::spawn(async move {
+            futures::pin_mut!(output);
+            while let Some((stream, Ok(line))) = output.next().await {
+                let message = RpcOutgoing::notification("output", OutputParams { stream, line });
+                let Ok(line) = message.to_line() else {
+                    continue;
+                };
+                if stdin.write_all(line.as_bytes()).await.is_err() {
+                    break;
+                }
+            }
+            // Closing stdin tells the plugin the output is complete
+            let _ = stdin.shutdown().await;
+        });
+
+        let mut indices: HashMap<u64, usize> = HashMap::new();
+        let mut lines = BufReader::new(stdout).lines();
+        while let Some(line) = lines.next_line().await? {
+            let Ok(message) = serde_json::from_str::<RpcIncoming>(&line) else {
+                tracing::debug!("Ignoring non-JSON-RPC plugin output: {line}");
+                continue;
+            };
+            match message.method.as_deref() {
+                Some("entry") => match serde_json::from_value::<EntryParams>(message.params) {
+                    Ok(EntryParams { index, entry }) => match indices.get(&index) {
+                        Some(&id) => msg_store.push_patch(ConversationPatch::replace(id, entry)),
+                        None => {
+                            let id = entry_index.next();
+                            indices.insert(index, id);
+                            msg_store
+                                .push_patch(ConversationPatch::add_normalized_entry(id, entry));
+                        }
+                    },
+                    Err(err) => {
+                        let entry = plugin_error_entry(format!(
+                            "Plugin `{}` sent an invalid entry: {err}",
+                            self.plugin
+                        ));
+                        msg_store.push_patch(ConversationPatch::add_normalized_entry(
+                            entry_index.next(),
+                            entry,
+                        ));
+                    }
+                },
+                Some("session_id") => {
+                    if let Ok(params) = serde_json::from_value::<SessionIdParams>(message.params) {
+                        msg_store.push_session_id(params.session_id);
+                    }
+                }
+                other => tracing::debug!("Ignoring plugin message {other:?}"),
+            }
+        }
+        forward.abort();
+        let _ = child.wait().await;
+        Ok(())
+    }
+}
+
+fn plugin_error_entry(content: String) -> NormalizedEntry {
+    NormalizedEntry {
+        timestamp: None,
+        entry_type: NormalizedEntryType::ErrorMessage {
+            error_type: NormalizedEntryError::Other,
+        },
+        content,
+        metadata: None,
+    }
+}
+
+#[async_trait]
+impl StandardCodingAgentExecutor for Plugin {
+    async fn spawn(
+        &self,
+        current_dir: &Path,
+        prompt: &str,
+        env: &ExecutionEnv,
+    ) -> Result<SpawnedChild, ExecutorError> {
+        self.spawn_plugin(current_dir, prompt, None, env).await
+    }
+
+    async fn spawn_follow_up(
+        &self,
+        current_dir: &Path,
+        prompt: &str,
+        session_id: &str,
+        _reset_to_message_id: Option<&str>,
+        env: &ExecutionEnv,
+    ) -> Result<SpawnedChild, ExecutorError> {
+        self.spawn_plugin(current_dir, prompt, Some(session_id), env)
+            .await
+    }
+
+    fn normalize_logs(&self, msg_store: Arc<MsgStore>, worktree_path: &Path) {
+        let plugin = self.clone();
+        let worktree_path = worktree_path.to_path_buf();
+        let entry_index = EntryIndexProvider::start_from(&msg_store);
+        tokio::spawn(async move {
+            if let Err(err) = plugin
+                .run_normalizer(msg_store.clone(), worktree_path, entry_index.clone())
+                .await
+            {
+                let entry = plugin_error_entry(format!(
+                    "Plugin `{}` failed to normalize the output: {err}",
+                    plugin.plugin
+                ));
+                msg_store.push_patch(ConversationPatch::add_normalized_entry(
+                    entry_index.next(),
+                    entry,
+                ));
+            }
+        });
+    }
+
+    fn default_mcp_config_path(&self) -> Option<PathBuf> {
+        None
+    }
+
+    fn get_availability_info(&self) -> AvailabilityInfo {
+        let query = || match self.query_availability(PLUGIN_AVAILABILITY_TIMEOUT) {
+            Ok(info) => info,
+            Err(PluginError::NotFound(_)) => AvailabilityInfo::NotFound,
+            Err(err) => {
+                tracing::warn!("Plugin `{}` availability check failed: {err}", self.plugin);
+                AvailabilityInfo::NotFound
+            }
+        };
+        // This trait method is synchronous; on a multi-threaded runtime, let other tasks move off
+        // this worker while the plugin answers
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) if handle.runtime_flavor() == tokio::runtime::RuntimeFlavor::MultiThread => {
+                tokio::task::block_in_place(query)
+            }
+            _ => query(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_responses_and_validates_entries() {
+        let stdout = b"starting up\n\
+            {\"jsonrpc\":\"2.0\",\"method\":\"log\",\"params\":{}}\n\
+            {\"jsonrpc\":\"2.0\",\"id\":1,\"result\":{\"command\":[\"my-agent\",\"--json\"]}}\n";
+        let plan: SpawnPlan = parse_response(stdout, "spawn").unwrap();
+        assert_eq!(plan.command, vec!["my-agent", "--json"]);
+        assert!(plan.stdin.is_none());
+
+        let error =
+            b"{\"jsonrpc\":\"2.0\",\"id\":1,\"error\":{\"code\":-32601,\"message\":\"no\"}}";
+        assert!(matches!(
+            parse_response::<SpawnPlan>(error, "spawn"),
+            Err(PluginError::Rpc { code: -32601, .. })
+        ));
+        assert!(matches!(
+            parse_response::<SpawnPlan>(b"", "spawn"),
+            Err(PluginError::NoResponse(_))
+        ));
+
+        let valid = serde_json::json!({
+            "index": 0,
+            "entry": { "entry_type": { "type": "assistant_message" }, "content": "hi" }
+        });
+        assert!(serde_json::from_value::<EntryParams>(valid).is_ok());
+        let invalid = serde_json::json!({
+            "index": 0,
+            "entry": { "entry_type": { "type": "shouting" }, "content": "hi" }
+        });
+        assert!(serde_json::from_value::<EntryParams>(invalid).is_err());
+        assert_eq!(variant_name("my-agent.v2"), "MY_AGENT_V2");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn stuck_plugins_time_out() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir =
+            std::env::temp_dir().join(format!("code-marshal-plugin-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("stuck");
+        std::fs::write(&path, "#!/bin/sh\nexec sleep 30\n").unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let plugin: Plugin = serde_json::from_value(serde_json::json!({ "plugin": path })).unwrap();
+
+        let env = ExecutionEnv::new(RepoContext::default(), false, String::new());
+        let timeout = Duration::from_millis(200);
+        let result = plugin
+            .call_with_timeout::<_, SpawnPlan>("spawn", Value::Null, &dir, &env, timeout)
+            .await;
+        assert!(matches!(result, Err(PluginError::Timeout { .. })));
+        assert!(matches!(
+            plugin.query_availability(timeout),
+            Err(PluginError::Timeout { .. })
+        ));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -33,8 +33,12 @@ pub struct QwenCode {
 }
 
 impl QwenCode {
+    pub fn base_command() -> &'static str {
+        "npx -y @qwen-code/qwen-code@0.9.1"
+    }
+
     fn build_command_builder(&self) -> Result<CommandBuilder, CommandBuildError> {
-        let mut builder = CommandBuilder::new("npx -y @qwen-code/qwen-code@0.9.1");
+        let mut builder = CommandBuilder::new(Self::base_command());
 
         if self.yolo.unwrap_or(false) {
             builder = builder.extend_params(["--yolo"]);
@@ -50,6 +54,11 @@ impl StandardCodingAgentExecutor for QwenCode {
         self.approvals = Some(approvals);
     }
 
+    fn require_approvals(&mut self) -> bool {
+        self.yolo = Some(false);
+        true
+    }
+
     async fn spawn(
         &self,
         current_dir: &Path,
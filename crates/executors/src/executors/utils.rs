@@ -1,4 +1,5 @@
 use std::{
+    io,
     num::NonZeroUsize,
     path::PathBuf,
     sync::{Arc, Mutex, OnceLock},
@@ -10,6 +11,20 @@ use lru::LruCache;
 use super::SlashCommandDescription;
 use crate::executors::BaseCodingAgent;
 
+/// `~/.code-marshal/<namespace>` (under `dev/` in debug builds), created if missing; where
+/// executors keep their own session files
+pub fn session_dir(namespace: &str) -> io::Result<PathBuf> {
+    let mut dir = dirs::home_dir()
+        .ok_or_else(|| io::Error::other("Could not determine home directory"))?
+        .join(".code-marshal");
+    if cfg!(debug_assertions) {
+        dir = dir.join("dev");
+    }
+    let dir = dir.join(namespace);
+    std::fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
 /// Parsed slash command with name and arguments.
 
 #[derive(Debug, Clone, PartialEq, Eq)]
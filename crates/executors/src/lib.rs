@@ -1,9 +1,18 @@
 pub mod actions;
 pub mod approvals;
+pub mod attachments;
+pub mod changes;
 pub mod command;
+pub mod diff_stream;
+pub mod doctor;
 pub mod env;
 pub mod executors;
 pub mod logs;
 pub mod mcp_config;
+pub mod models;
+pub mod plan;
 pub mod profile;
+pub mod protected;
 pub mod stdout_dup;
+pub mod template;
+pub mod tool_cache;
@@ -40,6 +40,25 @@ pub fn escape_json_pointer_segment(s: &str) -> String {
     s.replace('~', "~0").replace('/', "~1")
 }
 
+/// Where a diff patch is kept in the conversation document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiffSource {
+    /// A conversation entry under `/entries/`, keyed by entry index
+    Entry,
+    /// A live worktree diff under `/diffs/`, keyed by its escaped path so it can never collide
+    /// with a numeric conversation entry
+    Worktree,
+}
+
+impl DiffSource {
+    fn path(self, key: &str) -> String {
+        match self {
+            DiffSource::Entry => format!("/entries/{key}"),
+            DiffSource::Worktree => format!("/diffs/{key}"),
+        }
+    }
+}
+
 /// Helper functions to create JSON patches for conversation entries
 pub struct ConversationPatch;
 
@@ -77,22 +96,22 @@ impl ConversationPatch {
         from_value(json!([patch_entry])).unwrap()
     }
 
-    /// Create an ADD patch for a new diff at the given index
-    pub fn add_diff(entry_index: String, diff: Diff) -> Patch {
+    /// Create an ADD patch for a new diff at the given key of `source`
+    pub fn add_diff(source: DiffSource, key: String, diff: Diff) -> Patch {
         let patch_entry = PatchEntry {
             op: PatchOperation::Add,
-            path: format!("/entries/{entry_index}"),
+            path: source.path(&key),
             value: PatchType::Diff(diff),
         };
 
         from_value(json!([patch_entry])).unwrap()
     }
 
-    /// Create an ADD patch for a new diff at the given index
-    pub fn replace_diff(entry_index: String, diff: Diff) -> Patch {
+    /// Create a REPLACE patch for a diff already sent at the given key of `source`
+    pub fn replace_diff(source: DiffSource, key: String, diff: Diff) -> Patch {
         let patch_entry = PatchEntry {
             op: PatchOperation::Replace,
-            path: format!("/entries/{entry_index}"),
+            path: source.path(&key),
             value: PatchType::Diff(diff),
         };
 
@@ -100,10 +119,10 @@ impl ConversationPatch {
     }
 
     /// Create a REMOVE patch for removing a diff
-    pub fn remove_diff(entry_index: String) -> Patch {
+    pub fn remove_diff(source: DiffSource, key: String) -> Patch {
         from_value(json!([{
             "op": PatchOperation::Remove,
-            "path": format!("/entries/{entry_index}"),
+            "path": source.path(&key),
         }]))
         .unwrap()
     }
@@ -1,6 +1,6 @@
 //! Utilities for reading and writing external agent config files (not the server's own config).
 //!
-//! These helpers abstract over JSON vs TOML vs JSONC formats used by different agents.
+//! These helpers abstract over JSON vs TOML vs JSONC vs YAML formats used by different agents.
 //! JSONC (JSON with Comments) is supported with comment preservation using jsonc-parser's CST.
 
 use std::{collections::HashMap, path::Path, sync::LazyLock};
@@ -13,8 +13,9 @@ use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value};
 use tokio::fs;
 use ts_rs::TS;
+use workspace_utils::redact::is_secret_name;
 
-use crate::executors::{CodingAgent, ExecutorError};
+use crate::executors::{CodingAgent, ExecutorError, StandardCodingAgentExecutor};
 
 fn is_jsonc_file(path: &Path) -> bool {
     path.extension()
@@ -22,6 +23,12 @@ fn is_jsonc_file(path: &Path) -> bool {
         .is_some_and(|e| e.eq_ignore_ascii_case("jsonc"))
 }
 
+fn is_yaml_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| e.eq_ignore_ascii_case("yaml") || e.eq_ignore_ascii_case("yml"))
+}
+
 static DEFAULT_MCP_JSON: &str = include_str!("../default_mcp.json");
 pub static PRECONFIGURED_MCP_SERVERS: LazyLock<Value> = LazyLock::new(|| {
     serde_json::from_str::<Value>(DEFAULT_MCP_JSON).expect("Failed to parse default MCP JSON")
@@ -77,6 +84,11 @@ pub async fn read_agent_config(
                 Ok(None) => Ok(serde_json::json!({})),
                 Err(_) => Ok(serde_json::from_str(&file_content)?),
             }
+        } else if is_yaml_file(config_path) {
+            if file_content.trim().is_empty() {
+                return Ok(serde_json::json!({}));
+            }
+            Ok(serde_norway::from_str(&file_content)?)
         } else {
             Ok(serde_json::from_str(&file_content)?)
         }
@@ -96,6 +108,13 @@ pub async fn write_agent_config(
         fs::write(config_path, toml_content).await?;
     } else if is_jsonc_file(config_path) {
         write_jsonc_preserving_comments(config_path, config).await?;
+    } else if is_yaml_file(config_path) {
+        let current_content = fs::read_to_string(config_path).await.unwrap_or_default();
+        let output = match mcp_config.servers_path.first() {
+            Some(key) => update_yaml_key(&current_content, key, config)?,
+            None => serde_norway::to_string(config)?,
+        };
+        fs::write(config_path, output).await?;
     } else {
         let json_content = serde_json::to_string_pretty(config)?;
         fs::write(config_path, json_content).await?;
@@ -117,6 +136,53 @@ async fn write_jsonc_preserving_comments(
     Ok(())
 }
 
+/// Replace the top-level `key` block of a YAML document with `key` from `new_config`, keeping the
+/// rest of the text as it is. YAML config files (Goose's `config.yaml`) also hold the agent's
+/// own settings, so only the servers key is rewritten: comments and key order elsewhere survive,
+/// while comments inside that block are lost.
+fn update_yaml_key(
+    current_content: &str,
+    key: &str,
+    new_config: &Value,
+) -> Result<String, ExecutorError> {
+    let mut block = Map::new();
+    block.insert(
+        key.to_string(),
+        new_config.get(key).cloned().unwrap_or(Value::Null),
+    );
+    let block = serde_norway::to_string(&block)?;
+
+    let lines: Vec<&str> = current_content.lines().collect();
+    let Some(start) = lines.iter().position(|line| {
+        line.strip_prefix(key)
+            .and_then(|rest| rest.strip_prefix(':'))
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with([' ', '\t']))
+    }) else {
+        let mut output = current_content.to_string();
+        if !output.is_empty() && !output.ends_with('\n') {
+            output.push('\n');
+        }
+        output.push_str(&block);
+        return Ok(output);
+    };
+    // The block runs until the next line at column 0 (another key, a comment or a document
+    // marker); list items may start at column 0 too. Trailing blank lines stay outside it.
+    let mut end = lines[start + 1..]
+        .iter()
+        .position(|line| {
+            !line.is_empty() && !line.starts_with([' ', '\t']) && !line.starts_with("- ")
+        })
+        .map_or(lines.len(), |offset| start + 1 + offset);
+    while end > start + 1 && lines[end - 1].trim().is_empty() {
+        end -= 1;
+    }
+
+    let mut output: Vec<&str> = lines[..start].to_vec();
+    output.extend(block.lines());
+    output.extend(&lines[end..]);
+    Ok(output.join("\n") + "\n")
+}
+
 fn update_jsonc_content(current_content: &str, new_config: &Value) -> String {
     let root = CstRootNode::parse(current_content, &ParseOptions::default())
         .unwrap_or_else(|_| CstRootNode::parse("{}", &ParseOptions::default()).unwrap());
@@ -367,6 +433,43 @@ fn adapt_copilot(mut servers: ServerMap, meta: Option<Value>) -> Value {
     attach_meta(servers, meta)
 }
 
+fn adapt_goose(servers: ServerMap, meta: Option<Value>) -> Value {
+    let servers = servers
+        .into_iter()
+        .map(|(name, value)| {
+            let Value::Object(mut s) = value else {
+                return (name, value);
+            };
+            let mut extension = Map::new();
+            extension.insert("name".to_string(), Value::String(name.clone()));
+            if is_http_server(&s) {
+                let url = s
+                    .remove("url")
+                    .unwrap_or_else(|| Value::String(String::new()));
+                extension.insert(
+                    "type".to_string(),
+                    Value::String("streamable_http".to_string()),
+                );
+                extension.insert("uri".to_string(), url);
+                if let Some(headers) = s.remove("headers") {
+                    extension.insert("headers".to_string(), headers);
+                }
+            } else {
+                extension.insert("type".to_string(), Value::String("stdio".to_string()));
+                for (from, to) in [("command", "cmd"), ("args", "args"), ("env", "envs")] {
+                    if let Some(v) = s.remove(from) {
+                        extension.insert(to.to_string(), v);
+                    }
+                }
+            }
+            extension.insert("enabled".to_string(), Value::Bool(true));
+            extension.insert("timeout".to_string(), Value::from(300));
+            (name, Value::Object(extension))
+        })
+        .collect();
+    attach_meta(servers, meta)
+}
+
 enum Adapter {
     Passthrough,
     Gemini,
@@ -374,6 +477,7 @@ enum Adapter {
     Codex,
     Opencode,
     Copilot,
+    Goose,
 }
 
 fn apply_adapter(adapter: Adapter, canonical: Value) -> Value {
@@ -389,6 +493,7 @@ fn apply_adapter(adapter: Adapter, canonical: Value) -> Value {
         Adapter::Codex => adapt_codex(servers_only, meta),
         Adapter::Opencode => adapt_opencode(servers_only, meta),
         Adapter::Copilot => adapt_copilot(servers_only, meta),
+        Adapter::Goose => adapt_goose(servers_only, meta),
     }
 }
 
@@ -397,12 +502,19 @@ impl CodingAgent {
         use Adapter::*;
 
         let adapter = match self {
-            CodingAgent::ClaudeCode(_) | CodingAgent::Amp(_) | CodingAgent::Droid(_) => Passthrough,
+            CodingAgent::ClaudeCode(_)
+            | CodingAgent::Amp(_)
+            | CodingAgent::Droid(_)
+            | CodingAgent::Aider(_)
+            | CodingAgent::Acp(_)
+            | CodingAgent::Custom(_)
+            | CodingAgent::Plugin(_) => Passthrough,
             CodingAgent::QwenCode(_) | CodingAgent::Gemini(_) => Gemini,
             CodingAgent::CursorAgent(_) => Cursor,
             CodingAgent::Codex(_) => Codex,
             CodingAgent::Opencode(_) => Opencode,
             CodingAgent::Copilot(..) => Copilot,
+            CodingAgent::Goose(_) => Goose,
             #[cfg(feature = "qa-mode")]
             CodingAgent::QaMock(_) => Passthrough, // QA mock doesn't need MCP
         };
@@ -410,4 +522,78 @@ impl CodingAgent {
         let canonical = PRECONFIGURED_MCP_SERVERS.clone();
         apply_adapter(adapter, canonical)
     }
+
+    /// Secret-looking `env` and header values of the MCP servers in this agent's config file,
+    /// by name, so they can be redacted from its output
+    pub async fn mcp_server_secrets(&self) -> Vec<(String, String)> {
+        let Some(path) = self.default_mcp_config_path() else {
+            return Vec::new();
+        };
+        let mcp_config = self.get_mcp_config();
+        let Ok(config) = read_agent_config(&path, &mcp_config).await else {
+            return Vec::new();
+        };
+        let servers = mcp_config
+            .servers_path
+            .iter()
+            .try_fold(&config, |value, key| value.get(key))
+            .and_then(Value::as_object);
+
+        let mut secrets = Vec::new();
+        for server in servers.into_iter().flat_map(|servers| servers.values()) {
+            for field in ["env", "envs", "environment", "headers", "http_headers"] {
+                let entries = server.get(field).and_then(Value::as_object);
+                for (name, value) in entries.into_iter().flatten() {
+                    let Some(value) = value.as_str().filter(|_| is_secret_name(name)) else {
+                        continue;
+                    };
+                    if let Some(token) = value.strip_prefix("Bearer ") {
+                        secrets.push((name.clone(), token.to_string()));
+                    }
+                    secrets.push((name.clone(), value.to_string()));
+                }
+            }
+        }
+        secrets
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn yaml_update_rewrites_only_the_servers_key() {
+        let current = "\
+# Goose settings
+GOOSE_PROVIDER: anthropic # the provider
+extensions:
+  # old comment
+  developer:
+    enabled: true
+
+GOOSE_MODEL: claude-sonnet
+";
+        let config = serde_json::json!({
+            "GOOSE_MODEL": "reordered",
+            "extensions": { "context7": { "enabled": true, "type": "stdio" } }
+        });
+        let updated = update_yaml_key(current, "extensions", &config).unwrap();
+        assert_eq!(
+            updated,
+            "\
+# Goose settings
+GOOSE_PROVIDER: anthropic # the provider
+extensions:
+  context7:
+    enabled: true
+    type: stdio
+
+GOOSE_MODEL: claude-sonnet
+"
+        );
+
+        let appended = update_yaml_key("GOOSE_PROVIDER: openai", "extensions", &config).unwrap();
+        assert!(appended.starts_with("GOOSE_PROVIDER: openai\nextensions:\n  context7:"));
+    }
 }
@@ -0,0 +1,323 @@
+//! Model discovery: the models each agent can run, with context windows where known, and the
+//! check of a requested `--model` against them before anything is spawned.
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::executors::{BaseCodingAgent, CodingAgent, ExecutorError};
+
+/// A model an agent can run
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, TS)]
+#[ts(export)]
+pub struct ModelInfo {
+    /// The value to pass as the profile's `model` (or `--model`)
+    pub id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    /// Context window in tokens, when known
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_window: Option<u32>,
+    /// The model the agent uses when none is configured
+    #[serde(default)]
+    pub is_default: bool,
+}
+
+/// Where a [`ModelCatalog`] came from
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum ModelSource {
+    /// Queried from the agent itself (SDK, app-server or CLI); nothing else is accepted
+    Discovered,
+    /// A list shipped with code-marshal; the agent may accept models it does not name
+    Known,
+}
+
+/// The models available to one agent
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, TS)]
+#[ts(export)]
+pub struct ModelCatalog {
+    pub source: ModelSource,
+    pub models: Vec<ModelInfo>,
+}
+
+struct KnownModel {
+    id: &'static str,
+    name: &'static str,
+    context_window: Option<u32>,
+}
+
+const fn known(id: &'static str, name: &'static str, context_window: Option<u32>) -> KnownModel {
+    KnownModel {
+        id,
+        name,
+        context_window,
+    }
+}
+
+const CLAUDE_MODELS: &[KnownModel] = &[
+    known("sonnet", "Latest Sonnet", Some(200_000)),
+    known("opus", "Latest Opus", Some(200_000)),
+    known("haiku", "Latest Haiku", Some(200_000)),
+    known(
+        "opusplan",
+        "Opus for planning, Sonnet otherwise",
+        Some(200_000),
+    ),
+    known("claude-sonnet-4-5", "Claude Sonnet 4.5", Some(200_000)),
+    known("claude-opus-4-5", "Claude Opus 4.5", Some(200_000)),
+    known("claude-opus-4-1", "Claude Opus 4.1", Some(200_000)),
+    known("claude-haiku-4-5", "Claude Haiku 4.5", Some(200_000)),
+    known("claude-sonnet-4-20250514", "Claude Sonnet 4", Some(200_000)),
+];
+
+const GEMINI_MODELS: &[KnownModel] = &[
+    known("gemini-2.5-pro", "Gemini 2.5 Pro", Some(1_048_576)),
+    known("gemini-2.5-flash", "Gemini 2.5 Flash", Some(1_048_576)),
+    known(
+        "gemini-2.5-flash-lite",
+        "Gemini 2.5 Flash-Lite",
+        Some(1_048_576),
+    ),
+    known(
+        "gemini-3-pro-preview",
+        "Gemini 3 Pro (preview)",
+        Some(1_048_576),
+    ),
+    known(
+        "gemini-3-flash-preview",
+        "Gemini 3 Flash (preview)",
+        Some(1_048_576),
+    ),
+];
+
+const CODEX_MODELS: &[KnownModel] = &[
+    known("gpt-5.2-codex", "GPT-5.2 Codex", Some(272_000)),
+    known("gpt-5.2", "GPT-5.2", Some(272_000)),
+    known("gpt-5.1-codex-max", "GPT-5.1 Codex Max", Some(272_000)),
+    known("gpt-5.1-codex", "GPT-5.1 Codex", Some(272_000)),
+    known("gpt-5.1-codex-mini", "GPT-5.1 Codex Mini", Some(272_000)),
+    known("gpt-5-codex", "GPT-5 Codex", Some(272_000)),
+    known("gpt-5", "GPT-5", Some(272_000)),
+];
+
+const CURSOR_MODELS: &[KnownModel] = &[
+    known("auto", "Auto", None),
+    known("composer-1.5", "Composer 1.5", None),
+    known("composer-1", "Composer 1", None),
+    known("sonnet-4.5", "Claude 4.5 Sonnet", Some(200_000)),
+    known(
+        "sonnet-4.5-thinking",
+        "Claude 4.5 Sonnet (thinking)",
+        Some(200_000),
+    ),
+    known("opus-4.1", "Claude 4.1 Opus", Some(200_000)),
+    known("gpt-5", "GPT-5", Some(272_000)),
+    known("grok", "Grok", None),
+];
+
+fn known_list(agent: BaseCodingAgent) -> Option<&'static [KnownModel]> {
+    match agent {
+        BaseCodingAgent::ClaudeCode => Some(CLAUDE_MODELS),
+        BaseCodingAgent::Gemini => Some(GEMINI_MODELS),
+        BaseCodingAgent::Codex => Some(CODEX_MODELS),
+        BaseCodingAgent::CursorAgent => Some(CURSOR_MODELS),
+        _ => None,
+    }
+}
+
+/// The built-in model list for `agent`, if code-marshal ships one
+pub fn known_models(agent: BaseCodingAgent) -> Option<ModelCatalog> {
+    let models = known_list(agent)?
+        .iter()
+        .enumerate()
+        .map(|(index, model)| ModelInfo {
+            id: model.id.to_string(),
+            display_name: Some(model.name.to_string()),
+            context_window: model.context_window,
+            is_default: agent == BaseCodingAgent::CursorAgent && index == 0,
+        })
+        .collect();
+    Some(ModelCatalog {
+        source: ModelSource::Known,
+        models,
+    })
+}
+
+/// Context window of a model from the built-in lists; `provider/model` ids (OpenCode) are
+/// looked up by their model part
+pub fn known_context_window(model_id: &str) -> Option<u32> {
+    let model_id = model_id.rsplit('/').next().unwrap_or(model_id);
+    [CLAUDE_MODELS, GEMINI_MODELS, CODEX_MODELS, CURSOR_MODELS]
+        .iter()
+        .flat_map(|list| list.iter())
+        .find(|model| model.id == model_id)
+        .and_then(|model| model.context_window)
+}
+
+impl ModelCatalog {
+    /// A discovered catalog, with context windows the agent did not report filled in from the
+    /// built-in lists
+    pub fn discovered(models: Vec<ModelInfo>) -> Self {
+        let models = models
+            .into_iter()
+            .map(|mut model| {
+                model.context_window = model
+                    .context_window
+                    .or_else(|| known_context_window(&model.id));
+                model
+            })
+            .collect();
+        Self {
+            source: ModelSource::Discovered,
+            models,
+        }
+    }
+
+    pub fn find(&self, model: &str) -> Option<&ModelInfo> {
+        self.models.iter().find(|info| info.id == model)
+    }
+
+    /// Listed ids close to `model`, best match first
+    pub fn suggestions(&self, model: &str) -> Vec<String> {
+        let wanted = model.to_lowercase();
+        let max_distance = (wanted.len() / 3).max(2);
+        let mut scored: Vec<_> = self
+            .models
+            .iter()
+            .filter_map(|info| {
+                let candidate = info.id.to_lowercase();
+                let distance = if candidate.contains(&wanted) || wanted.contains(&candidate) {
+                    0
+                } else {
+                    edit_distance(&wanted, &candidate)
+                };
+                (distance <= max_distance).then_some((distance, info.id.clone()))
+            })
+            .collect();
+        scored.sort();
+        scored.into_iter().take(3).map(|(_, id)| id).collect()
+    }
+
+    /// Check a requested model before spawning. Unlisted models are rejected only when the
+    /// list came from the agent; built-in lists cannot be complete
+    pub fn validate(&self, agent: BaseCodingAgent, model: &str) -> Result<(), ExecutorError> {
+        if self.source == ModelSource::Known || self.find(model).is_some() {
+            return Ok(());
+        }
+        Err(ExecutorError::UnknownModel {
+            agent,
+            model: model.to_string(),
+            suggestions: self.suggestions(model),
+        })
+    }
+}
+
+/// `; did you mean ...?` for [`ExecutorError::UnknownModel`]
+pub(crate) fn suggestion_hint(suggestions: &[String]) -> String {
+    if suggestions.is_empty() {
+        return String::new();
+    }
+    let quoted: Vec<_> = suggestions.iter().map(|id| format!("`{id}`")).collect();
+    format!("; did you mean {}?", quoted.join(" or "))
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+fn model_field(agent: &mut CodingAgent) -> Option<&mut Option<String>> {
+    match agent {
+        CodingAgent::ClaudeCode(agent) => Some(&mut agent.model),
+        CodingAgent::Gemini(agent) => Some(&mut agent.model),
+        CodingAgent::Codex(agent) => Some(&mut agent.model),
+        CodingAgent::Opencode(agent) => Some(&mut agent.model),
+        CodingAgent::CursorAgent(agent) => Some(&mut agent.model),
+        CodingAgent::Copilot(agent) => Some(&mut agent.model),
+        CodingAgent::Droid(agent) => Some(&mut agent.model),
+        CodingAgent::Aider(agent) => Some(&mut agent.model),
+        CodingAgent::Goose(agent) => Some(&mut agent.model),
+        CodingAgent::Acp(agent) => Some(&mut agent.model),
+        _ => None,
+    }
+}
+
+/// The model configured in `agent`'s profile
+pub fn agent_model(agent: &CodingAgent) -> Option<String> {
+    model_field(&mut agent.clone()).and_then(|model| model.clone())
+}
+
+/// `agent` running `model`, or `None` if the agent has no model setting
+pub fn with_model(agent: &CodingAgent, model: &str) -> Option<CodingAgent> {
+    let mut agent = agent.clone();
+    *model_field(&mut agent)? = Some(model.to_string());
+    Some(agent)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn model(id: &str) -> ModelInfo {
+        ModelInfo {
+            id: id.to_string(),
+            display_name: None,
+            context_window: None,
+            is_default: false,
+        }
+    }
+
+    #[test]
+    fn discovered_catalog_rejects_unlisted_models() {
+        let catalog = ModelCatalog::discovered(vec![model("gpt-5.1-codex"), model("o4-mini")]);
+        assert_eq!(catalog.models[0].context_window, Some(272_000));
+        assert!(catalog.validate(BaseCodingAgent::Codex, "o4-mini").is_ok());
+
+        let err = catalog
+            .validate(BaseCodingAgent::Codex, "gpt-5.1-codx")
+            .unwrap_err();
+        match err {
+            ExecutorError::UnknownModel { suggestions, .. } => {
+                assert_eq!(suggestions, vec!["gpt-5.1-codex".to_string()]);
+            }
+            other => panic!("unexpected error: {other}"),
+        }
+    }
+
+    #[test]
+    fn known_catalog_accepts_unlisted_models() {
+        let catalog = known_models(BaseCodingAgent::ClaudeCode).unwrap();
+        assert!(catalog.find("sonnet").is_some());
+        assert!(
+            catalog
+                .validate(BaseCodingAgent::ClaudeCode, "claude-3-7-sonnet-latest")
+                .is_ok()
+        );
+        assert_eq!(
+            known_context_window("anthropic/claude-opus-4-1"),
+            Some(200_000)
+        );
+    }
+
+    #[test]
+    fn with_model_sets_the_profile_model() {
+        let gemini: CodingAgent = serde_json::from_value(json!({ "GEMINI": {} })).unwrap();
+        let gemini = with_model(&gemini, "gemini-2.5-flash").unwrap();
+        assert_eq!(agent_model(&gemini).as_deref(), Some("gemini-2.5-flash"));
+
+        let amp: CodingAgent = serde_json::from_value(json!({ "AMP": {} })).unwrap();
+        assert!(with_model(&amp, "anything").is_none());
+    }
+}
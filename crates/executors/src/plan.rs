@@ -0,0 +1,194 @@
+//! Plan-only runs: the agent is configured so it cannot modify the workspace, and the plan it
+//! produces is captured from the normalized log as a structured artifact.
+
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::{
+    executors::{
+        CodingAgent,
+        codex::{AskForApproval, SandboxMode},
+        droid::Autonomy,
+    },
+    logs::{ActionType, NormalizedEntry, NormalizedEntryType, TodoItem},
+};
+
+/// Appended to the prompt of plan-only runs; agents without a native plan mode rely on it
+pub const PLAN_ONLY_INSTRUCTIONS: &str = "Do not modify any files or run commands that change \
+     the workspace. Investigate as needed, then reply with a step-by-step implementation plan \
+     as a numbered list.";
+
+/// `agent` reconfigured so it cannot modify the workspace, or `None` if the agent has no
+/// non-mutating configuration
+pub fn plan_only_agent(agent: &CodingAgent) -> Option<CodingAgent> {
+    let mut agent = agent.clone();
+    match &mut agent {
+        CodingAgent::ClaudeCode(claude) => {
+            claude.plan = Some(true);
+            claude.approvals = None;
+            claude.dangerously_skip_permissions = None;
+        }
+        CodingAgent::Codex(codex) => {
+            codex.sandbox = Some(SandboxMode::ReadOnly);
+            codex.ask_for_approval = Some(AskForApproval::Never);
+        }
+        CodingAgent::Opencode(opencode) => {
+            opencode.agent = Some("plan".to_string());
+        }
+        CodingAgent::Droid(droid) => {
+            // `droid exec` without `--auto` is read-only
+            droid.autonomy = Autonomy::Normal;
+        }
+        _ => return None,
+    }
+    Some(agent)
+}
+
+/// The plan an agent produced in a plan-only run
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct PlanArtifact {
+    pub markdown: String,
+    /// Top-level steps extracted from the markdown (numbered items, else bullets)
+    pub steps: Vec<String>,
+    /// The agent's latest todo list, if it kept one
+    pub todos: Vec<TodoItem>,
+}
+
+impl PlanArtifact {
+    /// Build the artifact from a run's normalized entries: the last presented plan (e.g.
+    /// Claude's `ExitPlanMode`), otherwise the last assistant message
+    pub fn from_entries<'a>(
+        entries: impl IntoIterator<Item = &'a NormalizedEntry>,
+    ) -> Option<Self> {
+        let mut presented = None;
+        let mut last_message = None;
+        let mut todos = Vec::new();
+        for entry in entries {
+            match &entry.entry_type {
+                NormalizedEntryType::ToolUse {
+                    action_type: ActionType::PlanPresentation { plan },
+                    ..
+                } => presented = Some(plan.clone()),
+                NormalizedEntryType::ToolUse {
+                    action_type: ActionType::TodoManagement { todos: items, .. },
+                    ..
+                } => todos = items.clone(),
+                NormalizedEntryType::AssistantMessage if !entry.content.trim().is_empty() => {
+                    last_message = Some(entry.content.clone());
+                }
+                _ => {}
+            }
+        }
+        let markdown = presented.or(last_message)?.trim().to_string();
+        Some(Self {
+            steps: extract_steps(&markdown),
+            markdown,
+            todos,
+        })
+    }
+}
+
+/// Top-level steps of a markdown plan: numbered list items if there are any, otherwise
+/// unindented bullets. Checkbox markers are dropped.
+pub fn extract_steps(markdown: &str) -> Vec<String> {
+    let mut numbered = Vec::new();
+    let mut bullets = Vec::new();
+    for line in markdown.lines() {
+        if line.starts_with([' ', '\t']) {
+            continue;
+        }
+        if let Some(item) = strip_number(line) {
+            numbered.push(clean_item(item));
+        } else if let Some(item) = line
+            .strip_prefix("- ")
+            .or_else(|| line.strip_prefix("* "))
+            .or_else(|| line.strip_prefix("+ "))
+        {
+            bullets.push(clean_item(item));
+        }
+    }
+    let steps = if numbered.is_empty() {
+        bullets
+    } else {
+        numbered
+    };
+    steps.into_iter().filter(|s| !s.is_empty()).collect()
+}
+
+fn strip_number(line: &str) -> Option<&str> {
+    let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    if digits == 0 {
+        return None;
+    }
+    line[digits..]
+        .strip_prefix(". ")
+        .or_else(|| line[digits..].strip_prefix(") "))
+}
+
+fn clean_item(item: &str) -> String {
+    let item = item.trim();
+    let item = ["[ ] ", "[x] ", "[X] "]
+        .iter()
+        .find_map(|checkbox| item.strip_prefix(checkbox))
+        .unwrap_or(item);
+    item.trim().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(entry_type: NormalizedEntryType, content: &str) -> NormalizedEntry {
+        NormalizedEntry {
+            timestamp: None,
+            entry_type,
+            content: content.to_string(),
+            metadata: None,
+        }
+    }
+
+    #[test]
+    fn extracts_numbered_steps_over_bullets() {
+        let markdown = "# Plan\n\n- context\n1. Add the table\n   - with an index\n2) Write the \
+                        migration\n10. Update docs\n";
+        assert_eq!(
+            extract_steps(markdown),
+            vec!["Add the table", "Write the migration", "Update docs"]
+        );
+    }
+
+    #[test]
+    fn falls_back_to_bullets_and_drops_checkboxes() {
+        let markdown = "Steps:\n- [ ] Read the config\n* [x] Fix the parser\n  - nested\n";
+        assert_eq!(
+            extract_steps(markdown),
+            vec!["Read the config", "Fix the parser"]
+        );
+    }
+
+    #[test]
+    fn prefers_presented_plan_over_assistant_messages() {
+        let entries = vec![
+            entry(NormalizedEntryType::AssistantMessage, "Let me look around."),
+            entry(
+                NormalizedEntryType::ToolUse {
+                    tool_name: "ExitPlanMode".to_string(),
+                    action_type: ActionType::PlanPresentation {
+                        plan: "1. First\n2. Second".to_string(),
+                    },
+                    status: Default::default(),
+                },
+                "",
+            ),
+            entry(NormalizedEntryType::AssistantMessage, "Waiting for review."),
+        ];
+        let artifact = PlanArtifact::from_entries(&entries).unwrap();
+        assert_eq!(artifact.markdown, "1. First\n2. Second");
+        assert_eq!(artifact.steps, vec!["First", "Second"]);
+
+        let artifact = PlanArtifact::from_entries(&entries[2..]).unwrap();
+        assert_eq!(artifact.markdown, "Waiting for review.");
+        assert!(PlanArtifact::from_entries(&[]).is_none());
+    }
+}
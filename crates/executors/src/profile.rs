@@ -1,19 +1,26 @@
 use std::{
     collections::HashMap,
     fs,
+    path::{Path, PathBuf},
     str::FromStr,
     sync::{LazyLock, RwLock},
 };
 
 use convert_case::{Case, Casing};
+use schemars::JsonSchema;
 use serde::{Deserialize, Deserializer, Serialize, de::Error as DeError};
+use serde_json::Value;
 use thiserror::Error;
 use ts_rs::TS;
 
 use crate::executors::{
-    AvailabilityInfo, BaseCodingAgent, CodingAgent, StandardCodingAgentExecutor,
+    AvailabilityInfo, BaseCodingAgent, CodingAgent, StandardCodingAgentExecutor, plugin,
 };
 
+mod watcher;
+
+pub use watcher::{ProfilesEvent, spawn_profiles_watcher, subscribe_profile_events};
+
 /// Return the canonical form for variant keys.
 /// – "DEFAULT" is kept as-is  
 /// – everything else is converted to SCREAMING_SNAKE_CASE
@@ -107,6 +114,28 @@ impl ExecutorProfileId {
     }
 }
 
+impl FromStr for ExecutorProfileId {
+    type Err = ProfileError;
+
+    /// Parse `EXECUTOR` or `EXECUTOR:VARIANT` (executor names are case-insensitive)
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (executor, variant) = match s.split_once(':') {
+            Some((executor, variant)) => (executor, Some(variant)),
+            None => (s, None),
+        };
+        let norm = executor.trim().replace('-', "_").to_ascii_uppercase();
+        let executor = BaseCodingAgent::from_str(&norm)
+            .map_err(|_| ProfileError::Validation(format!("Unknown executor '{executor}'")))?;
+        match variant.map(str::trim) {
+            Some("") => Err(ProfileError::Validation(format!(
+                "Empty variant name in '{s}'"
+            ))),
+            Some(variant) => Ok(Self::with_variant(executor, canonical_variant_key(variant))),
+            None => Ok(Self::new(executor)),
+        }
+    }
+}
+
 impl std::fmt::Display for ExecutorProfileId {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match &self.variant {
@@ -116,7 +145,7 @@ impl std::fmt::Display for ExecutorProfileId {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, TS)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, TS, JsonSchema)]
 pub struct ExecutorConfig {
     #[serde(flatten)]
     pub configurations: HashMap<String, CodingAgent>,
@@ -170,7 +199,7 @@ impl ExecutorConfig {
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, TS)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, TS, JsonSchema)]
 pub struct ExecutorConfigs {
     pub executors: HashMap<BaseCodingAgent, ExecutorConfig>,
 }
@@ -200,14 +229,81 @@ impl ExecutorConfigs {
         EXECUTOR_PROFILES_CACHE.read().unwrap().clone()
     }
 
-    /// Reload executor profiles cache
-    pub fn reload() {
-        let mut cache = EXECUTOR_PROFILES_CACHE.write().unwrap();
-        *cache = Self::load();
+    /// Reload executor profiles cache. The new config is validated before it is swapped in;
+    /// on error the last good config stays active. Returns whether the config changed.
+    pub fn reload() -> Result<bool, ProfileError> {
+        Self::reload_into(&EXECUTOR_PROFILES_CACHE, Self::try_load())
     }
 
-    /// Load executor profiles from file or defaults
+    /// Swap a freshly loaded config into `cache`; a load error leaves the cache untouched
+    fn reload_into(
+        cache: &RwLock<Self>,
+        loaded: Result<Self, ProfileError>,
+    ) -> Result<bool, ProfileError> {
+        let configs = loaded?;
+        let mut cache = cache.write().unwrap();
+        if *cache == configs {
+            return Ok(false);
+        }
+        *cache = configs;
+        Ok(true)
+    }
+
+    /// Like [`Self::load`], but returns parse and validation errors instead of falling back
+    pub fn try_load() -> Result<Self, ProfileError> {
+        ProfileLayers::discover(&std::env::current_dir()?)?.merged()
+    }
+
+    /// Load executor profiles: defaults, then the user profiles.json, then the
+    /// project-local `.code-marshal/profiles.json` discovered from the working dir
     pub fn load() -> Self {
+        let configs = Self::load_user();
+        let Some(project_path) = std::env::current_dir()
+            .ok()
+            .and_then(|cwd| find_project_profiles(&cwd))
+        else {
+            return configs;
+        };
+
+        match read_trusted_project_layer(&project_path)
+            .and_then(|layer| configs.clone().with_project_layer(&layer))
+        {
+            Ok(merged) => {
+                tracing::info!("Loaded project profile overrides from {:?}", project_path);
+                merged
+            }
+            Err(e) => {
+                tracing::error!(
+                    "Failed to apply project profiles {:?}: {}, ignoring them",
+                    project_path,
+                    e
+                );
+                configs
+            }
+        }
+    }
+
+    /// Like [`Self::load_user`], but a user profiles.json that does not parse or validate is an
+    /// error instead of falling back to the defaults. Commands that write the user file use this,
+    /// so a broken file is never overwritten with the defaults.
+    pub fn try_load_user() -> Result<Self, ProfileError> {
+        let profiles_path = workspace_utils::assets::profiles_path();
+        let mut defaults = Self::from_defaults();
+        defaults.canonicalise();
+        let content = match fs::read_to_string(&profiles_path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(defaults),
+            Err(e) => return Err(e.into()),
+        };
+        let mut user_overrides: Self = serde_json::from_str(&content)?;
+        user_overrides.canonicalise();
+        let merged = Self::merge_with_defaults(defaults, user_overrides);
+        Self::validate_merged(&merged)?;
+        Ok(merged)
+    }
+
+    /// Load executor profiles from the user file or defaults (no project layer)
+    pub fn load_user() -> Self {
         let profiles_path = workspace_utils::assets::profiles_path();
 
         // Load defaults first
@@ -265,6 +361,188 @@ impl ExecutorConfigs {
         Ok(())
     }
 
+    /// Parse and validate a profiles file without touching the cache.
+    /// Returns the file merged over the built-in defaults.
+    pub fn load_file(path: &Path) -> Result<Self, ProfileError> {
+        let content = fs::read_to_string(path)?;
+        let mut overrides: Self = serde_json::from_str(&content)?;
+        overrides.canonicalise();
+
+        let mut defaults = Self::from_defaults();
+        defaults.canonicalise();
+        let merged = Self::merge_with_defaults(defaults, overrides);
+        Self::validate_merged(&merged)?;
+        Ok(merged)
+    }
+
+    /// Apply a field-level project layer: fields set in the layer replace the matching fields
+    /// of existing variants (nested objects such as `env` are merged), new variants are added.
+    pub fn with_project_layer(self, layer: &Value) -> Result<Self, ProfileError> {
+        let mut merged = serde_json::to_value(&self)?;
+        if let Some(executors) = layer.get("executors").and_then(Value::as_object) {
+            for (executor, variants) in executors {
+                let Some(variants) = variants.as_object() else {
+                    continue;
+                };
+                let target = merged["executors"]
+                    .as_object_mut()
+                    .expect("executors serialize as an object")
+                    .entry(executor.clone())
+                    .or_insert_with(|| Value::Object(Default::default()));
+                for (variant, config) in variants {
+                    match target.get_mut(variant) {
+                        Some(existing) => deep_merge(existing, config),
+                        None => {
+                            target[variant] = config.clone();
+                        }
+                    }
+                }
+            }
+        }
+
+        let merged: Self = serde_json::from_value(merged)?;
+        Self::validate_merged(&merged)?;
+        Ok(merged)
+    }
+
+    /// Overlay another (partial) set of profiles on top of this one
+    pub fn merged_with(self, overrides: Self) -> Self {
+        let mut overrides = overrides;
+        overrides.canonicalise();
+        Self::merge_with_defaults(self, overrides)
+    }
+
+    /// Only the parts of this config that differ from the built-in defaults
+    pub fn overrides(&self) -> Result<Self, ProfileError> {
+        let mut defaults = Self::from_defaults();
+        defaults.canonicalise();
+        let mut self_clone = self.clone();
+        self_clone.canonicalise();
+        Self::compute_overrides(&defaults, &self_clone)
+    }
+
+    /// JSON Schema for `profiles.json`, generated from the executor structs
+    pub fn json_schema() -> Value {
+        serde_json::to_value(schemars::schema_for!(ExecutorConfigs))
+            .expect("JSON Schema is always serializable")
+    }
+
+    /// Set a (dot-separated) field on a profile variant.
+    /// Field names are checked against the profile JSON schema and the result is deserialized
+    /// back into the executor struct, so unknown fields and type errors are rejected. Setting a
+    /// field on a missing variant creates it from `DEFAULT`.
+    pub fn set_field(
+        &mut self,
+        id: &ExecutorProfileId,
+        field: &str,
+        value: Value,
+    ) -> Result<(), ProfileError> {
+        self.edit_variant(id, field, true, |fields| {
+            set_json_path(fields, field, value);
+            Ok(())
+        })
+    }
+
+    /// Remove a (dot-separated) field from a profile variant, falling back to its default value
+    pub fn unset_field(&mut self, id: &ExecutorProfileId, field: &str) -> Result<(), ProfileError> {
+        self.edit_variant(id, field, false, |fields| {
+            if remove_json_path(fields, field) {
+                Ok(())
+            } else {
+                Err(ProfileError::Validation(format!(
+                    "Field '{field}' is not set on '{id}'"
+                )))
+            }
+        })
+    }
+
+    /// Restore a built-in variant to its default, or remove a user-defined variant
+    pub fn reset_variant(&mut self, id: &ExecutorProfileId) -> Result<(), ProfileError> {
+        let key = canonical_variant_key(id.variant.as_deref().unwrap_or("DEFAULT"));
+        let mut defaults = Self::from_defaults();
+        defaults.canonicalise();
+        let builtin = defaults
+            .executors
+            .get(&id.executor)
+            .and_then(|profile| profile.configurations.get(&key))
+            .cloned();
+
+        let profile = self.executors.get_mut(&id.executor).ok_or_else(|| {
+            ProfileError::Validation(format!("No profiles for executor '{}'", id.executor))
+        })?;
+        match builtin {
+            Some(config) => {
+                profile.configurations.insert(key, config);
+            }
+            None => {
+                profile.configurations.remove(&key).ok_or_else(|| {
+                    ProfileError::Validation(format!("Unknown profile variant '{id}'"))
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    fn edit_variant(
+        &mut self,
+        id: &ExecutorProfileId,
+        field: &str,
+        check_known: bool,
+        edit: impl FnOnce(&mut serde_json::Map<String, Value>) -> Result<(), ProfileError>,
+    ) -> Result<(), ProfileError> {
+        if field.is_empty() || field.split('.').any(str::is_empty) {
+            return Err(ProfileError::Validation(format!(
+                "Invalid field path '{field}'"
+            )));
+        }
+
+        let tag = id.executor.to_string();
+        // Serde silently drops unknown fields, so check the name against the schema
+        if check_known {
+            let schema = serde_json::to_value(schemars::schema_for!(CodingAgent))?;
+            let path: Vec<&str> = std::iter::once(tag.as_str())
+                .chain(field.split('.'))
+                .collect();
+            if !schema_has_path(&schema, &schema, &path) {
+                return Err(ProfileError::Validation(format!(
+                    "Unknown field '{field}' for executor '{}'",
+                    id.executor
+                )));
+            }
+        }
+
+        let key = canonical_variant_key(id.variant.as_deref().unwrap_or("DEFAULT"));
+        let profile = self.executors.get_mut(&id.executor).ok_or_else(|| {
+            ProfileError::Validation(format!("No profiles for executor '{}'", id.executor))
+        })?;
+        let current = profile
+            .configurations
+            .get(&key)
+            .or_else(|| profile.get_default())
+            .ok_or_else(|| {
+                ProfileError::Validation(format!(
+                    "Executor '{}' has no DEFAULT configuration",
+                    id.executor
+                ))
+            })?;
+
+        let mut value = serde_json::to_value(current)?;
+        let fields = value
+            .get_mut(&tag)
+            .and_then(Value::as_object_mut)
+            .ok_or_else(|| {
+                ProfileError::Validation(format!("Unexpected configuration shape for '{id}'"))
+            })?;
+        edit(fields)?;
+
+        let updated: CodingAgent = serde_json::from_value(value).map_err(|e| {
+            ProfileError::Validation(format!("Invalid value for '{field}' on '{id}': {e}"))
+        })?;
+
+        profile.configurations.insert(key, updated);
+        Ok(())
+    }
+
     /// Deep merge defaults with user overrides
     fn merge_with_defaults(mut defaults: Self, overrides: Self) -> Self {
         for (executor_key, override_profile) in overrides.executors {
@@ -351,7 +629,7 @@ impl ExecutorConfigs {
     }
 
     /// Validate that merged profiles are consistent and valid
-    fn validate_merged(merged: &Self) -> Result<(), ProfileError> {
+    pub fn validate_merged(merged: &Self) -> Result<(), ProfileError> {
         for (executor_key, profile) in &merged.executors {
             // Ensure default configuration exists
             let default_config = profile.configurations.get("DEFAULT").ok_or_else(|| {
@@ -368,12 +646,17 @@ impl ExecutorConfigs {
             }
 
             // Ensure configuration names don't conflict with reserved words
-            for config_name in profile.configurations.keys() {
+            for (config_name, agent) in &profile.configurations {
                 if config_name.starts_with("__") {
                     return Err(ProfileError::Validation(format!(
                         "Configuration name '{config_name}' is reserved (starts with '__')"
                     )));
                 }
+                if let CodingAgent::Custom(custom) = agent {
+                    custom.validate().map_err(|err| {
+                        ProfileError::Validation(format!("{executor_key}:{config_name}: {err}"))
+                    })?;
+                }
             }
         }
         Ok(())
@@ -399,6 +682,12 @@ impl ExecutorConfigs {
                 )
             })
             .cloned()
+            .or_else(|| {
+                // Plugins in the plugins directory run without a profile
+                (executor_profile_id.executor == BaseCodingAgent::Plugin)
+                    .then(|| plugin::discovered_agent(executor_profile_id.variant.as_deref()))
+                    .flatten()
+            })
     }
 
     pub fn get_coding_agent_or_default(
@@ -482,3 +771,617 @@ pub fn to_default_variant(id: &ExecutorProfileId) -> ExecutorProfileId {
         variant: None,
     }
 }
+
+/// Location of project-local profiles, relative to a repository root
+pub const PROJECT_PROFILES_PATH: &str = ".code-marshal/profiles.json";
+
+/// Find `.code-marshal/profiles.json` in `start` or the closest ancestor
+pub fn find_project_profiles(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(PROJECT_PROFILES_PATH))
+        .find(|path| path.is_file())
+}
+
+/// Fields an untrusted project layer may set: nothing that runs a command, changes the
+/// environment or loosens approvals
+pub const PROJECT_SAFE_FIELDS: &[&str] = &[
+    "model",
+    "variant",
+    "plan",
+    "reasoning_effort",
+    "model_reasoning_summary",
+    "model_reasoning_summary_format",
+    "append_prompt",
+];
+
+/// The repository a project profiles file belongs to (the parent of `.code-marshal`)
+pub fn project_root(project_path: &Path) -> Option<&Path> {
+    project_path.parent().and_then(Path::parent)
+}
+
+fn read_trusted_projects() -> Vec<PathBuf> {
+    fs::read_to_string(workspace_utils::assets::trusted_projects_path())
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+/// Whether the user trusts `root` to set every profile field from its project layer
+pub fn is_trusted_project(root: &Path) -> bool {
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    read_trusted_projects().contains(&root)
+}
+
+/// Add `root` to (or, with `trusted == false`, remove it from) the trusted project list.
+/// Returns whether the list changed.
+pub fn set_project_trusted(root: &Path, trusted: bool) -> Result<bool, ProfileError> {
+    let root = root.canonicalize()?;
+    let mut projects = read_trusted_projects();
+    let listed = projects.contains(&root);
+    if listed == trusted {
+        return Ok(false);
+    }
+    if trusted {
+        projects.push(root);
+    } else {
+        projects.retain(|p| *p != root);
+    }
+    fs::write(
+        workspace_utils::assets::trusted_projects_path(),
+        serde_json::to_string_pretty(&projects)?,
+    )?;
+    Ok(true)
+}
+
+/// Drop every field outside [`PROJECT_SAFE_FIELDS`] from a project layer, returning the
+/// dropped `EXECUTOR:VARIANT.field` paths
+fn restrict_project_layer(layer: &mut Value) -> Vec<String> {
+    let mut dropped = Vec::new();
+    let Some(executors) = layer.get_mut("executors").and_then(Value::as_object_mut) else {
+        return dropped;
+    };
+    for (executor, variants) in executors {
+        let Some(variants) = variants.as_object_mut() else {
+            continue;
+        };
+        for (variant, config) in variants {
+            let Some(config) = config.as_object_mut() else {
+                continue;
+            };
+            for fields in config.values_mut().filter_map(Value::as_object_mut) {
+                fields.retain(|field, _| {
+                    let safe = PROJECT_SAFE_FIELDS.contains(&field.as_str());
+                    if !safe {
+                        dropped.push(format!("{executor}:{variant}.{field}"));
+                    }
+                    safe
+                });
+            }
+        }
+    }
+    dropped
+}
+
+/// Read a project layer, restricted to [`PROJECT_SAFE_FIELDS`] unless the project is trusted
+fn read_trusted_project_layer(path: &Path) -> Result<Value, ProfileError> {
+    let mut layer = read_project_layer(path)?;
+    if project_root(path).is_some_and(is_trusted_project) {
+        return Ok(layer);
+    }
+    let dropped = restrict_project_layer(&mut layer);
+    if !dropped.is_empty() {
+        tracing::warn!(
+            "Ignoring {} from untrusted project profiles {:?}; run `code-marshal profiles trust` \
+             to apply them",
+            dropped.join(", "),
+            path
+        );
+    }
+    Ok(layer)
+}
+
+/// Read a project layer, normalising executor and variant keys
+fn read_project_layer(path: &Path) -> Result<Value, ProfileError> {
+    let raw: Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+    let executors = raw
+        .get("executors")
+        .and_then(Value::as_object)
+        .ok_or_else(|| ProfileError::Validation("missing 'executors' object".to_string()))?;
+
+    let mut normalised = serde_json::Map::new();
+    for (executor, variants) in executors {
+        let id = ExecutorProfileId::from_str(executor)?;
+        let variants = variants.as_object().ok_or_else(|| {
+            ProfileError::Validation(format!("'{executor}' must be an object of variants"))
+        })?;
+        let entry = normalised
+            .entry(id.executor.to_string())
+            .or_insert_with(|| Value::Object(Default::default()));
+        for (variant, config) in variants {
+            entry[canonical_variant_key(variant)] = config.clone();
+        }
+    }
+    Ok(serde_json::json!({ "executors": normalised }))
+}
+
+/// Merge `overlay` into `base`; objects merge recursively, `null` removes a field
+fn deep_merge(base: &mut Value, overlay: &Value) {
+    match (base.as_object_mut(), overlay.as_object()) {
+        (Some(base), Some(overlay)) => {
+            for (key, value) in overlay {
+                if value.is_null() {
+                    base.remove(key);
+                } else if let Some(existing) = base.get_mut(key) {
+                    deep_merge(existing, value);
+                } else {
+                    base.insert(key.clone(), value.clone());
+                }
+            }
+        }
+        _ => *base = overlay.clone(),
+    }
+}
+
+/// A configuration layer, lowest precedence first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProfileLayer {
+    Default,
+    User,
+    Project,
+}
+
+impl std::fmt::Display for ProfileLayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Default => write!(f, "default"),
+            Self::User => write!(f, "user"),
+            Self::Project => write!(f, "project"),
+        }
+    }
+}
+
+/// An effective profile field and the layer it came from
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldSource {
+    pub field: String,
+    pub value: Value,
+    pub layer: ProfileLayer,
+}
+
+/// The profile layers on disk. Unlike [`ExecutorConfigs::load`], parse errors are returned.
+#[derive(Debug, Clone)]
+pub struct ProfileLayers {
+    pub user_path: PathBuf,
+    pub user: Option<ExecutorConfigs>,
+    pub project_path: Option<PathBuf>,
+    pub project: Option<Value>,
+    /// Whether the project layer may set every field, see [`is_trusted_project`]
+    pub project_trusted: bool,
+}
+
+impl ProfileLayers {
+    pub fn discover(cwd: &Path) -> Result<Self, ProfileError> {
+        Self::discover_with(workspace_utils::assets::profiles_path(), cwd)
+    }
+
+    fn discover_with(user_path: PathBuf, cwd: &Path) -> Result<Self, ProfileError> {
+        let user = match fs::read_to_string(&user_path) {
+            Ok(content) => {
+                let mut user: ExecutorConfigs = serde_json::from_str(&content)?;
+                user.canonicalise();
+                Some(user)
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+        let project_path = find_project_profiles(cwd);
+        let project_trusted = project_path
+            .as_deref()
+            .and_then(project_root)
+            .is_some_and(is_trusted_project);
+        let project = project_path
+            .as_deref()
+            .map(read_trusted_project_layer)
+            .transpose()?;
+
+        Ok(Self {
+            user_path,
+            user,
+            project_path,
+            project,
+            project_trusted,
+        })
+    }
+
+    /// Merge all layers and validate the result
+    pub fn merged(&self) -> Result<ExecutorConfigs, ProfileError> {
+        let mut defaults = ExecutorConfigs::from_defaults();
+        defaults.canonicalise();
+        let mut merged = match &self.user {
+            Some(user) => ExecutorConfigs::merge_with_defaults(defaults, user.clone()),
+            None => defaults,
+        };
+        ExecutorConfigs::validate_merged(&merged)?;
+        if let Some(project) = &self.project {
+            merged = merged.with_project_layer(project)?;
+        }
+        Ok(merged)
+    }
+
+    /// List every effective field of a profile with the layer that set it
+    pub fn explain(&self, id: &ExecutorProfileId) -> Result<Vec<FieldSource>, ProfileError> {
+        let variant = canonical_variant_key(id.variant.as_deref().unwrap_or("DEFAULT"));
+        let tag = id.executor.to_string();
+        let effective = self
+            .merged()?
+            .get_coding_agent(&ExecutorProfileId::with_variant(
+                id.executor,
+                variant.clone(),
+            ))
+            .ok_or_else(|| ProfileError::Validation(format!("Unknown profile '{id}'")))?;
+        let effective = serde_json::to_value(effective)?;
+
+        let user = self
+            .user
+            .as_ref()
+            .and_then(|user| user.executors.get(&id.executor))
+            .and_then(|config| config.get_variant(&variant))
+            .map(serde_json::to_value)
+            .transpose()?;
+        let project = self
+            .project
+            .as_ref()
+            .and_then(|project| project["executors"][&tag].get(&variant));
+
+        let mut leaves = Vec::new();
+        collect_leaves(&effective[&tag], String::new(), &mut leaves);
+        Ok(leaves
+            .into_iter()
+            .map(|(field, value)| {
+                let set_in = |layer: Option<&Value>| {
+                    layer
+                        .and_then(|config| config.get(&tag))
+                        .and_then(|fields| get_json_path(fields, &field))
+                        .is_some_and(|v| !v.is_null())
+                };
+                let layer = if set_in(project) {
+                    ProfileLayer::Project
+                } else if set_in(user.as_ref()) {
+                    ProfileLayer::User
+                } else {
+                    ProfileLayer::Default
+                };
+                FieldSource {
+                    field,
+                    value,
+                    layer,
+                }
+            })
+            .collect())
+    }
+}
+
+fn collect_leaves(value: &Value, prefix: String, out: &mut Vec<(String, Value)>) {
+    match value.as_object() {
+        Some(fields) => {
+            for (key, value) in fields {
+                let path = if prefix.is_empty() {
+                    key.clone()
+                } else {
+                    format!("{prefix}.{key}")
+                };
+                collect_leaves(value, path, out);
+            }
+        }
+        None if !value.is_null() && !prefix.is_empty() => out.push((prefix, value.clone())),
+        None => {}
+    }
+}
+
+fn get_json_path<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    path.split('.').try_fold(value, |v, segment| v.get(segment))
+}
+
+/// Whether `path` names a property somewhere in `schema`, following `$ref`s into `root` and
+/// through `allOf`/`anyOf`/`oneOf`; any key is accepted under a map's `additionalProperties`.
+fn schema_has_path(root: &Value, schema: &Value, path: &[&str]) -> bool {
+    let Some((segment, rest)) = path.split_first() else {
+        return true;
+    };
+    if let Some(target) = schema
+        .get("$ref")
+        .and_then(Value::as_str)
+        .and_then(|r| root.pointer(r.strip_prefix('#').unwrap_or(r)))
+        && schema_has_path(root, target, path)
+    {
+        return true;
+    }
+    for combinator in ["allOf", "anyOf", "oneOf"] {
+        if let Some(variants) = schema.get(combinator).and_then(Value::as_array)
+            && variants.iter().any(|v| schema_has_path(root, v, path))
+        {
+            return true;
+        }
+    }
+    if let Some(property) = schema.get("properties").and_then(|p| p.get(*segment)) {
+        return schema_has_path(root, property, rest);
+    }
+    match schema.get("additionalProperties") {
+        Some(Value::Bool(true)) => true,
+        Some(inner @ Value::Object(_)) => schema_has_path(root, inner, rest),
+        _ => false,
+    }
+}
+
+fn set_json_path(fields: &mut serde_json::Map<String, Value>, path: &str, value: Value) {
+    let mut segments: Vec<&str> = path.split('.').collect();
+    let last = segments.pop().expect("path has at least one segment");
+    let mut current = fields;
+    for segment in segments {
+        let entry = current
+            .entry(segment.to_string())
+            .or_insert_with(|| Value::Object(Default::default()));
+        if !entry.is_object() {
+            *entry = Value::Object(Default::default());
+        }
+        current = entry.as_object_mut().expect("just ensured object");
+    }
+    current.insert(last.to_string(), value);
+}
+
+fn remove_json_path(fields: &mut serde_json::Map<String, Value>, path: &str) -> bool {
+    match path.split_once('.') {
+        Some((head, rest)) => fields
+            .get_mut(head)
+            .and_then(Value::as_object_mut)
+            .is_some_and(|inner| remove_json_path(inner, rest)),
+        None => fields.remove(path).is_some_and(|v| !v.is_null()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn defaults() -> ExecutorConfigs {
+        let mut configs = ExecutorConfigs::from_defaults();
+        configs.canonicalise();
+        configs
+    }
+
+    #[test]
+    fn parses_profile_ids() {
+        let id: ExecutorProfileId = "claude-code:plan".parse().unwrap();
+        assert_eq!(id.executor, BaseCodingAgent::ClaudeCode);
+        assert_eq!(id.variant.as_deref(), Some("PLAN"));
+
+        let id: ExecutorProfileId = "CODEX".parse().unwrap();
+        assert_eq!(id, ExecutorProfileId::new(BaseCodingAgent::Codex));
+
+        assert!("NOPE".parse::<ExecutorProfileId>().is_err());
+        assert!("CODEX:".parse::<ExecutorProfileId>().is_err());
+    }
+
+    #[test]
+    fn set_field_is_typed() {
+        let mut configs = defaults();
+        let id: ExecutorProfileId = "CLAUDE_CODE:PLAN".parse().unwrap();
+
+        configs
+            .set_field(&id, "model", Value::String("opus".into()))
+            .unwrap();
+        let agent = serde_json::to_value(configs.get_coding_agent(&id).unwrap()).unwrap();
+        assert_eq!(agent["CLAUDE_CODE"]["model"], "opus");
+        assert_eq!(agent["CLAUDE_CODE"]["plan"], true);
+
+        // Wrong type
+        assert!(
+            configs
+                .set_field(&id, "plan", Value::String("yes".into()))
+                .is_err()
+        );
+        // Unknown field
+        assert!(
+            configs
+                .set_field(&id, "no_such_field", Value::Bool(true))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn set_field_accepts_values_serde_skips() {
+        let mut configs = defaults();
+        let id = ExecutorProfileId::new(BaseCodingAgent::Acp);
+
+        // `args` is skipped when empty and `model` when None, but both are known fields
+        configs
+            .set_field(&id, "args", serde_json::json!([]))
+            .unwrap();
+        configs.set_field(&id, "model", Value::Null).unwrap();
+        assert!(
+            configs
+                .set_field(&id, "no_such_field", Value::Null)
+                .is_err()
+        );
+        assert!(
+            configs
+                .set_field(&id, "args.nested", Value::Bool(true))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn set_field_creates_variant_and_env() {
+        let mut configs = defaults();
+        let id: ExecutorProfileId = "CODEX:FAST".parse().unwrap();
+
+        configs
+            .set_field(&id, "env.FOO", Value::String("bar".into()))
+            .unwrap();
+        let agent = serde_json::to_value(configs.get_coding_agent(&id).unwrap()).unwrap();
+        assert_eq!(agent["CODEX"]["env"]["FOO"], "bar");
+        assert_eq!(agent["CODEX"]["sandbox"], "danger-full-access");
+
+        let overrides = configs.overrides().unwrap();
+        assert_eq!(overrides.executors.len(), 1);
+        assert!(
+            overrides.executors[&BaseCodingAgent::Codex]
+                .configurations
+                .contains_key("FAST")
+        );
+    }
+
+    #[test]
+    fn unset_and_reset() {
+        let mut configs = defaults();
+        let id: ExecutorProfileId = "CLAUDE_CODE:PLAN".parse().unwrap();
+
+        configs.unset_field(&id, "plan").unwrap();
+        assert!(configs.unset_field(&id, "plan").is_err());
+        assert!(!configs.overrides().unwrap().executors.is_empty());
+
+        configs.reset_variant(&id).unwrap();
+        assert!(configs.overrides().unwrap().executors.is_empty());
+
+        let custom: ExecutorProfileId = "CLAUDE_CODE:MINE".parse().unwrap();
+        configs
+            .set_field(&custom, "model", Value::String("sonnet".into()))
+            .unwrap();
+        configs.reset_variant(&custom).unwrap();
+        assert!(configs.get_coding_agent(&custom).is_none());
+    }
+
+    #[test]
+    fn project_layer_merges_fields() {
+        let layer = serde_json::json!({
+            "executors": {
+                "CLAUDE_CODE": {
+                    "DEFAULT": { "CLAUDE_CODE": { "model": "opus", "env": { "FOO": "bar" } } },
+                    "team": { "CLAUDE_CODE": { "plan": true } }
+                }
+            }
+        });
+        let dir = std::env::temp_dir().join(format!("profiles-{}", uuid::Uuid::new_v4()));
+        let path = dir.join(PROJECT_PROFILES_PATH);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, layer.to_string()).unwrap();
+        assert_eq!(
+            find_project_profiles(&dir.join("nested/dir")),
+            Some(path.clone())
+        );
+
+        let layers = ProfileLayers {
+            user_path: dir.join("user.json"),
+            user: None,
+            project_path: Some(path.clone()),
+            project: Some(read_project_layer(&path).unwrap()),
+            project_trusted: true,
+        };
+        let merged = layers.merged().unwrap();
+        let agent = serde_json::to_value(
+            merged
+                .get_coding_agent(&ExecutorProfileId::new(BaseCodingAgent::ClaudeCode))
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(agent["CLAUDE_CODE"]["model"], "opus");
+        assert_eq!(agent["CLAUDE_CODE"]["dangerously_skip_permissions"], true);
+        assert!(
+            merged
+                .get_coding_agent(&"CLAUDE_CODE:TEAM".parse().unwrap())
+                .is_some()
+        );
+
+        let sources = layers
+            .explain(&ExecutorProfileId::new(BaseCodingAgent::ClaudeCode))
+            .unwrap();
+        let layer_of = |field: &str| sources.iter().find(|s| s.field == field).unwrap().layer;
+        assert_eq!(layer_of("model"), ProfileLayer::Project);
+        assert_eq!(layer_of("env.FOO"), ProfileLayer::Project);
+        assert_eq!(
+            layer_of("dangerously_skip_permissions"),
+            ProfileLayer::Default
+        );
+
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn reload_keeps_last_good_config() {
+        let dir = std::env::temp_dir().join(format!("profiles-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let user_path = dir.join("profiles.json");
+        let load = || ProfileLayers::discover_with(user_path.clone(), &dir)?.merged();
+        let cache = RwLock::new(defaults());
+
+        // No user file: the defaults, which the cache already holds
+        assert!(!ExecutorConfigs::reload_into(&cache, load()).unwrap());
+
+        // A valid edit is swapped in
+        let mut edited = defaults();
+        let id: ExecutorProfileId = "CODEX:FAST".parse().unwrap();
+        edited
+            .set_field(&id, "model", Value::String("gpt-5".into()))
+            .unwrap();
+        fs::write(
+            &user_path,
+            serde_json::to_string(&edited.overrides().unwrap()).unwrap(),
+        )
+        .unwrap();
+        assert!(ExecutorConfigs::reload_into(&cache, load()).unwrap());
+        assert!(cache.read().unwrap().get_coding_agent(&id).is_some());
+
+        // Invalid edits are rejected and the last good config stays
+        fs::write(&user_path, "{ not json").unwrap();
+        assert!(ExecutorConfigs::reload_into(&cache, load()).is_err());
+        fs::write(
+            &user_path,
+            r#"{"executors":{"CODEX":{"DEFAULT":{"CLAUDE_CODE":{}}}}}"#,
+        )
+        .unwrap();
+        assert!(matches!(load(), Err(ProfileError::Validation(_))));
+        assert!(ExecutorConfigs::reload_into(&cache, load()).is_err());
+        assert_eq!(*cache.read().unwrap(), edited);
+
+        fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn untrusted_project_layer_keeps_safe_fields() {
+        let mut layer = serde_json::json!({
+            "executors": {
+                "CODEX": {
+                    "DEFAULT": { "CODEX": {
+                        "model": "gpt-5",
+                        "env": { "FOO": "bar" },
+                        "base_command_override": "sh -c evil",
+                        "sandbox": "danger-full-access"
+                    } }
+                }
+            }
+        });
+        let mut dropped = restrict_project_layer(&mut layer);
+        dropped.sort();
+        assert_eq!(
+            dropped,
+            [
+                "CODEX:DEFAULT.base_command_override",
+                "CODEX:DEFAULT.env",
+                "CODEX:DEFAULT.sandbox"
+            ]
+        );
+        assert_eq!(
+            layer["executors"]["CODEX"]["DEFAULT"]["CODEX"],
+            serde_json::json!({ "model": "gpt-5" })
+        );
+    }
+
+    #[test]
+    fn json_schema_covers_executors() {
+        let schema = ExecutorConfigs::json_schema().to_string();
+        assert!(schema.contains("CLAUDE_CODE"));
+        assert!(schema.contains("dangerously_skip_permissions"));
+    }
+}
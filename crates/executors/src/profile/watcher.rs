@@ -0,0 +1,152 @@
+//! Hot reload of the profiles cache for long-running processes.
+//!
+//! The user and project profiles files are polled for changes; a changed file is validated
+//! (see [`ExecutorConfigs::reload`]) before the cache is swapped, so a broken edit keeps the
+//! last good config active.
+
+use std::{
+    path::PathBuf,
+    sync::LazyLock,
+    time::{Duration, SystemTime},
+};
+
+use serde::Serialize;
+use tokio::{sync::broadcast, task::JoinHandle, time::MissedTickBehavior};
+use tokio_util::sync::CancellationToken;
+
+use super::{ExecutorConfigs, ProfileError, find_project_profiles};
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProfilesEvent {
+    /// The cache now holds a new, validated config
+    Changed { paths: Vec<PathBuf> },
+    /// A profiles file changed but failed to load; the previous config is still active
+    Rejected { paths: Vec<PathBuf>, error: String },
+}
+
+static PROFILE_EVENTS: LazyLock<broadcast::Sender<ProfilesEvent>> =
+    LazyLock::new(|| broadcast::channel(16).0);
+
+/// Subscribe to profile reload events
+pub fn subscribe_profile_events() -> broadcast::Receiver<ProfilesEvent> {
+    PROFILE_EVENTS.subscribe()
+}
+
+type Snapshot = Vec<(PathBuf, Option<(SystemTime, u64)>)>;
+
+fn watched_paths() -> Vec<PathBuf> {
+    let mut paths = vec![workspace_utils::assets::profiles_path()];
+    if let Some(project) = std::env::current_dir()
+        .ok()
+        .and_then(|cwd| find_project_profiles(&cwd))
+    {
+        paths.push(project);
+    }
+    paths
+}
+
+fn snapshot(paths: &[PathBuf]) -> Snapshot {
+    paths
+        .iter()
+        .map(|path| {
+            let stamp = std::fs::metadata(path)
+                .ok()
+                .and_then(|meta| Some((meta.modified().ok()?, meta.len())));
+            (path.clone(), stamp)
+        })
+        .collect()
+}
+
+/// Reload when `paths` differ from the `last` snapshot. Returns the event to emit, if any.
+fn poll(
+    paths: &[PathBuf],
+    last: &mut Snapshot,
+    reload: impl FnOnce() -> Result<bool, ProfileError>,
+) -> Option<ProfilesEvent> {
+    let current = snapshot(paths);
+    if current == *last {
+        return None;
+    }
+    *last = current;
+
+    let paths: Vec<PathBuf> = last
+        .iter()
+        .filter(|(_, stamp)| stamp.is_some())
+        .map(|(path, _)| path.clone())
+        .collect();
+    match reload() {
+        Ok(true) => {
+            tracing::info!("Reloaded executor profiles from {:?}", paths);
+            Some(ProfilesEvent::Changed { paths })
+        }
+        Ok(false) => None,
+        Err(e) => {
+            tracing::warn!("Ignoring invalid profiles change, keeping last good config: {e}");
+            Some(ProfilesEvent::Rejected {
+                paths,
+                error: e.to_string(),
+            })
+        }
+    }
+}
+
+/// Poll the profiles files every `interval` and reload the cache when they change.
+/// Emits a [`ProfilesEvent`] for every reload attempt that follows a change.
+pub fn spawn_profiles_watcher(interval: Duration, cancel: CancellationToken) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut last = snapshot(&watched_paths());
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        loop {
+            tokio::select! {
+                _ = cancel.cancelled() => break,
+                _ = ticker.tick() => {}
+            }
+
+            let Some(event) = poll(&watched_paths(), &mut last, ExecutorConfigs::reload) else {
+                continue;
+            };
+            // No subscribers is fine
+            let _ = PROFILE_EVENTS.send(event);
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn poll_reloads_only_on_change() {
+        let dir = std::env::temp_dir().join(format!("profiles-watch-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("profiles.json");
+        let paths = vec![path.clone()];
+        let mut last = snapshot(&paths);
+
+        // Nothing changed: no reload
+        assert!(poll(&paths, &mut last, || panic!("reloaded without a change")).is_none());
+
+        std::fs::write(&path, "{}").unwrap();
+        let event = poll(&paths, &mut last, || Ok(true));
+        assert!(matches!(event, Some(ProfilesEvent::Changed { paths }) if paths == [path.clone()]));
+        assert!(poll(&paths, &mut last, || panic!("reloaded twice")).is_none());
+
+        // A change that loads the same config is not reported
+        std::fs::write(&path, "{ }").unwrap();
+        assert!(poll(&paths, &mut last, || Ok(false)).is_none());
+
+        // An invalid change is reported as rejected
+        std::fs::write(&path, "{ not json").unwrap();
+        let event = poll(&paths, &mut last, || {
+            Err(ProfileError::Validation("broken".to_string()))
+        });
+        assert!(
+            matches!(event, Some(ProfilesEvent::Rejected { error, .. }) if error.contains("broken"))
+        );
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,402 @@
+//! Protected paths: files agents must not change, such as migrations, CI config, lockfiles or
+//! secrets. Patterns come from `.code-marshal/protected-paths` at the repository root (one per
+//! line, `#` comments) and `--protect`, and use gitignore-like globs:
+//! - a pattern without a `/` (`Cargo.lock`, `*.pem`) matches a file or directory name at any
+//!   depth; otherwise it is relative to the repository root (`.github/workflows/*.yml`)
+//! - `*` and `?` stay within one path segment, `**` crosses segments
+//! - a matching directory protects everything below it (`migrations/`)
+//!
+//! Tool calls touching a protected path are denied before they run (see
+//! [`crate::approvals::ProtectedPathsApprovalService`]; agents are switched to asking with
+//! [`crate::executors::StandardCodingAgentExecutor::require_approvals`]), and changes that
+//! slipped through, e.g. from agents that cannot ask, are found after the run from the git diff.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+use ts_rs::TS;
+
+use crate::changes::repo_relative;
+
+/// Repository config file with one protected path pattern per line
+pub const PROJECT_PROTECTED_PATHS: &str = ".code-marshal/protected-paths";
+
+#[derive(Debug, Error)]
+pub enum ProtectedPathsError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("invalid protected path pattern '{pattern}': {error}")]
+    Pattern {
+        pattern: String,
+        error: regex::Error,
+    },
+}
+
+/// What happened to a change to a protected path
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum ProtectedPathOutcome {
+    /// The tool call was denied before it ran
+    Blocked,
+    /// The run changed the path
+    Changed,
+    /// The run changed the path and the change was reverted
+    Reverted,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export)]
+pub struct ProtectedPathViolation {
+    /// Repo-relative path
+    pub path: String,
+    /// The pattern protecting it
+    pub pattern: String,
+    pub outcome: ProtectedPathOutcome,
+    /// The denied tool, for blocked tool calls
+    pub tool_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ProtectedPaths {
+    root: PathBuf,
+    rules: Vec<(String, Regex)>,
+}
+
+impl ProtectedPaths {
+    /// Patterns relative to the repository at `root`
+    pub fn new<I, S>(root: PathBuf, patterns: I) -> Result<Self, ProtectedPathsError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let mut rules = Vec::new();
+        for pattern in patterns {
+            let pattern = pattern.as_ref().trim();
+            if pattern.is_empty() || pattern.starts_with('#') {
+                continue;
+            }
+            let regex = Regex::new(&glob_to_regex(pattern)).map_err(|error| {
+                ProtectedPathsError::Pattern {
+                    pattern: pattern.to_string(),
+                    error,
+                }
+            })?;
+            rules.push((pattern.to_string(), regex));
+        }
+        Ok(Self { root, rules })
+    }
+
+    /// The patterns of `root`'s [`PROJECT_PROTECTED_PATHS`] file (if any) followed by `extra`
+    pub fn load(root: &Path, extra: &[String]) -> Result<Self, ProtectedPathsError> {
+        let file = match fs::read_to_string(root.join(PROJECT_PROTECTED_PATHS)) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let patterns = file
+            .lines()
+            .map(str::to_string)
+            .chain(extra.iter().cloned());
+        Self::new(root.to_path_buf(), patterns.collect::<Vec<_>>())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    pub fn patterns(&self) -> impl Iterator<Item = &str> {
+        self.rules.iter().map(|(pattern, _)| pattern.as_str())
+    }
+
+    /// The first pattern protecting `repo_path` (relative to the repository root)
+    pub fn matching(&self, repo_path: &str) -> Option<&str> {
+        let repo_path = repo_path.trim_start_matches("./").trim_end_matches('/');
+        self.rules
+            .iter()
+            .find(|(_, regex)| regex.is_match(repo_path))
+            .map(|(pattern, _)| pattern.as_str())
+    }
+
+    /// Protected paths an agent tool call would change: the paths of file edits, and any path
+    /// named by a shell command (commands cannot be told apart into reads and writes reliably)
+    pub fn violations_in_tool_call(
+        &self,
+        tool_name: &str,
+        tool_input: &Value,
+        current_dir: &Path,
+    ) -> Vec<ProtectedPathViolation> {
+        let candidates = match ToolKind::of(tool_name, tool_input) {
+            ToolKind::Edit => edit_paths(tool_input),
+            ToolKind::Command => command_paths(tool_input),
+            ToolKind::Other => return Vec::new(),
+        };
+        let mut violations: Vec<ProtectedPathViolation> = Vec::new();
+        for candidate in candidates {
+            let Some(path) = repo_relative(&candidate, &self.root, current_dir) else {
+                continue;
+            };
+            if violations.iter().any(|v| v.path == path) {
+                continue;
+            }
+            if let Some(pattern) = self.matching(&path) {
+                violations.push(ProtectedPathViolation {
+                    pattern: pattern.to_string(),
+                    path,
+                    outcome: ProtectedPathOutcome::Blocked,
+                    tool_name: Some(tool_name.to_string()),
+                });
+            }
+        }
+        violations
+    }
+}
+
+fn glob_to_regex(pattern: &str) -> String {
+    let trimmed = pattern.trim_end_matches('/');
+    let anchored = trimmed.contains('/');
+    let glob = trimmed.trim_start_matches('/');
+
+    let mut regex = String::from("^");
+    if !anchored {
+        regex.push_str("(?:.*/)?");
+    }
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    regex.push_str("(?:.*/)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push_str("(?:/.*)?$");
+    regex
+}
+
+enum ToolKind {
+    Edit,
+    Command,
+    Other,
+}
+
+impl ToolKind {
+    fn of(tool_name: &str, tool_input: &Value) -> Self {
+        // ACP agents describe the tool call's kind
+        if let Some(kind) = tool_input
+            .pointer("/tool_call/kind")
+            .and_then(Value::as_str)
+        {
+            return match kind {
+                "edit" | "delete" | "move" => Self::Edit,
+                "execute" => Self::Command,
+                _ => Self::Other,
+            };
+        }
+        match tool_name.to_ascii_lowercase().as_str() {
+            "edit" | "multiedit" | "write" | "notebookedit" | "patch" | "apply_patch" => Self::Edit,
+            "bash" | "shell" | "exec" | "execute" => Self::Command,
+            _ => Self::Other,
+        }
+    }
+}
+
+const PATH_KEYS: &[&str] = &[
+    "path",
+    "file_path",
+    "filePath",
+    "filepath",
+    "notebook_path",
+    "old_path",
+    "new_path",
+    "move_path",
+];
+
+/// Paths in an edit tool's input: values of path-like keys, keys of change maps (Codex
+/// `file_changes`) and OpenCode permission `patterns`, at any depth
+fn edit_paths(value: &Value) -> Vec<String> {
+    let mut paths = Vec::new();
+    collect_edit_paths(value, &mut paths);
+    paths
+}
+
+fn collect_edit_paths(value: &Value, paths: &mut Vec<String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                match (key.as_str(), value) {
+                    (key, Value::String(path)) if PATH_KEYS.contains(&key) => {
+                        paths.push(path.clone())
+                    }
+                    ("changes" | "file_changes", Value::Object(changes)) => {
+                        paths.extend(changes.keys().cloned());
+                        collect_edit_paths(value, paths);
+                    }
+                    ("patterns", Value::Array(items)) => {
+                        paths.extend(items.iter().filter_map(Value::as_str).map(str::to_string))
+                    }
+                    _ => collect_edit_paths(value, paths),
+                }
+            }
+        }
+        Value::Array(items) => items
+            .iter()
+            .for_each(|item| collect_edit_paths(item, paths)),
+        _ => {}
+    }
+}
+
+/// Path-like words of the shell commands in a command tool's input (`command` or `cmd`, as a
+/// string or an argv array whose elements may themselves be scripts, e.g. `bash -lc "..."`)
+fn command_paths(value: &Value) -> Vec<String> {
+    let mut scripts = Vec::new();
+    collect_commands(value, &mut scripts);
+    scripts
+        .iter()
+        .flat_map(|script| {
+            shlex::split(script)
+                .unwrap_or_else(|| script.split_whitespace().map(str::to_string).collect())
+        })
+        .filter_map(|word| {
+            // Redirections (`>file`, `2>>file`) and `--flag=value` arguments
+            let word = strip_redirection(&word);
+            let word = word.rsplit('=').next().unwrap_or(word);
+            let word = word.trim_end_matches([';', ')', '&', '|']);
+            (!word.is_empty() && !word.starts_with('-')).then(|| word.to_string())
+        })
+        .collect()
+}
+
+fn strip_redirection(word: &str) -> &str {
+    let rest = word.trim_start_matches(|c: char| c.is_ascii_digit());
+    let word = if rest.starts_with(['<', '>']) {
+        rest
+    } else {
+        word
+    };
+    word.trim_start_matches(['<', '>', '&', '|', ';', '('])
+}
+
+fn collect_commands(value: &Value, scripts: &mut Vec<String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                match (key.as_str(), value) {
+                    ("command" | "cmd", Value::String(script)) => scripts.push(script.clone()),
+                    ("command" | "cmd", Value::Array(argv)) => {
+                        scripts.extend(argv.iter().filter_map(Value::as_str).map(str::to_string))
+                    }
+                    _ => collect_commands(value, scripts),
+                }
+            }
+        }
+        Value::Array(items) => items
+            .iter()
+            .for_each(|item| collect_commands(item, scripts)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn protected() -> ProtectedPaths {
+        ProtectedPaths::new(
+            PathBuf::from("/repo"),
+            [
+                "# comment",
+                "migrations/",
+                ".github/workflows/*.yml",
+                "Cargo.lock",
+                "**/secrets/**",
+                "*.pem",
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn matches_gitignore_like_patterns() {
+        let paths = protected();
+        assert_eq!(paths.matching("migrations/001.sql"), Some("migrations/"));
+        assert_eq!(
+            paths.matching("app/migrations/001.sql"),
+            Some("migrations/")
+        );
+        assert_eq!(
+            paths.matching(".github/workflows/ci.yml"),
+            Some(".github/workflows/*.yml")
+        );
+        assert_eq!(paths.matching(".github/workflows/nested/ci.yml"), None);
+        assert_eq!(paths.matching("crates/a/Cargo.lock"), Some("Cargo.lock"));
+        assert_eq!(paths.matching("config/secrets/key"), Some("**/secrets/**"));
+        assert_eq!(paths.matching("certs/server.pem"), Some("*.pem"));
+        assert_eq!(paths.matching("src/migrations.rs"), None);
+        assert_eq!(paths.matching("Cargo.toml"), None);
+    }
+
+    #[test]
+    fn finds_protected_paths_in_edits_and_commands() {
+        let paths = protected();
+        let cwd = Path::new("/repo/app");
+        let found = |tool: &str, input: Value| -> Vec<String> {
+            paths
+                .violations_in_tool_call(tool, &input, cwd)
+                .into_iter()
+                .map(|v| v.path)
+                .collect()
+        };
+
+        assert_eq!(
+            found(
+                "Write",
+                json!({ "file_path": "/repo/app/migrations/002.sql" })
+            ),
+            vec!["app/migrations/002.sql"]
+        );
+        assert_eq!(
+            found(
+                "edit",
+                json!({ "file_changes": { "../Cargo.lock": {}, "src/main.rs": {} } })
+            ),
+            vec!["Cargo.lock"]
+        );
+        assert_eq!(
+            found(
+                "bash",
+                json!({ "command": ["bash", "-lc", "echo x >../.github/workflows/ci.yml"] })
+            ),
+            vec![".github/workflows/ci.yml"]
+        );
+        assert_eq!(
+            found(
+                "other",
+                json!({ "tool_call": { "kind": "edit", "locations": [{ "path": "k.pem" }] } })
+            ),
+            vec!["app/k.pem"]
+        );
+        // Reads are allowed
+        assert!(found("Read", json!({ "file_path": "migrations/001.sql" })).is_empty());
+        assert!(found("Bash", json!({ "command": "cargo test" })).is_empty());
+    }
+}
@@ -0,0 +1,365 @@
+//! Prompt templates.
+//!
+//! Placeholders use `{{ ... }}`:
+//! - `{{name}}`: a variable (e.g. from `--var name=value`)
+//! - `{{env.NAME}}`: an environment variable from [`TEMPLATE_ENV_ALLOWLIST`] or named with
+//!   `--var NAME`; other variables are not exposed, so a template cannot leak secrets
+//! - `{{git.branch}}`, `{{git.base_commit}}`, `{{git.short_commit}}`: the current HEAD
+//! - `{{@path/to/file}}`: the contents of a file inside the workspace (size limited)
+//!
+//! `\{{` produces a literal `{{`. Unknown placeholders are kept verbatim, so prompts that
+//! happen to contain `{{` (code samples, other template languages) pass through unchanged.
+//!
+//! Named templates live next to the profiles: `<asset_dir>/templates/<name>.md`, or
+//! `.code-marshal/templates/<name>.md` in the project (which takes precedence).
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use git::GitService;
+use serde_json::Value;
+use thiserror::Error;
+
+use crate::executors::CodingAgent;
+
+/// Max size of a single `{{@file}}` include
+pub const MAX_INCLUDE_BYTES: u64 = 64 * 1024;
+/// Max combined size of all includes in one render
+pub const MAX_TOTAL_INCLUDE_BYTES: u64 = 256 * 1024;
+
+/// Environment variables `{{env.NAME}}` can read without being named with `--var NAME`
+pub const TEMPLATE_ENV_ALLOWLIST: &[&str] = &["USER", "LOGNAME", "HOME", "SHELL", "LANG", "PWD"];
+
+/// Profile fields that are rendered as templates
+const TEMPLATED_FIELDS: &[&str] = &[
+    "append_prompt",
+    "base_instructions",
+    "developer_instructions",
+];
+
+#[derive(Debug, Error)]
+pub enum TemplateError {
+    #[error("Git info '{0}' is unavailable: not a git repository")]
+    GitUnavailable(String),
+    #[error("Unterminated placeholder near '{0}'")]
+    Unterminated(String),
+    #[error("Include '{path}' is {size} bytes, the limit is {limit}")]
+    IncludeTooLarge { path: String, size: u64, limit: u64 },
+    #[error("Include '{0}' is outside the workspace")]
+    IncludeOutsideWorkspace(String),
+    #[error("Template '{0}' not found")]
+    TemplateNotFound(String),
+    #[error("Invalid variable '{0}', expected KEY=VALUE or NAME")]
+    InvalidVariable(String),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+}
+
+/// Values available to templates
+#[derive(Debug, Clone, Default)]
+pub struct PromptContext {
+    pub vars: HashMap<String, String>,
+    pub env: HashMap<String, String>,
+    pub git_branch: Option<String>,
+    pub git_commit: Option<String>,
+    /// Root for `{{@file}}` includes; includes may not escape it
+    pub workspace_root: PathBuf,
+}
+
+impl PromptContext {
+    pub fn new(workspace_root: PathBuf) -> Self {
+        Self {
+            workspace_root,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_vars(mut self, vars: HashMap<String, String>) -> Self {
+        self.vars.extend(vars);
+        self
+    }
+
+    /// Expose the allowlisted environment variables and those named in `names`
+    pub fn with_env(mut self, env: HashMap<String, String>, names: &[String]) -> Self {
+        self.env = env
+            .into_iter()
+            .filter(|(key, _)| {
+                TEMPLATE_ENV_ALLOWLIST.contains(&key.as_str()) || names.contains(key)
+            })
+            .collect();
+        self
+    }
+
+    /// Fill in git info from the workspace HEAD; non-repositories are left without git info
+    pub fn with_git_info(mut self) -> Self {
+        match GitService::new().get_head_info(&self.workspace_root) {
+            Ok(head) => {
+                self.git_branch = Some(head.branch);
+                self.git_commit = Some(head.oid);
+            }
+            Err(e) => tracing::debug!("No git info for prompt templates: {e}"),
+        }
+        self
+    }
+
+    /// Render a template string
+    pub fn render(&self, template: &str) -> Result<String, TemplateError> {
+        let mut out = String::with_capacity(template.len());
+        let mut included = 0u64;
+        let mut rest = template;
+
+        while let Some(start) = rest.find("{{") {
+            if rest[..start].ends_with('\\') {
+                out.push_str(&rest[..start - 1]);
+                out.push_str("{{");
+                rest = &rest[start + 2..];
+                continue;
+            }
+            out.push_str(&rest[..start]);
+            let after = &rest[start + 2..];
+            let end = after.find("}}").ok_or_else(|| {
+                TemplateError::Unterminated(after.chars().take(20).collect::<String>())
+            })?;
+            match self.resolve(after[..end].trim(), &mut included)? {
+                Some(value) => out.push_str(&value),
+                None => out.push_str(&rest[start..start + end + 4]),
+            }
+            rest = &after[end + 2..];
+        }
+        out.push_str(rest);
+        Ok(out)
+    }
+
+    /// The value of a placeholder, or `None` when it is unknown
+    fn resolve(&self, key: &str, included: &mut u64) -> Result<Option<String>, TemplateError> {
+        if let Some(path) = key.strip_prefix('@') {
+            return self.include(path.trim(), included).map(Some);
+        }
+        if let Some(name) = key.strip_prefix("env.") {
+            return Ok(self.env.get(name).cloned());
+        }
+        if let Some(name) = key.strip_prefix("git.") {
+            let value = match name {
+                "branch" => self.git_branch.clone(),
+                "base_commit" => self.git_commit.clone(),
+                "short_commit" => self
+                    .git_commit
+                    .as_ref()
+                    .map(|oid| oid.chars().take(7).collect()),
+                _ => return Ok(None),
+            };
+            return value
+                .map(Some)
+                .ok_or_else(|| TemplateError::GitUnavailable(key.to_string()));
+        }
+        Ok(self.vars.get(key).cloned())
+    }
+
+    fn include(&self, path: &str, included: &mut u64) -> Result<String, TemplateError> {
+        let root = self.workspace_root.canonicalize()?;
+        let resolved = root.join(path).canonicalize()?;
+        if !resolved.starts_with(&root) {
+            return Err(TemplateError::IncludeOutsideWorkspace(path.to_string()));
+        }
+
+        let size = fs::metadata(&resolved)?.len();
+        if size > MAX_INCLUDE_BYTES {
+            return Err(TemplateError::IncludeTooLarge {
+                path: path.to_string(),
+                size,
+                limit: MAX_INCLUDE_BYTES,
+            });
+        }
+        *included += size;
+        if *included > MAX_TOTAL_INCLUDE_BYTES {
+            return Err(TemplateError::IncludeTooLarge {
+                path: path.to_string(),
+                size: *included,
+                limit: MAX_TOTAL_INCLUDE_BYTES,
+            });
+        }
+        // Included files are inserted verbatim, not rendered
+        Ok(String::from_utf8_lossy(&fs::read(&resolved)?).into_owned())
+    }
+
+    /// Render the templated prompt fields of an executor config
+    /// (`append_prompt`, `base_instructions`, `developer_instructions`)
+    pub fn render_agent(&self, agent: &CodingAgent) -> Result<CodingAgent, TemplateError> {
+        let mut value = serde_json::to_value(agent)?;
+        if let Some(fields) = value
+            .as_object_mut()
+            .and_then(|tagged| tagged.values_mut().next())
+            .and_then(Value::as_object_mut)
+        {
+            for field in TEMPLATED_FIELDS {
+                if let Some(Value::String(raw)) = fields.get(*field) {
+                    let rendered = self.render(raw)?;
+                    fields.insert(field.to_string(), Value::String(rendered));
+                }
+            }
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+}
+
+/// Parse `KEY=VALUE` or a bare `NAME` (as passed to `--var`). A bare name has no value: it
+/// exposes the environment variable `NAME` to `{{env.NAME}}`.
+pub fn parse_var(raw: &str) -> Result<(String, Option<String>), TemplateError> {
+    let (key, value) = match raw.split_once('=') {
+        Some((key, value)) => (key.trim(), Some(value.to_string())),
+        None => (raw.trim(), None),
+    };
+    if key.is_empty() {
+        return Err(TemplateError::InvalidVariable(raw.to_string()));
+    }
+    Ok((key.to_string(), value))
+}
+
+fn template_dirs(cwd: &Path) -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+    if let Some(project) = crate::profile::find_project_profiles(cwd)
+        .and_then(|profiles| profiles.parent().map(|dir| dir.join("templates")))
+    {
+        dirs.push(project);
+    }
+    dirs.push(workspace_utils::assets::asset_dir().join("templates"));
+    dirs
+}
+
+/// Load a named template; project templates shadow user templates
+pub fn load_named_template(name: &str, cwd: &Path) -> Result<String, TemplateError> {
+    if name.contains(['/', '\\']) || name.starts_with('.') {
+        return Err(TemplateError::TemplateNotFound(name.to_string()));
+    }
+    template_dirs(cwd)
+        .into_iter()
+        .map(|dir| dir.join(format!("{name}.md")))
+        .find(|path| path.is_file())
+        .ok_or_else(|| TemplateError::TemplateNotFound(name.to_string()))
+        .and_then(|path| Ok(fs::read_to_string(path)?))
+}
+
+/// All named templates, mapped to the file that provides them
+pub fn list_named_templates(cwd: &Path) -> BTreeMap<String, PathBuf> {
+    let mut templates = BTreeMap::new();
+    // Lowest precedence first so project templates win
+    for dir in template_dirs(cwd).into_iter().rev() {
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().is_some_and(|ext| ext == "md")
+                && let Some(name) = path.file_stem().and_then(|s| s.to_str())
+            {
+                templates.insert(name.to_string(), path);
+            }
+        }
+    }
+    templates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn context(root: PathBuf) -> PromptContext {
+        let mut ctx = PromptContext::new(root);
+        ctx.vars.insert("target".into(), "serde".into());
+        ctx.env.insert("USER".into(), "dev".into());
+        ctx.git_branch = Some("main".into());
+        ctx.git_commit = Some("0123456789abcdef".into());
+        ctx
+    }
+
+    #[test]
+    fn renders_variables() {
+        let ctx = context(std::env::temp_dir());
+        let out = ctx
+            .render("Upgrade {{ target }} on {{git.branch}}@{{git.short_commit}} for {{env.USER}}")
+            .unwrap();
+        assert_eq!(out, "Upgrade serde on main@0123456 for dev");
+        assert_eq!(
+            ctx.render(r"keep \{{literal}}").unwrap(),
+            "keep {{literal}}"
+        );
+        // Unknown placeholders are kept as-is
+        assert_eq!(
+            ctx.render("{{missing}} {{ env.SECRET }} {{git.nope}}")
+                .unwrap(),
+            "{{missing}} {{ env.SECRET }} {{git.nope}}"
+        );
+        assert!(matches!(
+            ctx.render("{{oops"),
+            Err(TemplateError::Unterminated(_))
+        ));
+    }
+
+    #[test]
+    fn includes_files_within_limits() {
+        let root = std::env::temp_dir().join(format!("template-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("notes.md"), "be careful").unwrap();
+        fs::write(
+            root.join("big.txt"),
+            vec![b'x'; MAX_INCLUDE_BYTES as usize + 1],
+        )
+        .unwrap();
+
+        let ctx = context(root.clone());
+        assert_eq!(
+            ctx.render("Note: {{@notes.md}}").unwrap(),
+            "Note: be careful"
+        );
+        assert!(matches!(
+            ctx.render("{{@big.txt}}"),
+            Err(TemplateError::IncludeTooLarge { .. })
+        ));
+        assert!(ctx.render("{{@../outside}}").is_err());
+
+        fs::remove_dir_all(root).ok();
+    }
+
+    #[test]
+    fn renders_agent_prompt_fields() {
+        let agent: CodingAgent = serde_json::from_value(serde_json::json!({
+            "CLAUDE_CODE": { "append_prompt": "\nWork on {{git.branch}}", "model": "{{target}}" }
+        }))
+        .unwrap();
+        let rendered = context(std::env::temp_dir()).render_agent(&agent).unwrap();
+        let value = serde_json::to_value(rendered).unwrap();
+        assert_eq!(value["CLAUDE_CODE"]["append_prompt"], "\nWork on main");
+        // Only prompt fields are templated
+        assert_eq!(value["CLAUDE_CODE"]["model"], "{{target}}");
+    }
+
+    #[test]
+    fn exposes_only_allowed_env() {
+        let env = HashMap::from([
+            ("USER".to_string(), "dev".to_string()),
+            ("API_TOKEN".to_string(), "secret".to_string()),
+            ("TARGET".to_string(), "x86".to_string()),
+        ]);
+        let ctx = PromptContext::new(std::env::temp_dir()).with_env(env, &["TARGET".to_string()]);
+        assert_eq!(
+            ctx.render("{{env.USER}} {{env.TARGET}} {{env.API_TOKEN}}")
+                .unwrap(),
+            "dev x86 {{env.API_TOKEN}}"
+        );
+    }
+
+    #[test]
+    fn parses_vars() {
+        assert_eq!(
+            parse_var("a=b=c").unwrap(),
+            ("a".to_string(), Some("b=c".to_string()))
+        );
+        assert_eq!(parse_var("NAME").unwrap(), ("NAME".to_string(), None));
+        assert!(parse_var("=value").is_err());
+    }
+}
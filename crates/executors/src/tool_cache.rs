@@ -0,0 +1,577 @@
+//! Managed tool cache: the npm-distributed agent CLIs installed once into a code-marshal-owned
+//! prefix instead of being fetched by `npx -y` on every run.
+//!
+//! Each install lives in `tools/<package>/<version>`; `tools/tools.json` records the version in
+//! use per package. [`CommandParts::into_resolved`](crate::command::CommandParts::into_resolved)
+//! runs an `npx -y <package>@...` command line from that install when there is one, whatever
+//! version the base command names, so installs and upgrades only happen explicitly
+//! (`code-marshal tools`). Agents whose package is not installed keep using `npx`.
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    process::Stdio,
+    time::Duration,
+};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::process::Command;
+use ts_rs::TS;
+use workspace_utils::shell::resolve_executable_path;
+
+use crate::executors::{
+    BaseCodingAgent, amp::Amp, claude, codex::Codex, copilot::Copilot, gemini::Gemini,
+    opencode::Opencode, qwen::QwenCode,
+};
+
+const LOCK_FILE: &str = "tools.json";
+const HELP_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Error)]
+pub enum ToolCacheError {
+    #[error("`npm` not found in PATH; managed tools are installed with npm")]
+    NpmNotFound,
+    #[error("{0} is not installed through npm, so it has no managed tool")]
+    NotManaged(BaseCodingAgent),
+    #[error("npm could not resolve {spec}: {message}")]
+    Resolve { spec: String, message: String },
+    #[error("installing {spec} failed: {message}")]
+    Install { spec: String, message: String },
+    #[error("`{0}` did not print its help")]
+    Help(String),
+    #[error("tool cache I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("invalid tool cache lock file: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// An agent CLI distributed as an npm package
+pub struct ManagedTool {
+    pub agent: BaseCodingAgent,
+    pub package: &'static str,
+    /// Executable the package installs into `node_modules/.bin`
+    pub bin: &'static str,
+    /// The agent's default base command, `npx -y <package>@<version>`
+    base_command: fn() -> &'static str,
+    /// Flags and subcommands the executor passes; an upgrade whose `--help` no longer
+    /// mentions one is likely to break it
+    pub flags: &'static [&'static str],
+    /// What else ties the executor to a version
+    pub note: Option<&'static str>,
+}
+
+fn claude_base_command() -> &'static str {
+    claude::base_command(false)
+}
+
+const MANAGED_TOOLS: &[ManagedTool] = &[
+    ManagedTool {
+        agent: BaseCodingAgent::ClaudeCode,
+        package: "@anthropic-ai/claude-code",
+        bin: "claude",
+        base_command: claude_base_command,
+        flags: &[
+            "-p",
+            "--verbose",
+            "--output-format",
+            "--input-format",
+            "--include-partial-messages",
+            "--replay-user-messages",
+            "--permission-mode",
+            "--dangerously-skip-permissions",
+            "--model",
+            "--resume",
+            "--disallowedTools",
+        ],
+        note: Some("the stream-json message format is only checked by running the agent"),
+    },
+    ManagedTool {
+        agent: BaseCodingAgent::Amp,
+        package: "@sourcegraph/amp",
+        bin: "amp",
+        base_command: Amp::base_command,
+        flags: &["--execute", "--stream-json", "--dangerously-allow-all"],
+        note: Some("not pinned upstream; `tools install` pins the latest release"),
+    },
+    ManagedTool {
+        agent: BaseCodingAgent::Gemini,
+        package: "@google/gemini-cli",
+        bin: "gemini",
+        base_command: Gemini::base_command,
+        flags: &["--model", "--yolo", "--allowed-tools", "--experimental-acp"],
+        note: None,
+    },
+    ManagedTool {
+        agent: BaseCodingAgent::QwenCode,
+        package: "@qwen-code/qwen-code",
+        bin: "qwen",
+        base_command: QwenCode::base_command,
+        flags: &["--yolo", "--acp"],
+        note: None,
+    },
+    ManagedTool {
+        agent: BaseCodingAgent::Copilot,
+        package: "@github/copilot",
+        bin: "copilot",
+        base_command: Copilot::base_command,
+        flags: &[
+            "--allow-all-tools",
+            "--model",
+            "--allow-tool",
+            "--deny-tool",
+            "--add-dir",
+            "--disable-mcp-server",
+            "--acp",
+        ],
+        note: None,
+    },
+    ManagedTool {
+        agent: BaseCodingAgent::Codex,
+        package: "@openai/codex",
+        bin: "codex",
+        base_command: Codex::base_command,
+        flags: &["app-server", "login", "--oss"],
+        note: Some("the app-server protocol is compiled in from codex rust-v0.98.0"),
+    },
+    ManagedTool {
+        agent: BaseCodingAgent::Opencode,
+        package: "opencode-ai",
+        bin: "opencode",
+        base_command: Opencode::base_command,
+        flags: &["serve", "--hostname", "--port"],
+        note: Some("the server API is only checked by running the agent"),
+    },
+];
+
+pub fn managed_tools() -> &'static [ManagedTool] {
+    MANAGED_TOOLS
+}
+
+pub fn managed_tool(agent: BaseCodingAgent) -> Result<&'static ManagedTool, ToolCacheError> {
+    MANAGED_TOOLS
+        .iter()
+        .find(|tool| tool.agent == agent)
+        .ok_or(ToolCacheError::NotManaged(agent))
+}
+
+/// The version in an npm package spec (`@scope/name@1.2.3` → `1.2.3`)
+fn spec_version(spec: &str) -> Option<&str> {
+    spec.rsplit_once('@')
+        .filter(|(name, _)| !name.is_empty())
+        .map(|(_, version)| version)
+}
+
+fn spec_package(spec: &str) -> &str {
+    spec.rsplit_once('@')
+        .filter(|(name, _)| !name.is_empty())
+        .map_or(spec, |(name, _)| name)
+}
+
+/// The package spec of an `npx` command line and the arguments after it
+fn npx_spec(program: &str, args: &[String]) -> Option<(String, Vec<String>)> {
+    let runner = Path::new(program)
+        .file_stem()
+        .is_some_and(|stem| stem == "npx");
+    if !runner {
+        return None;
+    }
+    let position = args.iter().position(|arg| !arg.starts_with('-'))?;
+    Some((args[position].clone(), args[position + 1..].to_vec()))
+}
+
+impl ManagedTool {
+    /// The version the executor was written against (`latest` when not pinned)
+    pub fn pinned_version(&self) -> &'static str {
+        (self.base_command)()
+            .split_whitespace()
+            .find_map(|word| spec_version(word).filter(|_| word.contains(self.package)))
+            .unwrap_or("latest")
+    }
+
+    /// The version in use from the cache, if the tool is installed
+    pub fn installed(&self) -> Option<InstalledTool> {
+        read_lock_blocking().ok()?.tools.get(self.package).cloned()
+    }
+
+    fn version_dir(&self, version: &str) -> PathBuf {
+        package_dir(self.package).join(version)
+    }
+
+    pub fn bin_path(&self, version: &str) -> PathBuf {
+        bin_in(&self.version_dir(version), self.bin)
+    }
+}
+
+/// An installed managed tool, as recorded in the lock file
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, TS)]
+#[ts(export)]
+pub struct InstalledTool {
+    pub version: String,
+    pub installed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(transparent)]
+struct ToolLock {
+    tools: BTreeMap<String, InstalledTool>,
+}
+
+fn package_dir(package: &str) -> PathBuf {
+    workspace_utils::assets::tools_dir().join(package.trim_start_matches('@').replace('/', "-"))
+}
+
+fn bin_in(version_dir: &Path, bin: &str) -> PathBuf {
+    let bin = if cfg!(windows) {
+        format!("{bin}.cmd")
+    } else {
+        bin.to_string()
+    };
+    version_dir.join("node_modules").join(".bin").join(bin)
+}
+
+fn lock_path() -> PathBuf {
+    workspace_utils::assets::tools_dir().join(LOCK_FILE)
+}
+
+fn parse_lock(read: std::io::Result<String>) -> Result<ToolLock, ToolCacheError> {
+    match read {
+        Ok(raw) => Ok(serde_json::from_str(&raw)?),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(ToolLock::default()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+async fn read_lock() -> Result<ToolLock, ToolCacheError> {
+    parse_lock(tokio::fs::read_to_string(lock_path()).await)
+}
+
+fn read_lock_blocking() -> Result<ToolLock, ToolCacheError> {
+    parse_lock(std::fs::read_to_string(lock_path()))
+}
+
+fn write_lock(lock: &ToolLock) -> Result<(), ToolCacheError> {
+    let dir = workspace_utils::assets::tools_dir();
+    std::fs::create_dir_all(&dir)?;
+    std::fs::write(dir.join(LOCK_FILE), serde_json::to_string_pretty(lock)?)?;
+    Ok(())
+}
+
+/// Whether the cached `installed` version may stand in for `npx <package>@<requested>`: an
+/// unversioned or `latest` spec, the version the executor pins itself (which `tools upgrade`
+/// moves on from), or exactly the installed version. Any other version was pinned on purpose,
+/// e.g. with `base_command_override`, and is left to `npx`.
+fn cache_serves(tool: &ManagedTool, requested: Option<&str>, installed: &str) -> bool {
+    match requested {
+        None | Some("latest") => true,
+        Some(version) => version == installed || version == tool.pinned_version(),
+    }
+}
+
+/// The cached install for an `npx [-y] <package>@<version> ...` command line of a managed
+/// package: its executable and the remaining arguments
+pub async fn cached_command(program: &str, args: &[String]) -> Option<(PathBuf, Vec<String>)> {
+    let (spec, rest) = npx_spec(program, args)?;
+    let tool = MANAGED_TOOLS
+        .iter()
+        .find(|tool| tool.package == spec_package(&spec))?;
+    let installed = read_lock()
+        .await
+        .inspect_err(|err| tracing::warn!("Ignoring the tool cache: {err}"))
+        .ok()?
+        .tools
+        .remove(tool.package)?;
+    if !cache_serves(tool, spec_version(&spec), &installed.version) {
+        tracing::warn!(
+            "{spec} is pinned but the tool cache has {} {}; running it with npx",
+            tool.package,
+            installed.version
+        );
+        return None;
+    }
+    let bin = tool.bin_path(&installed.version);
+    tokio::fs::try_exists(&bin)
+        .await
+        .unwrap_or(false)
+        .then_some((bin, rest))
+}
+
+async fn npm() -> Result<PathBuf, ToolCacheError> {
+    resolve_executable_path("npm")
+        .await
+        .ok_or(ToolCacheError::NpmNotFound)
+}
+
+fn last_line(output: &[u8]) -> String {
+    String::from_utf8_lossy(output)
+        .lines()
+        .rfind(|line| !line.trim().is_empty())
+        .unwrap_or("no output")
+        .trim()
+        .to_string()
+}
+
+/// The concrete version npm resolves `spec` (a version, range or tag such as `latest`) to
+pub async fn resolve_version(tool: &ManagedTool, spec: &str) -> Result<String, ToolCacheError> {
+    let spec = format!("{}@{spec}", tool.package);
+    let output = Command::new(npm().await?)
+        .args(["view", &spec, "version"])
+        .stdin(Stdio::null())
+        .output()
+        .await?;
+    // A range prints one `name@version 'version'` line per match; the last is the newest
+    let version = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .rfind(|line| !line.trim().is_empty())
+        .map(|line| {
+            line.rsplit(' ')
+                .next()
+                .unwrap_or(line)
+                .trim_matches('\'')
+                .to_string()
+        });
+    match version {
+        Some(version) if output.status.success() => Ok(version),
+        _ => Err(ToolCacheError::Resolve {
+            spec,
+            message: last_line(&output.stderr),
+        }),
+    }
+}
+
+/// Install `version` of `tool` into its own prefix (a no-op when already there) without
+/// switching to it; returns the executable
+pub async fn install(tool: &ManagedTool, version: &str) -> Result<PathBuf, ToolCacheError> {
+    let bin = tool.bin_path(version);
+    if bin.exists() {
+        return Ok(bin);
+    }
+    let dir = tool.version_dir(version);
+    std::fs::create_dir_all(&dir)?;
+    let spec = format!("{}@{version}", tool.package);
+    let output = Command::new(npm().await?)
+        .arg("install")
+        .arg("--prefix")
+        .arg(&dir)
+        .args(["--no-audit", "--no-fund", "--loglevel=error", &spec])
+        .stdin(Stdio::null())
+        .output()
+        .await?;
+    if !output.status.success() || !bin.exists() {
+        let _ = std::fs::remove_dir_all(&dir);
+        return Err(ToolCacheError::Install {
+            spec,
+            message: last_line(&output.stderr),
+        });
+    }
+    Ok(bin)
+}
+
+/// Make the installed `version` the one runs use, removing other installed versions
+pub fn activate(tool: &ManagedTool, version: &str) -> Result<(), ToolCacheError> {
+    let mut lock = read_lock_blocking()?;
+    lock.tools.insert(
+        tool.package.to_string(),
+        InstalledTool {
+            version: version.to_string(),
+            installed_at: Utc::now(),
+        },
+    );
+    write_lock(&lock)?;
+    remove_versions(tool, Some(version))
+}
+
+/// Stop using the cache for `tool` (runs go back to `npx`); returns the removed version
+pub fn remove(tool: &ManagedTool) -> Result<Option<String>, ToolCacheError> {
+    let mut lock = read_lock_blocking()?;
+    let removed = lock.tools.remove(tool.package).map(|tool| tool.version);
+    write_lock(&lock)?;
+    remove_versions(tool, None)?;
+    Ok(removed)
+}
+
+fn remove_versions(tool: &ManagedTool, keep: Option<&str>) -> Result<(), ToolCacheError> {
+    let Ok(entries) = std::fs::read_dir(package_dir(tool.package)) else {
+        return Ok(());
+    };
+    for entry in entries.flatten() {
+        if keep.is_none_or(|keep| entry.file_name() != keep) {
+            std::fs::remove_dir_all(entry.path())?;
+        }
+    }
+    Ok(())
+}
+
+/// How far a candidate version is from the current one
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, TS)]
+#[serde(rename_all = "snake_case")]
+#[ts(export)]
+pub enum VersionStep {
+    Same,
+    Patch,
+    Minor,
+    Major,
+    Downgrade,
+    /// Not `major.minor.patch` versions
+    Unknown,
+}
+
+fn version_step(current: &str, candidate: &str) -> VersionStep {
+    let parse = |version: &str| -> Option<[u64; 3]> {
+        let core = version.split(['-', '+']).next()?;
+        let mut parts = core.split('.').map(|part| part.parse::<u64>().ok());
+        Some([parts.next()??, parts.next()??, parts.next()??])
+    };
+    let (Some(current), Some(candidate)) = (parse(current), parse(candidate)) else {
+        return VersionStep::Unknown;
+    };
+    if candidate < current {
+        VersionStep::Downgrade
+    } else if candidate == current {
+        VersionStep::Same
+    } else if candidate[0] != current[0] || (current[0] == 0 && candidate[1] != current[1]) {
+        // Below 1.0, minor releases are allowed to break
+        VersionStep::Major
+    } else if candidate[1] != current[1] {
+        VersionStep::Minor
+    } else {
+        VersionStep::Patch
+    }
+}
+
+/// Whether the executor's flags survive a move from the current to a candidate version
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, TS)]
+#[ts(export)]
+pub struct Compatibility {
+    pub agent: BaseCodingAgent,
+    pub current: String,
+    pub candidate: String,
+    pub step: VersionStep,
+    /// Documented by the current version's `--help` but not by the candidate's
+    pub missing_flags: Vec<String>,
+    /// Documented by neither, so they could not be checked
+    pub unchecked_flags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub note: Option<String>,
+}
+
+impl Compatibility {
+    pub fn is_compatible(&self) -> bool {
+        self.missing_flags.is_empty()
+    }
+}
+
+fn documents(help: &str, flag: &str) -> bool {
+    help.split(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))
+        .any(|word| word == flag)
+}
+
+async fn help_text(bin: &Path) -> Result<String, ToolCacheError> {
+    let help = || ToolCacheError::Help(bin.display().to_string());
+    let mut command = Command::new(bin);
+    command
+        .arg("--help")
+        .stdin(Stdio::null())
+        .kill_on_drop(true);
+    let output = tokio::time::timeout(HELP_TIMEOUT, command.output())
+        .await
+        .map_err(|_| help())??;
+    let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+    if text.trim().is_empty() {
+        return Err(help());
+    }
+    Ok(text)
+}
+
+/// Install both versions (without switching) and compare their `--help` for the flags the
+/// executor passes. `current` is the installed version, or the pinned one.
+pub async fn compare(tool: &ManagedTool, candidate: &str) -> Result<Compatibility, ToolCacheError> {
+    let current = match tool.installed() {
+        Some(installed) => installed.version,
+        None => resolve_version(tool, tool.pinned_version()).await?,
+    };
+    let current_help = help_text(&install(tool, &current).await?).await?;
+    let candidate_help = help_text(&install(tool, candidate).await?).await?;
+
+    let mut missing_flags = Vec::new();
+    let mut unchecked_flags = Vec::new();
+    for flag in tool.flags {
+        match (
+            documents(&current_help, flag),
+            documents(&candidate_help, flag),
+        ) {
+            (true, false) => missing_flags.push(flag.to_string()),
+            (false, false) => unchecked_flags.push(flag.to_string()),
+            _ => {}
+        }
+    }
+    Ok(Compatibility {
+        agent: tool.agent,
+        step: version_step(&current, candidate),
+        current,
+        candidate: candidate.to_string(),
+        missing_flags,
+        unchecked_flags,
+        note: tool.note.map(str::to_string),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn npx_command_lines_map_to_managed_packages() {
+        let args: Vec<String> = ["-y", "@openai/codex@0.98.0", "app-server"]
+            .map(String::from)
+            .to_vec();
+        let (spec, rest) = npx_spec("/usr/bin/npx", &args).unwrap();
+        assert_eq!(spec_package(&spec), "@openai/codex");
+        assert_eq!(spec_version(&spec), Some("0.98.0"));
+        assert_eq!(rest, vec!["app-server".to_string()]);
+        assert!(npx_spec("codex", &args).is_none());
+
+        assert_eq!(spec_package("opencode-ai"), "opencode-ai");
+        assert_eq!(
+            managed_tool(BaseCodingAgent::Codex)
+                .unwrap()
+                .pinned_version(),
+            "0.98.0"
+        );
+        assert_eq!(
+            managed_tool(BaseCodingAgent::Amp).unwrap().pinned_version(),
+            "latest"
+        );
+    }
+
+    #[test]
+    fn cache_only_serves_unpinned_or_matching_versions() {
+        let codex = managed_tool(BaseCodingAgent::Codex).unwrap();
+        assert!(cache_serves(codex, None, "0.99.0"));
+        assert!(cache_serves(codex, Some("latest"), "0.99.0"));
+        // The executor's own pin is what `tools upgrade` replaces
+        assert!(cache_serves(codex, Some("0.98.0"), "0.99.0"));
+        assert!(cache_serves(codex, Some("0.97.0"), "0.97.0"));
+        assert!(!cache_serves(codex, Some("0.97.0"), "0.99.0"));
+    }
+
+    #[test]
+    fn version_steps_treat_pre_1_0_minors_as_breaking() {
+        assert_eq!(version_step("2.1.32", "2.1.40"), VersionStep::Patch);
+        assert_eq!(version_step("2.1.32", "2.2.0"), VersionStep::Minor);
+        assert_eq!(version_step("0.27.0", "0.28.0"), VersionStep::Major);
+        assert_eq!(version_step("1.1.59", "1.1.59"), VersionStep::Same);
+        assert_eq!(version_step("0.98.0", "0.97.1"), VersionStep::Downgrade);
+        assert_eq!(version_step("0.0.403", "nightly"), VersionStep::Unknown);
+    }
+
+    #[test]
+    fn documents_matches_whole_flags() {
+        let help = "  --output-format <format>  Output format\n  -p, --print  Print";
+        assert!(documents(help, "--output-format"));
+        assert!(documents(help, "-p"));
+        assert!(!documents(help, "--output"));
+    }
+}
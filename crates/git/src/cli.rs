@@ -342,6 +342,119 @@ impl GitCli {
         self.git(worktree_path, ["commit", "-m", message])?;
         Ok(())
     }
+
+    /// Record the current worktree (tracked changes and untracked, non-ignored files) as a
+    /// commit on top of HEAD and return its sha. Uses a temporary index, so HEAD, the index
+    /// and the worktree are left untouched; the commit is only reachable once a ref points
+    /// at it.
+    pub fn snapshot_worktree(
+        &self,
+        worktree_path: &Path,
+        message: &str,
+    ) -> Result<String, GitCliError> {
+        let tmp_dir = tempfile::TempDir::new()
+            .map_err(|e| GitCliError::CommandFailed(format!("temp dir create failed: {e}")))?;
+        let tmp_index = tmp_dir.path().join("index");
+        let mut envs = vec![(
+            OsString::from("GIT_INDEX_FILE"),
+            tmp_index.as_os_str().to_os_string(),
+        )];
+
+        let head = self
+            .git(
+                worktree_path,
+                ["rev-parse", "--verify", "-q", "HEAD^{commit}"],
+            )
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty());
+        match &head {
+            Some(head) => self.git_with_env(worktree_path, ["read-tree", head.as_str()], &envs)?,
+            None => self.git_with_env(worktree_path, ["read-tree", "--empty"], &envs)?,
+        };
+        self.git_with_env(
+            worktree_path,
+            Self::apply_default_excludes(vec!["add", "-A"]),
+            &envs,
+        )?;
+        let tree = self
+            .git_with_env(worktree_path, ["write-tree"], &envs)?
+            .trim()
+            .to_string();
+
+        for (key, value) in [
+            ("GIT_AUTHOR_NAME", "Code Marshal"),
+            ("GIT_AUTHOR_EMAIL", "noreply@codemarshal.com"),
+            ("GIT_COMMITTER_NAME", "Code Marshal"),
+            ("GIT_COMMITTER_EMAIL", "noreply@codemarshal.com"),
+        ] {
+            envs.push((OsString::from(key), OsString::from(value)));
+        }
+        let mut args = vec!["commit-tree".to_string(), tree];
+        if let Some(head) = head {
+            args.push("-p".to_string());
+            args.push(head);
+        }
+        args.push("-m".to_string());
+        args.push(message.to_string());
+        Ok(self
+            .git_with_env(worktree_path, args, &envs)?
+            .trim()
+            .to_string())
+    }
+
+    /// Make the files of the worktree at `worktree_path` (its top level) match `commit`: the
+    /// commit's files are written and other non-ignored files are removed. HEAD and the index
+    /// are left untouched; a temporary index is used to check the files out.
+    pub fn restore_worktree_files(
+        &self,
+        worktree_path: &Path,
+        commit: &str,
+    ) -> Result<(), GitCliError> {
+        let tmp_dir = tempfile::TempDir::new()
+            .map_err(|e| GitCliError::CommandFailed(format!("temp dir create failed: {e}")))?;
+        let tmp_index = tmp_dir.path().join("index");
+        let envs = vec![(
+            OsString::from("GIT_INDEX_FILE"),
+            tmp_index.as_os_str().to_os_string(),
+        )];
+
+        // Same scope as `snapshot_worktree`: tracked and untracked, non-ignored files
+        let current = self.git(
+            worktree_path,
+            Self::apply_default_excludes(vec![
+                "ls-files",
+                "-z",
+                "--cached",
+                "--others",
+                "--exclude-standard",
+            ]),
+        )?;
+        let wanted = self.git(
+            worktree_path,
+            ["ls-tree", "-r", "-z", "--name-only", "--full-tree", commit],
+        )?;
+        let wanted: std::collections::HashSet<&str> = wanted.split('\0').collect();
+        for path in current.split('\0') {
+            if path.is_empty() || wanted.contains(path) {
+                continue;
+            }
+            match std::fs::remove_file(worktree_path.join(path)) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => {
+                    return Err(GitCliError::CommandFailed(format!(
+                        "failed to remove {path}: {e}"
+                    )));
+                }
+            }
+        }
+
+        self.git_with_env(worktree_path, ["read-tree", commit], &envs)?;
+        self.git_with_env(worktree_path, ["checkout-index", "-a", "-f"], &envs)?;
+        Ok(())
+    }
+
     /// Fetch a branch to the given remote using native git authentication.
     pub fn fetch_with_refspec(
         &self,
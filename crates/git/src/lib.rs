@@ -104,6 +104,16 @@ impl Commit {
     }
 }
 
+impl std::str::FromStr for Commit {
+    type Err = GitServiceError;
+
+    fn from_str(sha: &str) -> Result<Self, Self::Err> {
+        git2::Oid::from_str(sha)
+            .map(Self)
+            .map_err(|_| GitServiceError::InvalidRepository(format!("Invalid commit SHA: {sha}")))
+    }
+}
+
 impl std::fmt::Display for Commit {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.0)
@@ -1067,6 +1077,13 @@ impl GitService {
         Ok(HeadInfo { branch, oid })
     }
 
+    /// The commit HEAD points at
+    pub fn get_head_commit(&self, repo_path: &Path) -> Result<Commit, GitServiceError> {
+        let repo = self.open_repo(repo_path)?;
+        let oid = repo.head()?.peel_to_commit()?.id();
+        Ok(Commit::new(oid))
+    }
+
     pub fn get_current_branch(&self, repo_path: &Path) -> Result<String, git2::Error> {
         // Thin wrapper for backward compatibility
         match self.get_head_info(repo_path) {
@@ -1211,6 +1228,87 @@ impl GitService {
         Ok(())
     }
 
+    /// Record the worktree, including uncommitted and untracked files, as a commit on top of
+    /// HEAD without touching HEAD, the index or any files. Returns the commit sha.
+    pub fn snapshot_worktree(
+        &self,
+        worktree_path: &Path,
+        message: &str,
+    ) -> Result<String, GitServiceError> {
+        GitCli::new()
+            .snapshot_worktree(worktree_path, message)
+            .map_err(|e| GitServiceError::InvalidRepository(format!("git snapshot failed: {e}")))
+    }
+
+    /// Restore a commit made by [`Self::snapshot_worktree`]: files match the snapshot and
+    /// files created since are removed. HEAD and the index are left alone, so commits made
+    /// since the snapshot are kept and the restored files show up as unstaged changes.
+    /// Ignored files are left alone.
+    pub fn restore_worktree_snapshot(
+        &self,
+        worktree_path: &Path,
+        snapshot_sha: &str,
+    ) -> Result<(), GitServiceError> {
+        GitCli::new()
+            .restore_worktree_files(worktree_path, snapshot_sha)
+            .map_err(|e| {
+                GitServiceError::InvalidRepository(format!("git snapshot restore failed: {e}"))
+            })
+    }
+
+    /// Put `paths` (relative to the worktree root) back to their contents in `commit`, e.g. a
+    /// [`Self::snapshot_worktree`] commit: files missing from it are removed. Only the files
+    /// change; HEAD and the index are left alone, except that staged new files are unstaged.
+    pub fn restore_paths_from_commit(
+        &self,
+        worktree_path: &Path,
+        commit_sha: &str,
+        paths: &[String],
+    ) -> Result<(), GitServiceError> {
+        let cli = GitCli::new();
+        let map_err =
+            |e: GitCliError| GitServiceError::InvalidRepository(format!("git restore failed: {e}"));
+        for path in paths {
+            let in_commit = cli
+                .git(
+                    worktree_path,
+                    ["cat-file", "-e", &format!("{commit_sha}:{path}")],
+                )
+                .is_ok();
+            if in_commit {
+                cli.git(
+                    worktree_path,
+                    [
+                        "restore",
+                        &format!("--source={commit_sha}"),
+                        "--worktree",
+                        "--",
+                        path.as_str(),
+                    ],
+                )
+                .map_err(map_err)?;
+            } else {
+                cli.git(
+                    worktree_path,
+                    [
+                        "rm",
+                        "-q",
+                        "--cached",
+                        "--ignore-unmatch",
+                        "--",
+                        path.as_str(),
+                    ],
+                )
+                .map_err(map_err)?;
+                match std::fs::remove_file(worktree_path.join(path)) {
+                    Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+                    _ => {}
+                }
+            }
+        }
+        Ok(())
+    }
+
     /// Add a worktree for a branch, optionally creating the branch
     pub fn add_worktree(
         &self,
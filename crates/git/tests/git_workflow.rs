@@ -520,3 +520,43 @@ fn squash_merge_libgit2_sets_author_without_user() {
         assert_eq!(email.as_deref(), Some("noreply@vibekanban.com"));
     }
 }
+
+#[test]
+fn snapshot_and_restore_worktree_round_trip() {
+    let td = TempDir::new().unwrap();
+    let repo_path = init_repo_main(&td);
+    write_file(&repo_path, "tracked.txt", "v1\n");
+    let s = GitService::new();
+    s.commit(&repo_path, "add tracked").unwrap();
+    let base = s.get_head_info(&repo_path).unwrap().oid;
+
+    // Snapshot a dirty worktree: a modified tracked file and an untracked one
+    write_file(&repo_path, "tracked.txt", "v2\n");
+    write_file(&repo_path, "notes.txt", "draft\n");
+    let snapshot = s.snapshot_worktree(&repo_path, "snapshot").unwrap();
+    assert_eq!(s.get_head_info(&repo_path).unwrap().oid, base);
+    let (tracked, untracked) = s.get_worktree_change_counts(&repo_path).unwrap();
+    assert_eq!((tracked, untracked), (1, 1));
+
+    // Keep working: commit, edit and create more files
+    write_file(&repo_path, "tracked.txt", "v3\n");
+    s.commit(&repo_path, "more work").unwrap();
+    let later = s.get_head_info(&repo_path).unwrap().oid;
+    write_file(&repo_path, "later.txt", "new\n");
+    fs::remove_file(repo_path.join("notes.txt")).unwrap();
+
+    // Only the files go back; the commit made since is kept
+    s.restore_worktree_snapshot(&repo_path, &snapshot).unwrap();
+    assert_eq!(s.get_head_info(&repo_path).unwrap().oid, later);
+    assert_eq!(
+        fs::read_to_string(repo_path.join("tracked.txt")).unwrap(),
+        "v2\n"
+    );
+    assert_eq!(
+        fs::read_to_string(repo_path.join("notes.txt")).unwrap(),
+        "draft\n"
+    );
+    assert!(!repo_path.join("later.txt").exists());
+    let (tracked, untracked) = s.get_worktree_change_counts(&repo_path).unwrap();
+    assert_eq!((tracked, untracked), (1, 1));
+}
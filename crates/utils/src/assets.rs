@@ -32,6 +32,21 @@ pub fn profiles_path() -> std::path::PathBuf {
     asset_dir().join("profiles.json")
 }
 
+/// Directory holding executor plugin binaries (see the executors crate's `plugin` module)
+pub fn plugins_dir() -> std::path::PathBuf {
+    asset_dir().join("plugins")
+}
+
+/// Prefix holding the managed agent CLIs (see the executors crate's `tool_cache` module)
+pub fn tools_dir() -> std::path::PathBuf {
+    asset_dir().join("tools")
+}
+
+/// Repositories whose `.code-marshal/profiles.json` may set every profile field
+pub fn trusted_projects_path() -> std::path::PathBuf {
+    asset_dir().join("trusted_projects.json")
+}
+
 pub fn credentials_path() -> std::path::PathBuf {
     asset_dir().join("credentials.json")
 }
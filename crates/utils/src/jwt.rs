@@ -41,3 +41,8 @@ pub fn extract_subject(token: &str) -> Result<Uuid, TokenClaimsError> {
     let sub = data.claims.sub.ok_or(TokenClaimsError::MissingSubject)?;
     Uuid::parse_str(&sub).map_err(|_| TokenClaimsError::InvalidSubject(sub))
 }
+
+/// Whether `token` decodes as a JWT (header and claims), without verifying its signature.
+pub fn is_jwt(token: &str) -> bool {
+    insecure_decode::<serde_json::Value>(token).is_ok()
+}
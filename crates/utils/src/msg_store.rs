@@ -8,7 +8,7 @@ use futures::{StreamExt, TryStreamExt, future};
 use tokio::{sync::broadcast, task::JoinHandle};
 use tokio_stream::wrappers::BroadcastStream;
 
-use crate::{log_msg::LogMsg, stream_lines::LinesStreamExt};
+use crate::{log_msg::LogMsg, redact::Redactor, stream_lines::LinesStreamExt};
 
 // 100 MB Limit
 const HISTORY_BYTES: usize = 100000 * 1024;
@@ -27,6 +27,7 @@ struct Inner {
 pub struct MsgStore {
     inner: RwLock<Inner>,
     sender: broadcast::Sender<LogMsg>,
+    redactor: Option<Arc<Redactor>>,
 }
 
 impl Default for MsgStore {
@@ -44,10 +45,23 @@ impl MsgStore {
                 total_bytes: 0,
             }),
             sender,
+            redactor: None,
+        }
+    }
+
+    /// A store that redacts every message before it is stored or sent to listeners
+    pub fn with_redactor(redactor: Arc<Redactor>) -> Self {
+        Self {
+            redactor: Some(redactor),
+            ..Self::new()
         }
     }
 
     pub fn push(&self, msg: LogMsg) {
+        let msg = match &self.redactor {
+            Some(redactor) => redactor.redact_msg(msg),
+            None => msg,
+        };
         let _ = self.sender.send(msg.clone()); // live listeners
         let bytes = msg.approx_bytes();
 
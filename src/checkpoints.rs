@@ -0,0 +1,366 @@
+//! `code-marshal checkpoints ...`: optional checkpoint commits of the worktree during a run,
+//! taken at the start, at each turn boundary, after every N completed file edits and at the end.
+//! Checkpoints are snapshot commits (see [`crate::rewind`]) kept alive by
+//! `refs/code-marshal/checkpoints/<RUN>/<N>`, so HEAD, the index and the files are untouched.
+
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use executors::logs::{ActionType, NormalizedEntry, NormalizedEntryType, ToolStatus};
+use git::{GitCli, GitService};
+use tokio::{sync::mpsc, task::JoinHandle};
+
+use crate::rewind;
+
+const REF_PREFIX: &str = "refs/code-marshal/checkpoints/";
+
+/// A recorded checkpoint, identified by `<RUN>/<N>`
+#[derive(Debug, Clone)]
+pub struct Checkpoint {
+    pub run: String,
+    pub number: usize,
+    pub commit: String,
+    pub created: String,
+    pub subject: String,
+}
+
+impl Checkpoint {
+    pub fn id(&self) -> String {
+        format!("{}/{}", self.run, self.number)
+    }
+}
+
+/// All checkpoints, ordered by run and number
+pub fn list_checkpoints(repo: &Path) -> Result<Vec<Checkpoint>> {
+    let out = GitCli::new()
+        .git(
+            repo,
+            [
+                "for-each-ref",
+                "--format=%(refname)%09%(objectname)%09%(creatordate:iso)%09%(subject)",
+                REF_PREFIX,
+            ],
+        )
+        .context("Failed to list checkpoints")?;
+    let mut checkpoints: Vec<Checkpoint> = out
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(4, '\t');
+            let (run, number) = fields.next()?.strip_prefix(REF_PREFIX)?.split_once('/')?;
+            Some(Checkpoint {
+                run: run.to_string(),
+                number: number.parse().ok()?,
+                commit: fields.next()?.to_string(),
+                created: fields.next()?.to_string(),
+                subject: fields.next()?.to_string(),
+            })
+        })
+        .collect();
+    checkpoints.sort_by(|a, b| (&a.run, a.number).cmp(&(&b.run, b.number)));
+    Ok(checkpoints)
+}
+
+struct Writer {
+    repo: PathBuf,
+    run: String,
+    next: usize,
+    last_tree: Option<String>,
+}
+
+impl Writer {
+    /// Snapshot the worktree unless it matches the previous checkpoint
+    fn write(&mut self, reason: &str, session_id: Option<&str>) -> Result<()> {
+        let subject = match session_id {
+            Some(id) => format!("Checkpoint {}: {reason} (session {id})", self.next),
+            None => format!("Checkpoint {}: {reason}", self.next),
+        };
+        let commit = GitService::new().snapshot_worktree(&self.repo, &subject)?;
+        let tree = GitCli::new()
+            .git(&self.repo, ["rev-parse", &format!("{commit}^{{tree}}")])?
+            .trim()
+            .to_string();
+        if self.last_tree.as_deref() == Some(tree.as_str()) {
+            return Ok(());
+        }
+        let id = format!("{}/{}", self.run, self.next);
+        GitCli::new().update_ref(&self.repo, &format!("{REF_PREFIX}{id}"), &commit)?;
+        println!("[SYSTEM] Checkpoint {id}: {reason}");
+        self.last_tree = Some(tree);
+        self.next += 1;
+        Ok(())
+    }
+}
+
+/// Watches a run's normalized entries and writes checkpoints in order, off the streaming task
+pub struct Checkpointer {
+    tx: mpsc::UnboundedSender<(String, Option<String>)>,
+    worker: JoinHandle<()>,
+    every_edits: Option<usize>,
+    session_id: Option<String>,
+    /// Tool entries already seen completed, by entry index
+    completed: HashSet<usize>,
+    /// A tool completed since the last checkpoint
+    dirty: bool,
+    edits: usize,
+}
+
+impl Checkpointer {
+    /// Start checkpointing `repo` (a worktree top level); `every_edits` also checkpoints after
+    /// that many completed file edits. Writes the run's starting point right away.
+    pub fn start(repo: PathBuf, every_edits: Option<usize>) -> Self {
+        // The pid keeps runs started in the same second apart
+        let secs = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let run = format!("{secs}-{}", std::process::id());
+        println!("[SYSTEM] Checkpointing this run as {run}; see `code-marshal checkpoints list`");
+        let (tx, mut rx) = mpsc::unbounded_channel::<(String, Option<String>)>();
+        let worker = tokio::spawn(async move {
+            let mut writer = Writer {
+                repo,
+                run,
+                next: 0,
+                last_tree: None,
+            };
+            while let Some((reason, session_id)) = rx.recv().await {
+                let result = tokio::task::spawn_blocking(move || {
+                    let result = writer
+                        .write(&reason, session_id.as_deref())
+                        .with_context(|| format!("Failed to write checkpoint ({reason})"));
+                    (writer, result)
+                })
+                .await;
+                match result {
+                    Ok((returned, result)) => {
+                        writer = returned;
+                        if let Err(e) = result {
+                            tracing::warn!("{e:#}");
+                        }
+                    }
+                    Err(e) => {
+                        tracing::warn!("Checkpoint task failed: {e}");
+                        break;
+                    }
+                }
+            }
+        });
+        let checkpointer = Self::with_channel(tx, worker, every_edits);
+        checkpointer.checkpoint("start of run");
+        checkpointer
+    }
+
+    fn with_channel(
+        tx: mpsc::UnboundedSender<(String, Option<String>)>,
+        worker: JoinHandle<()>,
+        every_edits: Option<usize>,
+    ) -> Self {
+        Self {
+            tx,
+            worker,
+            every_edits,
+            session_id: None,
+            completed: HashSet::new(),
+            dirty: false,
+            edits: 0,
+        }
+    }
+
+    pub fn set_session_id(&mut self, session_id: &str) {
+        self.session_id = Some(session_id.to_string());
+    }
+
+    fn checkpoint(&self, reason: &str) {
+        let _ = self.tx.send((reason.to_string(), self.session_id.clone()));
+    }
+
+    /// Feed the latest version of a normalized entry
+    pub fn observe(&mut self, index: usize, entry: &NormalizedEntry) {
+        match &entry.entry_type {
+            // A new user message starts the next turn, so the previous one is complete
+            NormalizedEntryType::UserMessage if self.dirty => {
+                self.dirty = false;
+                self.edits = 0;
+                self.checkpoint("end of turn");
+            }
+            NormalizedEntryType::ToolUse {
+                action_type,
+                status: status @ (ToolStatus::Success | ToolStatus::Failed),
+                ..
+            } if self.completed.insert(index) => {
+                self.dirty = true;
+                if matches!(action_type, ActionType::FileEdit { .. })
+                    && matches!(status, ToolStatus::Success)
+                {
+                    self.edits += 1;
+                    if self.every_edits.is_some_and(|n| self.edits >= n) {
+                        self.checkpoint(&format!("after {} file edits", self.edits));
+                        self.edits = 0;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Write the end-of-run checkpoint and wait for queued checkpoints
+    pub async fn finish(self) {
+        self.checkpoint("end of run");
+        drop(self.tx);
+        let _ = self.worker.await;
+    }
+}
+
+pub fn run(args: &[String]) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let repo =
+        || rewind::repo_root(&cwd).ok_or_else(|| anyhow::anyhow!("Not inside a git worktree"));
+    match args.first().map(String::as_str) {
+        Some("list") => {
+            let run = args.get(1);
+            let checkpoints: Vec<Checkpoint> = list_checkpoints(&repo()?)?
+                .into_iter()
+                .filter(|c| run.is_none_or(|run| &c.run == run))
+                .collect();
+            if checkpoints.is_empty() {
+                println!("[SYSTEM] No checkpoints found");
+            }
+            for c in &checkpoints {
+                println!(
+                    "  {:<16} {}  {}  {}",
+                    c.id(),
+                    &c.commit[..c.commit.len().min(12)],
+                    c.created,
+                    c.subject
+                );
+            }
+            Ok(())
+        }
+        Some("restore") => {
+            let id = args
+                .get(1)
+                .ok_or_else(|| anyhow::anyhow!("Missing <RUN>/<N>"))?;
+            let repo = repo()?;
+            let checkpoint = list_checkpoints(&repo)?
+                .into_iter()
+                .find(|c| &c.id() == id)
+                .ok_or_else(|| {
+                    anyhow::anyhow!("Unknown checkpoint {id}; see `code-marshal checkpoints list`")
+                })?;
+            rewind::restore_commit(
+                &repo,
+                &checkpoint.commit,
+                &format!("Worktree before restoring checkpoint {id}"),
+            )?;
+            println!("[SYSTEM] Worktree restored to checkpoint {id}");
+            println!("[SYSTEM] Undo with `code-marshal checkpoints undo`");
+            Ok(())
+        }
+        Some("undo") => {
+            rewind::undo(&repo()?)?;
+            println!("[SYSTEM] Worktree restored to its state before the last restore");
+            Ok(())
+        }
+        _ => {
+            print!(
+                r#"Usage: code-marshal checkpoints <COMMAND>
+
+Runs started with --checkpoint record the worktree (including uncommitted and untracked files)
+at the start, after each turn, after every --checkpoint-every N file edits and at the end, as
+commits under refs/code-marshal/checkpoints/<RUN>/<N>.
+
+Commands:
+  list [RUN]               List checkpoints, optionally for one run
+  restore <RUN>/<N>        Restore the worktree to a checkpoint
+  undo                     Restore the worktree as it was before the last restore
+"#
+            );
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use executors::logs::FileChange;
+
+    use super::*;
+
+    fn checkpointer(
+        every_edits: Option<usize>,
+    ) -> (
+        Checkpointer,
+        mpsc::UnboundedReceiver<(String, Option<String>)>,
+    ) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let worker = tokio::spawn(async {});
+        (Checkpointer::with_channel(tx, worker, every_edits), rx)
+    }
+
+    fn entry(entry_type: NormalizedEntryType) -> NormalizedEntry {
+        NormalizedEntry {
+            timestamp: None,
+            entry_type,
+            content: String::new(),
+            metadata: None,
+        }
+    }
+
+    fn edit(status: ToolStatus) -> NormalizedEntry {
+        entry(NormalizedEntryType::ToolUse {
+            tool_name: "Edit".to_string(),
+            action_type: ActionType::FileEdit {
+                path: "src/lib.rs".to_string(),
+                changes: vec![FileChange::Delete],
+            },
+            status,
+        })
+    }
+
+    fn reasons(rx: &mut mpsc::UnboundedReceiver<(String, Option<String>)>) -> Vec<String> {
+        std::iter::from_fn(|| rx.try_recv().ok())
+            .map(|(reason, _)| reason)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn checkpoints_turns_with_completed_tools() {
+        let (mut checkpointer, mut rx) = checkpointer(None);
+        checkpointer.set_session_id("s1");
+
+        // A turn without completed tools is not checkpointed
+        checkpointer.observe(0, &entry(NormalizedEntryType::UserMessage));
+        checkpointer.observe(1, &edit(ToolStatus::Created));
+        checkpointer.observe(2, &entry(NormalizedEntryType::UserMessage));
+        assert!(reasons(&mut rx).is_empty());
+
+        checkpointer.observe(1, &edit(ToolStatus::Success));
+        checkpointer.observe(3, &entry(NormalizedEntryType::UserMessage));
+        assert_eq!(
+            rx.try_recv().unwrap(),
+            ("end of turn".to_string(), Some("s1".to_string()))
+        );
+        // Nothing completed since the last checkpoint
+        checkpointer.observe(4, &entry(NormalizedEntryType::UserMessage));
+        assert!(reasons(&mut rx).is_empty());
+    }
+
+    #[tokio::test]
+    async fn checkpoints_every_n_edits() {
+        let (mut checkpointer, mut rx) = checkpointer(Some(2));
+        checkpointer.observe(0, &edit(ToolStatus::Success));
+        // Re-observing an updated entry does not count it again
+        checkpointer.observe(0, &edit(ToolStatus::Success));
+        // Failed edits are not counted
+        checkpointer.observe(1, &edit(ToolStatus::Failed));
+        assert!(reasons(&mut rx).is_empty());
+
+        checkpointer.observe(2, &edit(ToolStatus::Success));
+        assert_eq!(reasons(&mut rx), ["after 2 file edits"]);
+        checkpointer.observe(3, &edit(ToolStatus::Success));
+        assert!(reasons(&mut rx).is_empty());
+    }
+}
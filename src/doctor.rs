@@ -0,0 +1,144 @@
+//! `code-marshal doctor [--agent X]`: start each agent's CLI, check its login and MCP config,
+//! and say what to do about anything that fails. `--check-installed` only looks for files.
+
+use std::str::FromStr;
+
+use anyhow::Result;
+use executors::{
+    doctor::{diagnose, AgentDiagnosis, CheckStatus},
+    executors::{BaseAgentCapability, BaseCodingAgent},
+    profile::ExecutorProfileId,
+};
+
+use crate::{create_agent, AGENT_TYPES};
+
+pub async fn run(args: &[String]) -> Result<()> {
+    let mut profile: Option<String> = None;
+    let mut json_output = false;
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--agent" | "-a" => {
+                profile = Some(
+                    args.get(i + 1)
+                        .cloned()
+                        .ok_or_else(|| anyhow::anyhow!("Missing value for --agent"))?,
+                );
+                i += 2;
+            }
+            "--json" => {
+                json_output = true;
+                i += 1;
+            }
+            "help" | "--help" | "-h" => {
+                print_doctor_usage();
+                return Ok(());
+            }
+            other => anyhow::bail!("Unknown doctor option: {other}"),
+        }
+    }
+
+    let single = profile.is_some();
+    let profile_ids = match profile {
+        Some(raw) => vec![ExecutorProfileId::from_str(&raw)
+            .map_err(|_| anyhow::anyhow!("Unknown agent type: {raw}"))?],
+        // ACP, custom and plugin agents have nothing to check until a profile configures them
+        None => AGENT_TYPES
+            .iter()
+            .filter(|at| {
+                !matches!(
+                    at,
+                    BaseCodingAgent::Acp | BaseCodingAgent::Custom | BaseCodingAgent::Plugin
+                )
+            })
+            .map(|&at| ExecutorProfileId::new(at))
+            .collect(),
+    };
+
+    let current_dir = std::env::current_dir()?;
+    let diagnoses = futures::future::join_all(profile_ids.iter().map(|profile_id| {
+        let current_dir = current_dir.clone();
+        async move {
+            let agent = create_agent(profile_id)?;
+            let setup = agent
+                .capabilities()
+                .contains(&BaseAgentCapability::SetupHelper)
+                .then(|| format!("code-marshal setup {profile_id}"));
+            Ok::<_, anyhow::Error>(diagnose(&agent, &current_dir, setup.as_deref()).await)
+        }
+    }))
+    .await;
+
+    let mut entries = Vec::new();
+    for (profile_id, diagnosis) in profile_ids.into_iter().zip(diagnoses) {
+        entries.push((profile_id, diagnosis?));
+    }
+    let failing = entries
+        .iter()
+        .filter(|(_, diagnosis)| !diagnosis.is_healthy())
+        .count();
+
+    if json_output {
+        let entries: Vec<serde_json::Value> = entries
+            .iter()
+            .map(|(profile_id, diagnosis)| {
+                serde_json::json!({
+                    "profile": profile_id.to_string(),
+                    "healthy": diagnosis.is_healthy(),
+                    "checks": diagnosis.checks,
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+    } else {
+        for (profile_id, diagnosis) in &entries {
+            print_diagnosis(profile_id, diagnosis);
+        }
+        println!(
+            "[SYSTEM] {} of {} agent(s) ready",
+            entries.len() - failing,
+            entries.len()
+        );
+    }
+
+    if single && failing > 0 {
+        anyhow::bail!("{} is not ready", entries[0].0);
+    }
+    Ok(())
+}
+
+fn print_diagnosis(profile_id: &ExecutorProfileId, diagnosis: &AgentDiagnosis) {
+    let verdict = if diagnosis.is_healthy() {
+        "ready"
+    } else {
+        "NOT READY"
+    };
+    println!("[SYSTEM] {profile_id}: {verdict}");
+    for check in &diagnosis.checks {
+        let status = match check.status {
+            CheckStatus::Ok => "ok",
+            CheckStatus::Warning => "warn",
+            CheckStatus::Failed => "FAIL",
+            CheckStatus::Skipped => "skip",
+        };
+        println!("  {status:<4}  {:<10}  {}", check.kind, check.detail);
+        if let Some(remediation) = &check.remediation {
+            println!("  {:<4}  {:<10}  fix: {remediation}", "", "");
+        }
+    }
+}
+
+fn print_doctor_usage() {
+    print!(
+        r#"Usage: code-marshal doctor [--agent <AGENT[:VARIANT]>] [--json]
+
+Checks that each agent can actually run, and prints what to do about anything that fails:
+  command     the agent's program resolves on PATH (honouring base_command_override)
+  version     the CLI starts and prints its version (npx may download it first)
+  auth        a login the agent reports or finds, or a credential environment variable
+  mcp_config  the agent's MCP config file parses
+Without --agent, every built-in agent is checked; ACP, CUSTOM and PLUGIN agents need --agent.
+Exits non-zero when the agent given with --agent is not ready.
+"#
+    );
+}
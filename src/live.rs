@@ -0,0 +1,345 @@
+//! Live (interactive) sessions: the agent process stays alive and further user turns arrive
+//! on stdin or through the session API.
+//!
+//! The session API is a line-delimited JSON protocol over TCP. It only listens on loopback
+//! addresses, and each connection must first authenticate with the token printed when the
+//! session starts. Each request is one JSON object and gets one JSON response line:
+//!
+//! ```text
+//! {"type":"auth","token":"<token>"}                  ->  {"ok":true}
+//! {"type":"send_message","message":"now add tests"}  ->  {"ok":true}
+//! {"type":"interrupt"}                               ->  {"ok":true}
+//! {"type":"interrupt","instruction":"use sqlx"}      ->  {"ok":true}
+//! {"type":"set_mode","mode":"bypass"}                ->  {"ok":true}
+//! {"type":"end"}                                     ->  {"ok":true}
+//! ```
+//!
+//! An interrupt stops the running turn; with an instruction the agent continues the same
+//! session with it (steering). Modes are `plan`, `default`, `accept_edits` and `bypass`; plan
+//! first, review the plan, then `/execute` switches to `bypass` and tells the agent to go ahead.
+
+use std::{
+    str::FromStr,
+    sync::{Arc, Mutex},
+};
+
+use anyhow::Result;
+use executors::executors::{LiveInput, LiveInputSender, SessionMode};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+
+/// Shared handle to a live session's input; ending it lets the agent exit after its turn
+#[derive(Clone)]
+pub struct LiveSession {
+    input: Arc<Mutex<Option<LiveInputSender>>>,
+}
+
+impl LiveSession {
+    pub fn new(input: LiveInputSender) -> Self {
+        Self {
+            input: Arc::new(Mutex::new(Some(input))),
+        }
+    }
+
+    pub fn send(&self, input: LiveInput) -> Result<()> {
+        let guard = self.input.lock().unwrap();
+        let sender = guard
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Session has ended"))?;
+        sender
+            .send(input)
+            .map_err(|_| anyhow::anyhow!("Agent is no longer accepting input"))
+    }
+
+    pub fn end(&self) {
+        self.input.lock().unwrap().take();
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum SessionRequest {
+    Auth {
+        token: String,
+    },
+    SendMessage {
+        message: String,
+    },
+    Interrupt {
+        #[serde(default)]
+        instruction: Option<String>,
+    },
+    SetMode {
+        mode: SessionMode,
+    },
+    End,
+}
+
+#[derive(Debug, Serialize)]
+struct SessionResponse {
+    ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl From<Result<()>> for SessionResponse {
+    fn from(result: Result<()>) -> Self {
+        match result {
+            Ok(()) => Self {
+                ok: true,
+                error: None,
+            },
+            Err(e) => Self {
+                ok: false,
+                error: Some(e.to_string()),
+            },
+        }
+    }
+}
+
+/// Sent by `/execute` when no instruction is given
+const EXECUTE_PLAN_MESSAGE: &str = "The plan is approved. Implement it now.";
+
+/// Read user turns from stdin, one per line. `/interrupt` stops the running turn and
+/// `/steer <text>` replaces it with a new instruction. `/mode <mode>` switches the permission
+/// mode and `/execute [text]` leaves plan mode to carry out the plan. `/exit` ends the session;
+/// so does EOF unless the session API is also accepting input.
+pub fn spawn_stdin_input(session: LiveSession, end_on_eof: bool) {
+    println!(
+        "[SYSTEM] Live session: type a message and press Enter to send it, /interrupt or \
+         /steer <text> to redirect the running turn, /mode <plan|default|accept_edits|bypass> \
+         or /execute to switch modes, /exit to finish."
+    );
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(tokio::io::stdin()).lines();
+        loop {
+            match lines.next_line().await {
+                Ok(Some(line)) => {
+                    let line = line.trim();
+                    if line.is_empty() {
+                        continue;
+                    }
+                    if line == "/exit" {
+                        break;
+                    }
+                    let inputs = match parse_stdin_line(line) {
+                        Ok(inputs) => inputs,
+                        Err(e) => {
+                            println!("[SYSTEM] {e}");
+                            continue;
+                        }
+                    };
+                    for input in inputs {
+                        if let Err(e) = session.send(input) {
+                            println!("[SYSTEM] {e}");
+                            return;
+                        }
+                    }
+                }
+                Ok(None) if !end_on_eof => return,
+                Ok(None) => break,
+                Err(e) => {
+                    println!("[SYSTEM] Failed to read stdin: {e}");
+                    break;
+                }
+            }
+        }
+        println!("[SYSTEM] Ending live session after the current turn...");
+        session.end();
+    });
+}
+
+fn parse_stdin_line(line: &str) -> Result<Vec<LiveInput>> {
+    if line == "/interrupt" {
+        return Ok(vec![LiveInput::Interrupt { instruction: None }]);
+    }
+    if let Some(instruction) = line.strip_prefix("/steer ") {
+        let instruction = instruction.trim();
+        if !instruction.is_empty() {
+            return Ok(vec![LiveInput::Interrupt {
+                instruction: Some(instruction.to_string()),
+            }]);
+        }
+    }
+    if let Some(mode) = line.strip_prefix("/mode ") {
+        let mode = SessionMode::from_str(mode.trim()).map_err(|_| {
+            anyhow::anyhow!("Unknown mode {mode:?}; use plan, default, accept_edits or bypass")
+        })?;
+        return Ok(vec![LiveInput::SetMode(mode)]);
+    }
+    if line == "/execute" || line.starts_with("/execute ") {
+        let instruction = line["/execute".len()..].trim();
+        let instruction = if instruction.is_empty() {
+            EXECUTE_PLAN_MESSAGE
+        } else {
+            instruction
+        };
+        return Ok(vec![
+            LiveInput::SetMode(SessionMode::Bypass),
+            LiveInput::UserMessage(instruction.to_string()),
+        ]);
+    }
+    Ok(vec![LiveInput::UserMessage(line.to_string())])
+}
+
+/// Serve the session API on `addr` until the process exits. The API has no other access
+/// control, so `addr` must be a loopback address and connections must present a token.
+pub async fn serve_session_api(addr: &str, session: LiveSession) -> Result<()> {
+    let resolved: Vec<_> = tokio::net::lookup_host(addr).await?.collect();
+    if resolved.is_empty() || resolved.iter().any(|a| !a.ip().is_loopback()) {
+        anyhow::bail!(
+            "--listen {addr} is not a loopback address; use e.g. 127.0.0.1:7878 and forward the \
+             port if the session must be reached from elsewhere"
+        );
+    }
+    let listener = TcpListener::bind(resolved.as_slice()).await?;
+    let token = Arc::new(uuid::Uuid::new_v4().simple().to_string());
+    println!(
+        "[SYSTEM] Session API listening on {}; authenticate each connection with \
+         {{\"type\":\"auth\",\"token\":\"{token}\"}}",
+        listener.local_addr()?
+    );
+    tokio::spawn(async move {
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tokio::spawn(handle_connection(stream, session.clone(), token.clone()));
+                }
+                Err(e) => tracing::warn!("Session API accept failed: {e}"),
+            }
+        }
+    });
+    Ok(())
+}
+
+async fn handle_connection(stream: TcpStream, session: LiveSession, token: Arc<String>) {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    let mut authenticated = false;
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let result = parse_request(&line).and_then(|request| match request {
+            SessionRequest::Auth { token: given } if given == *token => {
+                authenticated = true;
+                Ok(())
+            }
+            SessionRequest::Auth { .. } => Err(anyhow::anyhow!("Invalid token")),
+            _ if !authenticated => Err(anyhow::anyhow!(
+                "Not authenticated; send {{\"type\":\"auth\",\"token\":\"...\"}} first"
+            )),
+            request => handle_request(request, &session),
+        });
+        // Unauthenticated connections get one answer, not a session to probe
+        let close = !authenticated;
+        let response = SessionResponse::from(result);
+        let mut out = serde_json::to_string(&response).unwrap_or_default();
+        out.push('\n');
+        if writer.write_all(out.as_bytes()).await.is_err() || close {
+            break;
+        }
+    }
+}
+
+fn parse_request(line: &str) -> Result<SessionRequest> {
+    Ok(serde_json::from_str(line)?)
+}
+
+fn handle_request(request: SessionRequest, session: &LiveSession) -> Result<()> {
+    match request {
+        SessionRequest::Auth { .. } => Ok(()),
+        SessionRequest::SendMessage { message } => session.send(LiveInput::UserMessage(message)),
+        SessionRequest::Interrupt { instruction } => {
+            session.send(LiveInput::Interrupt { instruction })
+        }
+        SessionRequest::SetMode { mode } => session.send(LiveInput::SetMode(mode)),
+        SessionRequest::End => {
+            session.end();
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use executors::executors::live_input_channel;
+
+    use super::*;
+
+    #[test]
+    fn parses_stdin_commands() {
+        assert!(matches!(
+            parse_stdin_line("/steer  use sqlx ").unwrap().as_slice(),
+            [LiveInput::Interrupt { instruction: Some(i) }] if i == "use sqlx"
+        ));
+        assert!(matches!(
+            parse_stdin_line("/interrupt").unwrap().as_slice(),
+            [LiveInput::Interrupt { instruction: None }]
+        ));
+        // A bare /steer has no instruction and is sent as a message
+        assert!(matches!(
+            parse_stdin_line("/steer").unwrap().as_slice(),
+            [LiveInput::UserMessage(m)] if m == "/steer"
+        ));
+
+        assert!(matches!(
+            parse_stdin_line("/mode accept_edits").unwrap().as_slice(),
+            [LiveInput::SetMode(SessionMode::AcceptEdits)]
+        ));
+        assert!(parse_stdin_line("/mode yolo").is_err());
+
+        assert!(matches!(
+            parse_stdin_line("/execute").unwrap().as_slice(),
+            [LiveInput::SetMode(SessionMode::Bypass), LiveInput::UserMessage(m)]
+                if m == EXECUTE_PLAN_MESSAGE
+        ));
+        assert!(matches!(
+            parse_stdin_line("/execute skip step 3").unwrap().as_slice(),
+            [LiveInput::SetMode(SessionMode::Bypass), LiveInput::UserMessage(m)]
+                if m == "skip step 3"
+        ));
+        assert!(matches!(
+            parse_stdin_line("/executed it").unwrap().as_slice(),
+            [LiveInput::UserMessage(m)] if m == "/executed it"
+        ));
+    }
+
+    #[test]
+    fn decodes_api_requests() {
+        let (tx, mut rx) = live_input_channel();
+        let session = LiveSession::new(tx);
+        let handle = |line: &str| parse_request(line).and_then(|r| handle_request(r, &session));
+
+        handle(r#"{"type":"send_message","message":"now add tests"}"#).unwrap();
+        assert!(matches!(rx.try_recv(), Ok(LiveInput::UserMessage(m)) if m == "now add tests"));
+
+        handle(r#"{"type":"interrupt"}"#).unwrap();
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(LiveInput::Interrupt { instruction: None })
+        ));
+        handle(r#"{"type":"interrupt","instruction":"use sqlx"}"#).unwrap();
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(LiveInput::Interrupt { instruction: Some(i) }) if i == "use sqlx"
+        ));
+
+        handle(r#"{"type":"set_mode","mode":"plan"}"#).unwrap();
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(LiveInput::SetMode(SessionMode::Plan))
+        ));
+
+        assert!(handle(r#"{"type":"set_mode","mode":"yolo"}"#).is_err());
+        assert!(handle(r#"{"type":"unknown"}"#).is_err());
+        assert!(handle("not json").is_err());
+        assert!(rx.try_recv().is_err());
+
+        handle(r#"{"type":"end"}"#).unwrap();
+        assert!(handle(r#"{"type":"send_message","message":"late"}"#).is_err());
+    }
+}
@@ -1,13 +1,54 @@
-use std::{collections::HashMap, str::FromStr, sync::Arc};
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::PathBuf,
+    str::FromStr,
+    sync::Arc,
+};
 
 use anyhow::{Context, Result};
 use executors::{
-    approvals::NoopExecutorApprovalService,
+    approvals::{
+        ExecutorApprovalService, NoopExecutorApprovalService, PlanReviewApprovalService,
+        ProtectedPathsApprovalService,
+    },
+    attachments::prepare_attachments,
+    changes::ChangesReport,
+    diff_stream::spawn_diff_streamer,
     env::{ExecutionEnv, RepoContext},
-    executors::{BaseCodingAgent, CodingAgent, StandardCodingAgentExecutor},
+    executors::{BaseAgentCapability, BaseCodingAgent, CodingAgent, StandardCodingAgentExecutor},
+    logs::{
+        utils::patch::extract_normalized_entry_from_patch, NormalizedEntry, NormalizedEntryError,
+        NormalizedEntryType,
+    },
+    models::with_model,
+    plan::{plan_only_agent, PlanArtifact, PLAN_ONLY_INSTRUCTIONS},
+    profile::{
+        spawn_profiles_watcher, subscribe_profile_events, ExecutorConfigs, ExecutorProfileId,
+        ProfilesEvent,
+    },
+    protected::{
+        ProtectedPathOutcome, ProtectedPathViolation, ProtectedPaths, PROJECT_PROTECTED_PATHS,
+    },
+    template::{load_named_template, parse_var, PromptContext},
 };
 use futures::StreamExt;
-use workspace_utils::{log_msg::LogMsg, msg_store::MsgStore};
+use git::{Commit, DiffTarget, GitService};
+use workspace_utils::{
+    diff::{compute_line_change_counts, Diff},
+    log_msg::LogMsg,
+    msg_store::MsgStore,
+    redact::{set_global_redactor, RedactingStderr, Redactor},
+};
+
+mod checkpoints;
+mod doctor;
+mod live;
+mod models;
+mod profiles;
+mod rewind;
+mod setup;
+mod templates;
+mod tools;
 
 #[tokio::main]
 async fn main() -> Result<()> {
@@ -17,7 +58,7 @@ async fn main() -> Result<()> {
                 .add_directive(tracing::Level::INFO.into())
                 .add_directive("executors=debug".parse().unwrap()),
         )
-        .with_writer(std::io::stderr)
+        .with_writer(RedactingStderr)
         .init();
 
     let args: Vec<String> = std::env::args().collect();
@@ -33,12 +74,59 @@ async fn main() -> Result<()> {
         return Ok(());
     }
 
+    if args[1] == "profiles" {
+        return profiles::run(&args[2..]);
+    }
+    if args[1] == "templates" {
+        return templates::run(&args[2..]);
+    }
+    if args[1] == "rewind" {
+        return rewind::run(&args[2..]);
+    }
+    if args[1] == "checkpoints" {
+        return checkpoints::run(&args[2..]);
+    }
+    if args[1] == "models" {
+        return models::run(&args[2..]).await;
+    }
+    if args[1] == "setup" {
+        return setup::run(&args[2..]).await;
+    }
+    if args[1] == "doctor" {
+        return doctor::run(&args[2..]).await;
+    }
+    if args[1] == "tools" {
+        return tools::run(&args[2..]).await;
+    }
+
     let mut agent_type_str: Option<String> = None;
+    let mut variant: Option<String> = None;
+    let mut model: Option<String> = None;
     let mut follow_up_session_id: Option<String> = None;
     let mut include_raw_logs = false;
     // Default to pretty output to reduce token volume for human/AI consumers.
     let mut json_output = false;
-    let mut prompt = String::new();
+    let mut positionals: Vec<String> = Vec::new();
+    let mut last_positional_index: Option<usize> = None;
+    let mut prompt_file: Option<String> = None;
+    let mut attachments: Vec<PathBuf> = Vec::new();
+    let mut template_name: Option<String> = None;
+    let mut template_vars: HashMap<String, String> = HashMap::new();
+    let mut template_env: Vec<String> = Vec::new();
+    let mut interactive = false;
+    let mut listen_addr: Option<String> = None;
+    let mut plan_only = false;
+    let mut plan_output: Option<PathBuf> = None;
+    let mut rewind_to: Option<String> = None;
+    let mut snapshots = false;
+    let mut checkpoint = false;
+    let mut checkpoint_every: Option<usize> = None;
+    let mut stream_diffs = false;
+    let mut changes_report = false;
+    let mut protect: Vec<String> = Vec::new();
+    let mut revert_protected = false;
+    let mut redact = true;
+    let mut redact_patterns: Vec<String> = Vec::new();
 
     // Simple arg parsing (intentionally lightweight; clap can be added later)
     let mut i = 1;
@@ -48,6 +136,37 @@ async fn main() -> Result<()> {
                 print_usage();
                 return Ok(());
             }
+            // `code-marshal chat ...` is shorthand for `--interactive`
+            "chat" if i == 1 => {
+                interactive = true;
+                i += 1;
+            }
+            "--interactive" | "-i" => {
+                interactive = true;
+                i += 1;
+            }
+            "--listen" => {
+                if i + 1 < args.len() {
+                    listen_addr = Some(args[i + 1].clone());
+                    interactive = true;
+                    i += 2;
+                } else {
+                    anyhow::bail!("Missing value for --listen <ADDR>");
+                }
+            }
+            "--plan-only" => {
+                plan_only = true;
+                i += 1;
+            }
+            "--plan-output" => {
+                if i + 1 < args.len() {
+                    plan_output = Some(PathBuf::from(&args[i + 1]));
+                    plan_only = true;
+                    i += 2;
+                } else {
+                    anyhow::bail!("Missing value for --plan-output <PATH>");
+                }
+            }
             "--list-agents" | "-l" => {
                 list_agents();
                 return Ok(());
@@ -64,6 +183,22 @@ async fn main() -> Result<()> {
                     anyhow::bail!("Missing value for --agent");
                 }
             }
+            "--variant" => {
+                if i + 1 < args.len() {
+                    variant = Some(args[i + 1].clone());
+                    i += 2;
+                } else {
+                    anyhow::bail!("Missing value for --variant");
+                }
+            }
+            "--model" | "-m" => {
+                if i + 1 < args.len() {
+                    model = Some(args[i + 1].clone());
+                    i += 2;
+                } else {
+                    anyhow::bail!("Missing value for --model");
+                }
+            }
             "--follow-up" | "-f" => {
                 if i + 1 < args.len() {
                     follow_up_session_id = Some(args[i + 1].clone());
@@ -72,6 +207,104 @@ async fn main() -> Result<()> {
                     anyhow::bail!("Missing value for --follow-up <SESSION_ID>");
                 }
             }
+            "--rewind-to" => {
+                if i + 1 < args.len() {
+                    rewind_to = Some(args[i + 1].clone());
+                    i += 2;
+                } else {
+                    anyhow::bail!("Missing value for --rewind-to <MESSAGE_ID>");
+                }
+            }
+            "--snapshots" => {
+                snapshots = true;
+                i += 1;
+            }
+            "--changes-report" => {
+                changes_report = true;
+                i += 1;
+            }
+            "--protect" => {
+                if i + 1 < args.len() {
+                    protect.push(args[i + 1].clone());
+                    i += 2;
+                } else {
+                    anyhow::bail!("Missing value for --protect <PATTERN>");
+                }
+            }
+            "--revert-protected" => {
+                revert_protected = true;
+                i += 1;
+            }
+            "--no-redact" => {
+                redact = false;
+                i += 1;
+            }
+            "--redact" => {
+                if i + 1 < args.len() {
+                    redact_patterns.push(args[i + 1].clone());
+                    i += 2;
+                } else {
+                    anyhow::bail!("Missing value for --redact <REGEX>");
+                }
+            }
+            "--diffs" => {
+                stream_diffs = true;
+                i += 1;
+            }
+            "--checkpoint" => {
+                checkpoint = true;
+                i += 1;
+            }
+            "--checkpoint-every" => {
+                if i + 1 < args.len() {
+                    let n: usize =
+                        args[i + 1].parse().ok().filter(|n| *n > 0).ok_or_else(|| {
+                            anyhow::anyhow!("--checkpoint-every expects a positive number")
+                        })?;
+                    checkpoint_every = Some(n);
+                    checkpoint = true;
+                    i += 2;
+                } else {
+                    anyhow::bail!("Missing value for --checkpoint-every <N>");
+                }
+            }
+            "--template" | "-t" => {
+                if i + 1 < args.len() {
+                    template_name = Some(args[i + 1].clone());
+                    i += 2;
+                } else {
+                    anyhow::bail!("Missing value for --template <NAME>");
+                }
+            }
+            "--var" => {
+                if i + 1 < args.len() {
+                    match parse_var(&args[i + 1])? {
+                        (key, Some(value)) => {
+                            template_vars.insert(key, value);
+                        }
+                        (name, None) => template_env.push(name),
+                    }
+                    i += 2;
+                } else {
+                    anyhow::bail!("Missing value for --var <KEY=VALUE|NAME>");
+                }
+            }
+            "--prompt-file" => {
+                if i + 1 < args.len() {
+                    prompt_file = Some(args[i + 1].clone());
+                    i += 2;
+                } else {
+                    anyhow::bail!("Missing value for --prompt-file <PATH>");
+                }
+            }
+            "--attach" => {
+                if i + 1 < args.len() {
+                    attachments.push(PathBuf::from(&args[i + 1]));
+                    i += 2;
+                } else {
+                    anyhow::bail!("Missing value for --attach <PATH>");
+                }
+            }
             "--raw" => {
                 include_raw_logs = true;
                 i += 1;
@@ -80,25 +313,52 @@ async fn main() -> Result<()> {
                 json_output = true;
                 i += 1;
             }
-            arg if arg.starts_with('-') => {
+            arg if arg.starts_with('-') && arg != "-" => {
                 anyhow::bail!("Unknown argument: {}", arg);
             }
             arg => {
-                // Treat the first positional argument as the prompt (remaining positionals are ignored)
-                prompt = arg.to_string();
+                // Positional words are joined into the prompt, but only if they are contiguous:
+                // `code-marshal fix -a CODEX the bug` is rejected rather than guessed at.
+                if last_positional_index.is_some_and(|last| last + 1 != i) {
+                    anyhow::bail!(
+                        "Ambiguous prompt: positional arguments are separated by options. Quote the prompt as one argument or use --prompt-file."
+                    );
+                }
+                positionals.push(arg.to_string());
+                last_positional_index = Some(i);
                 i += 1;
             }
         }
     }
 
-    if prompt.is_empty() {
+    if rewind_to.is_some() && follow_up_session_id.is_none() {
+        anyhow::bail!("--rewind-to resumes an existing session; pass --follow-up <SESSION_ID>");
+    }
+    if plan_only && interactive {
+        anyhow::bail!("--plan-only runs a single turn; use `/mode plan` in a live session instead");
+    }
+    if interactive && (positionals.iter().any(|p| p == "-") || prompt_file.as_deref() == Some("-"))
+    {
+        anyhow::bail!("Live sessions read further messages from stdin; pass the first prompt as an argument or file");
+    }
+    let mut prompt = read_prompt(&positionals, prompt_file.as_deref())?;
+    if interactive && prompt.is_empty() && template_name.is_none() {
+        println!("[SYSTEM] Enter the first message:");
+        let mut line = String::new();
+        std::io::stdin()
+            .read_line(&mut line)
+            .context("Failed to read the first message from stdin")?;
+        prompt = line.trim().to_string();
+    }
+
+    if prompt.is_empty() && template_name.is_none() {
         print_usage();
         return Ok(());
     }
 
     // Determine agent type
-    let agent_type = if let Some(s) = agent_type_str {
-        BaseCodingAgent::from_str(&s.to_uppercase()).map_err(|_| {
+    let mut profile_id = if let Some(s) = agent_type_str {
+        ExecutorProfileId::from_str(&s).map_err(|_| {
             anyhow::anyhow!(
                 "Unknown agent type: {}. Valid values: CLAUDE_CODE, CURSOR_AGENT, CODEX, OPENCODE, GEMINI, QWEN_CODE, etc.",
                 s
@@ -109,7 +369,7 @@ async fn main() -> Result<()> {
         let available = get_installed_agent_types()?;
         if let Some(first) = available.first() {
             println!("[SYSTEM] Using first available agent: {}", first);
-            *first
+            ExecutorProfileId::new(*first)
         } else {
             anyhow::bail!(
                 "No coding agents found on system. Please install one (e.g., claude-code, cursor, etc.)"
@@ -117,20 +377,157 @@ async fn main() -> Result<()> {
         }
     };
 
+    if let Some(variant) = variant {
+        if profile_id.variant.is_some() {
+            anyhow::bail!(
+                "--variant {variant} conflicts with --agent {profile_id}; give the variant once"
+            );
+        }
+        profile_id = ExecutorProfileId::from_str(&format!("{}:{variant}", profile_id.executor))?;
+    }
+    let agent_type = profile_id.executor;
+
     println!(
         "[SYSTEM] Initializing Code-Marshal with Agent: {}...",
-        agent_type
+        profile_id
     );
 
-    // 1) Setup executor
-    let mut agent = create_agent(agent_type)?;
+    // 1) Setup executor. With --template or --var, the prompt and the profile's prompt fields
+    // are templates; otherwise they are sent as written.
+    let current_dir = std::env::current_dir()?;
+    let templating =
+        template_name.is_some() || !template_vars.is_empty() || !template_env.is_empty();
+    let mut agent = create_agent(&profile_id)?;
+    let prompt = if templating {
+        let prompt_template = match &template_name {
+            Some(name) => {
+                // The positional prompt (if any) is available to the template as {{prompt}}
+                template_vars.insert("prompt".to_string(), prompt.clone());
+                load_named_template(name, &current_dir)?
+            }
+            None => prompt.clone(),
+        };
+        let template_ctx = PromptContext::new(current_dir.clone())
+            .with_vars(template_vars)
+            .with_env(std::env::vars().collect(), &template_env)
+            .with_git_info();
+        agent = template_ctx
+            .render_agent(&agent)
+            .context("Failed to render profile prompt fields")?;
+        template_ctx
+            .render(&prompt_template)
+            .context("Failed to render prompt")?
+    } else {
+        prompt
+    };
+
+    // --model overrides the profile's model; it is checked against the agent's models first
+    if let Some(model) = &model {
+        agent = with_model(&agent, model)
+            .ok_or_else(|| anyhow::anyhow!("Agent {agent_type} has no model setting"))?;
+        models::check_model(&agent, agent_type, model, &current_dir).await?;
+    }
 
-    // 2) Auto-approval (fully automated)
-    let approval_service = Arc::new(NoopExecutorApprovalService);
-    agent.use_approvals(approval_service);
+    // Text attachments are inlined into the prompt; images need native agent support
+    let (prompt, images) = prepare_attachments(&prompt, &attachments, &current_dir)?;
+
+    // Requested features are checked against the agent's capabilities before anything runs
+    let requested = [
+        (
+            follow_up_session_id.is_some(),
+            BaseAgentCapability::SessionFork,
+            "--follow-up",
+        ),
+        (
+            rewind_to.is_some(),
+            BaseAgentCapability::MessageReset,
+            "--rewind-to",
+        ),
+        (interactive, BaseAgentCapability::LiveInput, "--interactive"),
+        (plan_only, BaseAgentCapability::PlanOnly, "--plan-only"),
+        (
+            !images.is_empty(),
+            BaseAgentCapability::ImageInput,
+            "--attach <IMAGE>",
+        ),
+    ];
+    let requested: Vec<_> = requested
+        .into_iter()
+        .filter(|(wanted, _, _)| *wanted)
+        .map(|(_, capability, flag)| (capability, flag))
+        .collect();
+    agent.require_capabilities(&requested)?;
+
+    // Plan-only runs use a non-mutating configuration of the agent
+    let prompt = if plan_only {
+        agent = plan_only_agent(&agent)
+            .ok_or_else(|| anyhow::anyhow!("Agent {agent_type} does not support plan-only runs"))?;
+        format!("{prompt}\n\n{PLAN_ONLY_INSTRUCTIONS}")
+    } else {
+        prompt
+    };
+    let message_reset = agent
+        .capabilities()
+        .contains(&BaseAgentCapability::MessageReset);
+    // Agents that can resume from a message get a worktree snapshot per message id, so a later
+    // --rewind-to can restore the files along with the conversation
+    let snapshot_repo = if snapshots && message_reset {
+        rewind::repo_root(&current_dir)
+    } else {
+        None
+    };
+
+    // Protected paths: the repository's .code-marshal/protected-paths plus --protect
+    let protected = match rewind::repo_root(&current_dir) {
+        Some(repo) => Some(ProtectedPaths::load(&repo, &protect)?).filter(|p| !p.is_empty()),
+        None if !protect.is_empty() => anyhow::bail!("--protect requires a git worktree"),
+        None => None,
+    }
+    .map(Arc::new);
+    if revert_protected && protected.is_none() {
+        anyhow::bail!(
+            "--revert-protected needs protected paths (--protect or {PROJECT_PROTECTED_PATHS})"
+        );
+    }
+
+    // 2) Auto-approval (fully automated). Live sessions and plan-only runs stop at a finished
+    // plan instead, so it can be reviewed before it is executed
+    let approvals: Arc<dyn ExecutorApprovalService> = if interactive || plan_only {
+        Arc::new(PlanReviewApprovalService)
+    } else {
+        Arc::new(NoopExecutorApprovalService)
+    };
+    // Tool calls that would change a protected path are denied and listed as they happen
+    let (violations_tx, mut violations_rx) = tokio::sync::mpsc::unbounded_channel();
+    match &protected {
+        Some(paths) => {
+            println!(
+                "[SYSTEM] Protected paths: {}",
+                paths.patterns().collect::<Vec<_>>().join(", ")
+            );
+            // Profiles that skip approvals would never reach the protected paths check up front
+            if !agent.require_approvals() {
+                println!(
+                    "[SYSTEM] Warning: {profile_id} does not ask before editing; protected paths \
+                     are only checked after the run"
+                );
+            }
+            agent.use_approvals(Arc::new(ProtectedPathsApprovalService::new(
+                paths.clone(),
+                current_dir.clone(),
+                approvals,
+                violations_tx,
+            )));
+        }
+        None => agent.use_approvals(approvals),
+    }
+    tokio::spawn(async move {
+        while let Some(violation) = violations_rx.recv().await {
+            print_protected_violation(&violation, json_output);
+        }
+    });
 
     // 3) Environment setup
-    let current_dir = std::env::current_dir()?;
     let repo_context = RepoContext::new(current_dir.clone(), vec![]);
     let mut env = ExecutionEnv::new(repo_context, false, String::new());
 
@@ -140,6 +537,85 @@ async fn main() -> Result<()> {
         vars.insert(key, value);
     }
     env.merge(&vars);
+    let env = env.with_images(images).with_interactive(interactive);
+
+    // Secrets are masked in all event output and logs: values of secret-looking variables of
+    // the environment and the profile's env, MCP server credentials, known token formats
+    // (API keys, GitHub tokens, JWTs) and --redact patterns
+    let redactor = if redact {
+        let mut redactor = Redactor::new().with_env(&env.vars);
+        if let Some(profile_env) = agent.cmd_overrides().and_then(|cmd| cmd.env.as_ref()) {
+            redactor = redactor.with_env(profile_env);
+        }
+        for (name, value) in agent.mcp_server_secrets().await {
+            redactor = redactor.with_secret(name, value);
+        }
+        for pattern in &redact_patterns {
+            redactor = redactor
+                .with_pattern(pattern)
+                .with_context(|| format!("Invalid --redact pattern {pattern:?}"))?;
+        }
+        let redactor = Arc::new(redactor);
+        set_global_redactor(Some(redactor.clone()));
+        Some(redactor)
+    } else {
+        if !redact_patterns.is_empty() {
+            anyhow::bail!("--redact and --no-redact cannot be combined");
+        }
+        None
+    };
+
+    // Files are restored when the run that reached the message recorded --snapshots
+    if let Some(message_id) = rewind_to.as_deref() {
+        match rewind::repo_root(&current_dir) {
+            Some(repo) if rewind::has_snapshot(&repo, message_id)? => {
+                rewind::restore(&repo, message_id)?;
+                println!(
+                    "[SYSTEM] Worktree restored to the best-effort snapshot of message {message_id}"
+                );
+                println!("[SYSTEM] Undo the file restore with `code-marshal rewind undo`");
+            }
+            _ => println!(
+                "[SYSTEM] No worktree snapshot recorded for message {message_id}; \
+                 rewinding the conversation only"
+            ),
+        }
+    }
+
+    // Live diffs, the changes report and the protected paths check diff against the worktree as
+    // it was before the run, so earlier uncommitted work is not attributed to the agent. Plan-only
+    // runs use it, where there is a worktree, to verify that they changed nothing.
+    let run_base_flag = if stream_diffs {
+        Some("--diffs")
+    } else if changes_report {
+        Some("--changes-report")
+    } else if protected.is_some() {
+        Some("--protect")
+    } else {
+        None
+    };
+    let run_base = if run_base_flag.is_some() || plan_only {
+        match (rewind::repo_root(&current_dir), run_base_flag) {
+            (Some(repo), _) => {
+                let base = GitService::new()
+                    .snapshot_worktree(&repo, "Worktree at the start of the run")
+                    .context("Failed to record the worktree before the run")?;
+                Some((repo, base))
+            }
+            (None, Some(flag)) => anyhow::bail!("{flag} requires a git worktree"),
+            (None, None) => None,
+        }
+    } else {
+        None
+    };
+
+    let mut checkpointer = if checkpoint {
+        let repo = rewind::repo_root(&current_dir)
+            .ok_or_else(|| anyhow::anyhow!("--checkpoint requires a git worktree"))?;
+        Some(checkpoints::Checkpointer::start(repo, checkpoint_every))
+    } else {
+        None
+    };
 
     // 4) Spawn agent (initial or follow-up)
     println!("[SYSTEM] Spawning agent in {:?}", current_dir);
@@ -147,7 +623,13 @@ async fn main() -> Result<()> {
     let mut spawned = if let Some(session_id) = follow_up_session_id.as_deref() {
         println!("[SYSTEM] Follow-up session: {}", session_id);
         agent
-            .spawn_follow_up(&current_dir, &prompt, session_id, None, &env)
+            .spawn_follow_up(
+                &current_dir,
+                &prompt,
+                session_id,
+                rewind_to.as_deref(),
+                &env,
+            )
             .await
             .context("Failed to spawn follow-up")?
     } else {
@@ -157,12 +639,27 @@ async fn main() -> Result<()> {
             .context("Failed to spawn agent")?
     };
 
+    if interactive {
+        let input = spawned
+            .live_input
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("Agent {agent_type} did not start a live session"))?;
+        let session = live::LiveSession::new(input);
+        if let Some(addr) = &listen_addr {
+            live::serve_session_api(addr, session.clone()).await?;
+        }
+        live::spawn_stdin_input(session, listen_addr.is_none());
+    }
+
     // 5) Initialize message store
     //
     // IMPORTANT: in vibe-kanban, the "container" layer streams child stdout/stderr into MsgStore.
     // code-marshal is a CLI, so we must do that wiring here; otherwise normalize_logs has nothing
     // to consume and you won't see SessionId / assistant messages / tool calls.
-    let msg_store = Arc::new(MsgStore::new());
+    let msg_store = Arc::new(match redactor {
+        Some(redactor) => MsgStore::with_redactor(redactor),
+        None => MsgStore::new(),
+    });
 
     // Wire child stdout/stderr -> MsgStore
     {
@@ -172,12 +669,13 @@ async fn main() -> Result<()> {
             let msg_store_clone = msg_store.clone();
             tokio::spawn(async move {
                 let mut stream = ReaderStream::new(stdout);
+                let mut pending: Vec<u8> = Vec::new();
                 while let Some(chunk) = futures::StreamExt::next(&mut stream).await {
                     match chunk {
                         Ok(bytes) => {
-                            let s = String::from_utf8_lossy(bytes.as_ref()).into_owned();
-                            if !s.is_empty() {
-                                msg_store_clone.push_stdout(s);
+                            pending.extend_from_slice(bytes.as_ref());
+                            if let Some(lines) = take_complete_lines(&mut pending) {
+                                msg_store_clone.push_stdout(lines);
                             }
                         }
                         Err(e) => {
@@ -187,6 +685,9 @@ async fn main() -> Result<()> {
                         }
                     }
                 }
+                if !pending.is_empty() {
+                    msg_store_clone.push_stdout(String::from_utf8_lossy(&pending).into_owned());
+                }
             });
         }
 
@@ -194,12 +695,13 @@ async fn main() -> Result<()> {
             let msg_store_clone = msg_store.clone();
             tokio::spawn(async move {
                 let mut stream = ReaderStream::new(stderr);
+                let mut pending: Vec<u8> = Vec::new();
                 while let Some(chunk) = futures::StreamExt::next(&mut stream).await {
                     match chunk {
                         Ok(bytes) => {
-                            let s = String::from_utf8_lossy(bytes.as_ref()).into_owned();
-                            if !s.is_empty() {
-                                msg_store_clone.push_stderr(s);
+                            pending.extend_from_slice(bytes.as_ref());
+                            if let Some(lines) = take_complete_lines(&mut pending) {
+                                msg_store_clone.push_stderr(lines);
                             }
                         }
                         Err(e) => {
@@ -209,6 +711,9 @@ async fn main() -> Result<()> {
                         }
                     }
                 }
+                if !pending.is_empty() {
+                    msg_store_clone.push_stderr(String::from_utf8_lossy(&pending).into_owned());
+                }
             });
         }
     }
@@ -223,12 +728,53 @@ async fn main() -> Result<()> {
         });
     }
 
+    // Stream live worktree diffs (from git, independent of the agent's normalizer)
+    let mut diff_streamer = match run_base.as_ref().filter(|_| stream_diffs) {
+        Some((repo, base)) => {
+            let cancel = tokio_util::sync::CancellationToken::new();
+            let handle = spawn_diff_streamer(
+                msg_store.clone(),
+                repo.clone(),
+                base.parse::<Commit>()?,
+                std::time::Duration::from_secs(1),
+                cancel.clone(),
+            );
+            Some((cancel, handle))
+        }
+        None => None,
+    };
+
+    // Hot-reload profiles while a live session runs; invalid edits keep the last good config.
+    // One-shot runs keep the profiles they started with.
+    let profiles_watch = tokio_util::sync::CancellationToken::new();
+    if interactive {
+        spawn_profiles_watcher(std::time::Duration::from_secs(2), profiles_watch.clone());
+        let mut events = subscribe_profile_events();
+        tokio::spawn(async move {
+            while let Ok(event) = events.recv().await {
+                match event {
+                    ProfilesEvent::Changed { paths } => {
+                        println!("[SYSTEM] Profiles reloaded from {:?}", paths);
+                    }
+                    ProfilesEvent::Rejected { error, .. } => {
+                        println!("[SYSTEM] Ignoring invalid profiles change: {error}");
+                    }
+                }
+            }
+        });
+    }
+
     // 7) Stream normalized logs to stdout, and *reliably* terminate when the child exits.
     println!("[SYSTEM] Task started. Streaming normalized events...");
 
     let mut stream = msg_store.history_plus_stream();
     let mut exit_signal = spawned.exit_signal.take();
     let mut child_exited = false;
+    let mut session_id: Option<String> = None;
+    // Latest version of each normalized entry, for the plan artifact
+    let mut entries: BTreeMap<usize, NormalizedEntry> = BTreeMap::new();
+    let mut setup_hint_shown = false;
+    let recorder = snapshot_repo.map(rewind::SnapshotRecorder::start);
 
     loop {
         tokio::select! {
@@ -255,6 +801,11 @@ async fn main() -> Result<()> {
                 // We wait a tiny bit to allow background log processors to catch up
                 // before we push the finished marker.
                 tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+                // Final diffs go out before the finished marker
+                if let Some((cancel, handle)) = diff_streamer.take() {
+                    cancel.cancel();
+                    let _ = handle.await;
+                }
                 msg_store.push_finished();
                 println!("[SYSTEM] Child process exited: {:?}", res);
             }
@@ -274,10 +825,44 @@ async fn main() -> Result<()> {
                             }
                         }
 
+                        if let LogMsg::JsonPatch(patch) = &msg {
+                            if let Some((index, entry)) = extract_normalized_entry_from_patch(patch)
+                            {
+                                if let Some(checkpointer) = &mut checkpointer {
+                                    checkpointer.observe(index, &entry);
+                                }
+                                if !setup_hint_shown
+                                    && matches!(
+                                        entry.entry_type,
+                                        NormalizedEntryType::ErrorMessage {
+                                            error_type: NormalizedEntryError::SetupRequired
+                                        }
+                                    )
+                                {
+                                    setup_hint_shown = true;
+                                    setup::print_setup_hint(&agent, &profile_id).await;
+                                }
+                                entries.insert(index, entry);
+                            }
+                        }
+
                         // Surface session id clearly for follow-ups
                         if let LogMsg::SessionId(id) = &msg {
+                            session_id = Some(id.clone());
                             println!("[SYSTEM] SessionId: {}", id);
-                            println!("[SYSTEM] Follow-up usage: code-marshal -a {} --follow-up {} \"your next prompt\"", agent_type, id);
+                            println!("[SYSTEM] Follow-up usage: code-marshal -a {} --follow-up {} \"your next prompt\"", profile_id, id);
+                            if recorder.is_some() {
+                                println!("[SYSTEM] Rewind points (best-effort snapshots): code-marshal rewind list {id}");
+                            }
+                            if let Some(checkpointer) = &mut checkpointer {
+                                checkpointer.set_session_id(id);
+                            }
+                        }
+
+                        if let (LogMsg::MessageId(id), Some(recorder), Some(session)) =
+                            (&msg, &recorder, &session_id)
+                        {
+                            recorder.record(session, id);
                         }
 
                         if matches!(msg, LogMsg::Finished) {
@@ -302,62 +887,301 @@ async fn main() -> Result<()> {
         }
     }
 
+    profiles_watch.cancel();
+    if let Some(recorder) = recorder {
+        recorder.finish().await;
+    }
+    if let Some(checkpointer) = checkpointer {
+        checkpointer.finish().await;
+    }
+    let protected_changes = match (&protected, &run_base) {
+        (Some(paths), Some((repo, base))) => {
+            check_protected_paths(paths, repo, base, revert_protected, json_output)?
+        }
+        _ => Vec::new(),
+    };
+    if let Some((repo, base)) = run_base.as_ref().filter(|_| changes_report) {
+        report_changes(repo, base, entries.values(), &current_dir, json_output)?;
+    }
+    if plan_only {
+        report_plan(
+            entries.values(),
+            plan_output.as_deref(),
+            json_output,
+            run_base.as_ref(),
+            &profile_id,
+            session_id.as_deref(),
+        )?;
+    }
     println!("[SYSTEM] Code-Marshal session concluded.");
+    if !protected_changes.is_empty() {
+        let reverted = if revert_protected { " (reverted)" } else { "" };
+        anyhow::bail!(
+            "The run changed {} protected path(s){reverted}",
+            protected_changes.len()
+        );
+    }
     Ok(())
 }
 
-fn create_agent(agent_type: BaseCodingAgent) -> Result<CodingAgent> {
-    let agent_json = "{}";
-    match agent_type {
-        BaseCodingAgent::ClaudeCode => Ok(serde_json::from_str::<
-            executors::executors::claude::ClaudeCode,
-        >(agent_json)?
-        .into()),
-        BaseCodingAgent::CursorAgent => Ok(serde_json::from_str::<
-            executors::executors::cursor::CursorAgent,
-        >(agent_json)?
-        .into()),
-        BaseCodingAgent::Codex => {
-            Ok(serde_json::from_str::<executors::executors::codex::Codex>(agent_json)?.into())
+/// Drain the complete lines at the start of `pending`. Output is forwarded line by line so a
+/// secret (or a UTF-8 character) split across two reads is still seen whole by the redactor.
+fn take_complete_lines(pending: &mut Vec<u8>) -> Option<String> {
+    let end = pending.iter().rposition(|b| *b == b'\n')? + 1;
+    let lines: Vec<u8> = pending.drain(..end).collect();
+    Some(String::from_utf8_lossy(&lines).into_owned())
+}
+
+/// Find the protected paths the run changed according to git and, with `revert`, put them back
+/// as they were before the run
+fn check_protected_paths(
+    paths: &ProtectedPaths,
+    repo: &std::path::Path,
+    base: &str,
+    revert: bool,
+    json_output: bool,
+) -> Result<Vec<ProtectedPathViolation>> {
+    let git = GitService::new();
+    let base_commit: Commit = base.parse()?;
+    let diffs = git.get_diffs(
+        DiffTarget::Worktree {
+            worktree_path: repo,
+            base_commit: &base_commit,
+        },
+        None,
+    )?;
+    let mut violations: Vec<ProtectedPathViolation> = Vec::new();
+    // Both sides of a rename are reverted, so the file is back at its old path only
+    let mut to_revert: Vec<String> = Vec::new();
+    for diff in &diffs {
+        let sides: Vec<&String> = [&diff.old_path, &diff.new_path]
+            .into_iter()
+            .flatten()
+            .collect();
+        let mut violating = false;
+        for path in &sides {
+            if let Some(pattern) = paths.matching(path) {
+                violating = true;
+                if !violations.iter().any(|v| &v.path == *path) {
+                    violations.push(ProtectedPathViolation {
+                        path: path.to_string(),
+                        pattern: pattern.to_string(),
+                        outcome: ProtectedPathOutcome::Changed,
+                        tool_name: None,
+                    });
+                }
+            }
+        }
+        if violating {
+            to_revert.extend(sides.into_iter().cloned());
+        }
+    }
+    to_revert.sort();
+    to_revert.dedup();
+
+    if revert && !to_revert.is_empty() {
+        git.restore_paths_from_commit(repo, base, &to_revert)
+            .context("Failed to revert protected paths")?;
+        for violation in &mut violations {
+            violation.outcome = ProtectedPathOutcome::Reverted;
         }
-        BaseCodingAgent::Opencode => Ok(serde_json::from_str::<
-            executors::executors::opencode::Opencode,
-        >(agent_json)?
-        .into()),
-        BaseCodingAgent::Gemini => {
-            Ok(serde_json::from_str::<executors::executors::gemini::Gemini>(agent_json)?.into())
+    }
+    for violation in &violations {
+        print_protected_violation(violation, json_output);
+    }
+    Ok(violations)
+}
+
+fn print_protected_violation(violation: &ProtectedPathViolation, json_output: bool) {
+    if json_output {
+        let json = serde_json::to_string(violation).unwrap_or_else(|_| format!("{violation:?}"));
+        println!("[PROTECTED] {json}");
+        return;
+    }
+    let what = match (violation.outcome, &violation.tool_name) {
+        (ProtectedPathOutcome::Blocked, Some(tool)) => format!("Blocked {tool}"),
+        (ProtectedPathOutcome::Blocked, None) => "Blocked".to_string(),
+        (ProtectedPathOutcome::Changed, _) => "Changed".to_string(),
+        (ProtectedPathOutcome::Reverted, _) => "Changed and reverted".to_string(),
+    };
+    println!(
+        "[PROTECTED] {what}: {} (protected by '{}')",
+        violation.path, violation.pattern
+    );
+}
+
+/// Print the files the run changed according to git, reconciled with the edits the agent
+/// reported
+fn report_changes<'a>(
+    repo: &std::path::Path,
+    base: &str,
+    entries: impl IntoIterator<Item = &'a NormalizedEntry>,
+    current_dir: &std::path::Path,
+    json_output: bool,
+) -> Result<()> {
+    let git = GitService::new();
+    let base: Commit = base.parse()?;
+    let diffs = git.get_diffs(
+        DiffTarget::Worktree {
+            worktree_path: repo,
+            base_commit: &base,
+        },
+        None,
+    )?;
+    let counts = git.get_worktree_change_counts(repo)?;
+    let report = ChangesReport::new(diffs, entries, repo, current_dir, counts);
+    if json_output {
+        println!("[CHANGES] {}", serde_json::to_string(&report)?);
+        return Ok(());
+    }
+
+    println!(
+        "[CHANGES] {} files changed, +{} -{} (worktree: {} uncommitted tracked, {} untracked)",
+        report.files.len(),
+        report.additions,
+        report.deletions,
+        report.uncommitted_tracked,
+        report.untracked
+    );
+    for file in &report.files {
+        let renamed = file
+            .old_path
+            .as_deref()
+            .map(|old| format!(" (from {old})"))
+            .unwrap_or_default();
+        let note = match (file.reported, file.content_omitted) {
+            (false, _) => "  [not reported by the agent; likely a shell command]",
+            (true, true) => "  [content omitted]",
+            (true, false) => "",
+        };
+        println!(
+            "[CHANGES]   {:?} {}{renamed} +{} -{}{note}",
+            file.change, file.path, file.additions, file.deletions
+        );
+    }
+    for path in &report.reported_not_changed {
+        println!("[CHANGES]   reported edit did not land (no net change): {path}");
+    }
+    Ok(())
+}
+
+/// Print (and optionally save) the plan of a plan-only run, check that the worktree is
+/// unchanged since `run_base` and show the follow-up command that executes the plan in the same
+/// session
+fn report_plan<'a>(
+    entries: impl IntoIterator<Item = &'a NormalizedEntry>,
+    output: Option<&std::path::Path>,
+    json_output: bool,
+    run_base: Option<&(std::path::PathBuf, String)>,
+    profile_id: &ExecutorProfileId,
+    session_id: Option<&str>,
+) -> Result<()> {
+    let Some(plan) = PlanArtifact::from_entries(entries) else {
+        anyhow::bail!("The agent did not produce a plan");
+    };
+    if json_output {
+        println!("[PLAN] {}", serde_json::to_string(&plan)?);
+    } else {
+        println!("[PLAN]\n{}", plan.markdown);
+        for (n, step) in plan.steps.iter().enumerate() {
+            println!("[PLAN][step {}] {step}", n + 1);
         }
-        BaseCodingAgent::QwenCode => {
-            Ok(serde_json::from_str::<executors::executors::qwen::QwenCode>(agent_json)?.into())
+        for todo in &plan.todos {
+            println!("[PLAN][todo:{}] {}", todo.status, todo.content);
         }
-        BaseCodingAgent::Amp => {
-            Ok(serde_json::from_str::<executors::executors::amp::Amp>(agent_json)?.into())
+    }
+    if let Some(path) = output {
+        std::fs::write(path, serde_json::to_string_pretty(&plan)?)
+            .with_context(|| format!("Failed to write plan to {}", path.display()))?;
+        println!("[SYSTEM] Plan written to {}", path.display());
+    }
+
+    if let Some((repo, base)) = run_base {
+        let base_commit: Commit = base.parse()?;
+        let diffs = GitService::new().get_diffs(
+            DiffTarget::Worktree {
+                worktree_path: repo,
+                base_commit: &base_commit,
+            },
+            None,
+        )?;
+        if !diffs.is_empty() {
+            let paths: Vec<&str> = diffs
+                .iter()
+                .filter_map(|diff| diff.new_path.as_deref().or(diff.old_path.as_deref()))
+                .collect();
+            anyhow::bail!(
+                "Plan-only run changed {} file(s): {}; inspect `git status`",
+                paths.len(),
+                paths.join(", ")
+            );
         }
-        BaseCodingAgent::Copilot => {
-            Ok(serde_json::from_str::<executors::executors::copilot::Copilot>(agent_json)?.into())
+        println!("[SYSTEM] Verified: no files changed during the run");
+    } else {
+        println!("[SYSTEM] Not inside a git worktree; cannot verify that the plan-only run left the files unchanged");
+    }
+
+    if let Some(id) = session_id {
+        println!(
+            "[SYSTEM] Execute the plan: code-marshal -a {profile_id} --follow-up {id} \"Execute the plan.\""
+        );
+    }
+    Ok(())
+}
+
+/// Build the prompt from `--prompt-file` (`-` for stdin) or the joined positional arguments
+/// (a lone `-` also reads stdin). Mixing the two is an error.
+fn read_prompt(positionals: &[String], prompt_file: Option<&str>) -> Result<String> {
+    let from_stdin = || -> Result<String> {
+        std::io::read_to_string(std::io::stdin()).context("Failed to read prompt from stdin")
+    };
+    match (prompt_file, positionals) {
+        (Some(_), [_, ..]) => {
+            anyhow::bail!("Ambiguous prompt: both --prompt-file and a positional prompt were given")
         }
-        BaseCodingAgent::Droid => {
-            Ok(serde_json::from_str::<executors::executors::droid::Droid>(agent_json)?.into())
+        (Some("-"), []) => from_stdin(),
+        (Some(path), []) => std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read prompt file {path}")),
+        (None, [only]) if only == "-" => from_stdin(),
+        (None, words) if words.iter().any(|w| w == "-") => {
+            anyhow::bail!("Ambiguous prompt: `-` (stdin) cannot be combined with other words")
         }
+        (None, words) => Ok(words.join(" ")),
     }
 }
 
-fn get_installed_agent_types() -> Result<Vec<BaseCodingAgent>> {
-    let all_types = vec![
-        BaseCodingAgent::ClaudeCode,
-        BaseCodingAgent::CursorAgent,
-        BaseCodingAgent::Codex,
-        BaseCodingAgent::Opencode,
-        BaseCodingAgent::Gemini,
-        BaseCodingAgent::QwenCode,
-        BaseCodingAgent::Amp,
-        BaseCodingAgent::Copilot,
-        BaseCodingAgent::Droid,
-    ];
+/// Resolve the executor config for a profile (built-in defaults merged with user profiles.json)
+fn create_agent(profile_id: &ExecutorProfileId) -> Result<CodingAgent> {
+    ExecutorConfigs::get_cached()
+        .get_coding_agent(profile_id)
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Unknown profile: {profile_id}. Run `code-marshal profiles list` to see available profiles."
+            )
+        })
+}
 
+const AGENT_TYPES: &[BaseCodingAgent] = &[
+    BaseCodingAgent::ClaudeCode,
+    BaseCodingAgent::CursorAgent,
+    BaseCodingAgent::Codex,
+    BaseCodingAgent::Opencode,
+    BaseCodingAgent::Gemini,
+    BaseCodingAgent::QwenCode,
+    BaseCodingAgent::Amp,
+    BaseCodingAgent::Copilot,
+    BaseCodingAgent::Droid,
+    BaseCodingAgent::Aider,
+    BaseCodingAgent::Goose,
+    BaseCodingAgent::Acp,
+    BaseCodingAgent::Custom,
+    BaseCodingAgent::Plugin,
+];
+
+fn get_installed_agent_types() -> Result<Vec<BaseCodingAgent>> {
     let mut installed = Vec::new();
-    for at in all_types {
-        if let Ok(agent) = create_agent(at) {
+    for &at in AGENT_TYPES {
+        if let Ok(agent) = create_agent(&ExecutorProfileId::new(at)) {
             if agent.get_availability_info().is_available() {
                 installed.push(at);
             }
@@ -370,19 +1194,7 @@ async fn check_installed_agents() -> Result<()> {
     println!("[SYSTEM] Checking for installed agent binaries...");
     let installed = get_installed_agent_types()?;
 
-    let all_types = vec![
-        BaseCodingAgent::ClaudeCode,
-        BaseCodingAgent::CursorAgent,
-        BaseCodingAgent::Codex,
-        BaseCodingAgent::Opencode,
-        BaseCodingAgent::Gemini,
-        BaseCodingAgent::QwenCode,
-        BaseCodingAgent::Amp,
-        BaseCodingAgent::Copilot,
-        BaseCodingAgent::Droid,
-    ];
-
-    for at in all_types {
+    for &at in AGENT_TYPES {
         let status = if installed.contains(&at) {
             "INSTALLED"
         } else {
@@ -404,26 +1216,155 @@ fn list_agents() {
     println!("  - AMP          (Bloop)");
     println!("  - COPILOT      (GitHub)");
     println!("  - DROID        (Droid)");
+    println!("  - AIDER        (Aider)");
+    println!("  - GOOSE        (Block)");
+    println!("  - ACP          (any Agent Client Protocol agent, configured in profiles.json)");
+    println!("  - CUSTOM       (any plain-text CLI agent, configured in profiles.json)");
+    println!(
+        "  - PLUGIN       (executor plugins in {})",
+        workspace_utils::assets::plugins_dir().display()
+    );
+    for (name, _) in executors::executors::plugin::discover_plugins() {
+        println!("      PLUGIN:{name}");
+    }
+    print_capability_matrix();
+}
+
+/// One row per agent (its DEFAULT profile) and discovered plugin, one column per capability
+fn print_capability_matrix() {
+    let mut rows: Vec<(String, Option<CodingAgent>)> = AGENT_TYPES
+        .iter()
+        .filter(|at| **at != BaseCodingAgent::Plugin)
+        .map(|&at| {
+            (
+                at.to_string(),
+                create_agent(&ExecutorProfileId::new(at)).ok(),
+            )
+        })
+        .collect();
+    for (name, _) in executors::executors::plugin::discover_plugins() {
+        let agent = executors::executors::plugin::discovered_agent(Some(&name));
+        rows.push((format!("PLUGIN:{name}"), agent));
+    }
+    let width = rows.iter().map(|(name, _)| name.len()).max().unwrap_or(0) + 2;
+
+    let capabilities: Vec<BaseAgentCapability> = BaseAgentCapability::all().collect();
+    println!();
+    println!("[SYSTEM] Capabilities (x = supported, DEFAULT profiles):");
+    let header: String = (1..=capabilities.len())
+        .map(|n| format!("{n:>3}"))
+        .collect();
+    println!("  {:<width$}{header}", "");
+    for (name, agent) in rows {
+        let cells: String = match agent {
+            Some(agent) => {
+                let supported = agent.capabilities();
+                capabilities
+                    .iter()
+                    .map(|c| if supported.contains(c) { "  x" } else { "  ." })
+                    .collect()
+            }
+            None => "  (no DEFAULT profile configured)".to_string(),
+        };
+        println!("  {name:<width$}{cells}");
+    }
+    for (n, capability) in capabilities.iter().enumerate() {
+        let name = capability.to_string();
+        println!("  {:>3}  {name:<15} {}", n + 1, capability.description());
+    }
 }
 
 fn print_usage() {
     // Use a single raw string to avoid any weird escaping / parsing issues across toolchains.
     print!(
-        r#"Usage: code-marshal [OPTIONS] <PROMPT>
+        r#"Usage: code-marshal [OPTIONS] <PROMPT>...
+       code-marshal [OPTIONS] --prompt-file <PATH|->
+       code-marshal chat [OPTIONS] [PROMPT]...
+
+       code-marshal profiles <COMMAND>
+       code-marshal templates <COMMAND>
+       code-marshal rewind <COMMAND>
+       code-marshal checkpoints <COMMAND>
+       code-marshal models [--agent <AGENT>] [--json]
+       code-marshal setup <AGENT> [--dry-run]
+       code-marshal doctor [--agent <AGENT>] [--json]
+       code-marshal tools <COMMAND>
 
 Modes:
   oneshot (default): run a single prompt in a new agent session
   follow-up        : resume/fork an existing session via --follow-up <SESSION_ID>
+  chat             : live session; the agent stays running and each stdin line is a new turn
+                     (/exit or EOF ends it; /interrupt or /steer <TEXT> redirects the running
+                     turn; /mode <MODE> switches between plan, default, accept_edits and bypass;
+                     /execute [TEXT] runs a reviewed plan). Same as --interactive
+  profiles         : list/show/set/unset/validate/export/import executor profiles
+  templates        : list/show/render named prompt templates
+  rewind           : list/restore the worktree snapshots recorded at each message id
+  checkpoints      : list/restore the checkpoints of --checkpoint runs
+  models           : list the models each agent can run, with context windows where known
+  setup            : install an agent's CLI and log in (CODEX, CURSOR_AGENT)
+  doctor           : check each agent's CLI starts, is logged in and has a valid MCP config
+  tools            : install/upgrade/compare the cached, pinned npm agent CLIs runs use
+
+With --template or --var, prompts are templates: {{{{name}}}} (from --var), {{{{env.NAME}}}} (USER,
+HOME and the like, or NAME given with --var NAME), {{{{git.branch}}}}, {{{{git.base_commit}}}},
+{{{{@path/to/file}}}} (workspace files, size limited); \{{{{ is a literal {{{{ and unknown
+placeholders are kept as written.
 
 Options:
   -h, --help                  Show this help
-  -a, --agent <AGENT>         Specify the agent to use
-                              (Defaults to the first installed agent found)
+  -a, --agent <AGENT>         Specify the agent to use, optionally with a profile variant
+                              (e.g. CODEX or CODEX:HIGH; defaults to the first installed agent found)
+      --variant <VARIANT>     Profile variant to use for the agent (see `code-marshal profiles list`)
+  -m, --model <MODEL>         Override the profile's model; checked against `code-marshal models`
+                              before the agent starts (unknown models the agent reports are errors)
   -f, --follow-up <SESSION>   Run as follow-up using an existing session id
+      --rewind-to <MESSAGE_ID>
+                              With --follow-up, resume from an earlier message and restore the
+                              worktree snapshot recorded there, if any (`code-marshal rewind list`)
+      --snapshots             Snapshot the worktree (including untracked files) at each message id
+                              so --rewind-to can restore the files; best-effort, a snapshot may
+                              include edits made just after its message. `code-marshal rewind
+                              prune` deletes them
+      --changes-report        After the run, list the files it changed according to git (+/- lines),
+                              flagging changes the agent did not report (e.g. from shell commands)
+                              and reported edits that did not land
+      --protect <PATTERN>     Protect paths matching a gitignore-like pattern from agent changes
+                              (repeatable; added to .code-marshal/protected-paths). Approvals
+                              are turned on so tool calls touching them are denied (agents that
+                              cannot ask are only checked after the run), and the run fails if
+                              they changed anyway
+      --revert-protected      Also revert changes to protected paths after the run
+      --redact <REGEX>        Also mask matches of REGEX in all output (repeatable). Values of
+                              secret-looking env vars (process, profile and MCP server config),
+                              API keys, GitHub tokens and JWTs are always masked
+      --no-redact             Don't mask secrets in the output
+      --diffs                 Stream live worktree diffs (add/replace/remove per path, from git)
+                              while the agent runs
+      --checkpoint            Record worktree checkpoints at the start, after each turn and at the
+                              end of the run (see `code-marshal checkpoints list`)
+      --checkpoint-every <N>  Like --checkpoint, and also after every N completed file edits
+  -t, --template <NAME>       Use a named prompt template (the PROMPT argument becomes {{{{prompt}}}})
+      --var <KEY=VALUE|NAME>  Set a template variable, or expose env var NAME as {{{{env.NAME}}}}
+                              (repeatable)
+      --prompt-file <PATH>    Read the prompt from a file (`-` for stdin; a lone `-` PROMPT also works)
+      --attach <PATH>         Attach a file (repeatable). Images (png/jpg/gif/webp) are sent natively
+                              to agents that support them; other files are inlined into the prompt
+  -i, --interactive           Keep the agent running and read further turns from stdin
+      --listen <ADDR>         Live session that also accepts turns over a JSON-lines TCP API, e.g.
+                              {{"type":"send_message","message":"..."}}, {{"type":"interrupt"}}
+                              (optional "instruction"), {{"type":"set_mode","mode":"..."}} or
+                              {{"type":"end"}}. ADDR must be a loopback address; connections
+                              first send {{"type":"auth","token":"..."}} with the printed token
+      --plan-only             Run the agent in a non-mutating configuration and report its plan
+                              (markdown, steps and todos), verifying that no files changed
+                              during the run. Supported by CLAUDE_CODE, CODEX, OPENCODE and DROID
+      --plan-output <PATH>    With --plan-only, also write the plan as JSON to PATH
       --json                  Emit machine-readable LogMsg JSON events instead of pretty output
       --raw                   Also emit raw child stdout/stderr events (default: normalized-only)
   -l, --list-agents           List all supported agent types
-  -c, --check-installed       Check which agents are installed on the system
+  -c, --check-installed       Check which agents are installed on the system (files only;
+                              `doctor` starts them)
 "#
     );
 }
@@ -442,8 +1383,7 @@ enum PatchValue {
     NormalizedEntry(Box<executors::logs::NormalizedEntry>),
     Stdout(String),
     Stderr(String),
-    // We don't need diff payloads in the CLI pretty-printer; keep the variant for forward-compat.
-    Diff(()),
+    Diff(Box<Diff>),
 }
 
 #[derive(serde::Deserialize)]
@@ -524,8 +1464,33 @@ fn pretty_print_logmsg(msg: &LogMsg) {
                     Some(PatchValue::Stderr(s)) => {
                         println!("[EVENT][stderr][{kind}] {}", s.trim_end());
                     }
-                    Some(PatchValue::Diff(_)) => {
-                        println!("[EVENT][diff][{kind}] path={} ", e.path);
+                    Some(PatchValue::Diff(diff)) => {
+                        let path = diff.new_path.as_deref().or(diff.old_path.as_deref());
+                        let (additions, deletions) = match (diff.additions, diff.deletions) {
+                            (Some(a), Some(d)) => (a, d),
+                            _ => compute_line_change_counts(
+                                diff.old_content.as_deref().unwrap_or_default(),
+                                diff.new_content.as_deref().unwrap_or_default(),
+                            ),
+                        };
+                        let omitted = if diff.content_omitted {
+                            " (content omitted)"
+                        } else {
+                            ""
+                        };
+                        println!(
+                            "[EVENT][diff][{kind}] {} {:?} +{additions} -{deletions}{omitted}",
+                            path.unwrap_or_default(),
+                            diff.change
+                        );
+                    }
+                    // Live worktree diffs are keyed by path under /diffs/
+                    None if e.path.starts_with("/diffs/") => {
+                        let path = e.path.trim_start_matches("/diffs/");
+                        println!(
+                            "[EVENT][diff][{kind}] {}",
+                            path.replace("~1", "/").replace("~0", "~")
+                        );
                     }
                     None => {
                         println!("[EVENT][patch][{kind}] path={} (no value)", e.path);
@@ -0,0 +1,192 @@
+//! `code-marshal models [--agent X]`: the models each agent can run, queried from the agent
+//! where it can list them (OpenCode, Codex, Cursor) and from built-in lists otherwise.
+
+use std::{path::Path, str::FromStr};
+
+use anyhow::Result;
+use executors::{
+    executors::{
+        BaseAgentCapability, BaseCodingAgent, CodingAgent, ExecutorError,
+        StandardCodingAgentExecutor,
+    },
+    models::{agent_model, ModelCatalog, ModelSource},
+    profile::ExecutorProfileId,
+};
+
+use crate::{create_agent, AGENT_TYPES};
+
+pub async fn run(args: &[String]) -> Result<()> {
+    let mut profile: Option<String> = None;
+    let mut json_output = false;
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--agent" | "-a" => {
+                profile = Some(
+                    args.get(i + 1)
+                        .cloned()
+                        .ok_or_else(|| anyhow::anyhow!("Missing value for --agent"))?,
+                );
+                i += 2;
+            }
+            "--json" => {
+                json_output = true;
+                i += 1;
+            }
+            "help" | "--help" | "-h" => {
+                print_models_usage();
+                return Ok(());
+            }
+            other => anyhow::bail!("Unknown models option: {other}"),
+        }
+    }
+
+    let current_dir = std::env::current_dir()?;
+    let profile_ids = match profile {
+        Some(raw) => vec![ExecutorProfileId::from_str(&raw)
+            .map_err(|_| anyhow::anyhow!("Unknown agent type: {raw}"))?],
+        None => AGENT_TYPES
+            .iter()
+            .map(|&at| ExecutorProfileId::new(at))
+            .filter(|id| {
+                create_agent(id).is_ok_and(|agent| {
+                    agent
+                        .capabilities()
+                        .contains(&BaseAgentCapability::ModelListing)
+                })
+            })
+            .collect(),
+    };
+    let single = profile_ids.len() == 1;
+
+    let mut entries = Vec::new();
+    for profile_id in profile_ids {
+        let agent = create_agent(&profile_id)?;
+        let result = match discover(&agent, &current_dir).await {
+            Err(err) if single => anyhow::bail!("Failed to list {profile_id} models: {err}"),
+            result => result,
+        };
+        entries.push((profile_id, agent_model(&agent), result));
+    }
+
+    if json_output {
+        let entries: Vec<serde_json::Value> = entries
+            .iter()
+            .map(|(profile_id, configured, result)| match result {
+                Ok(catalog) => serde_json::json!({
+                    "profile": profile_id.to_string(),
+                    "configured_model": configured,
+                    "source": catalog.source,
+                    "models": catalog.models,
+                }),
+                Err(err) => serde_json::json!({
+                    "profile": profile_id.to_string(),
+                    "error": err.to_string(),
+                }),
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+
+    for (profile_id, configured, result) in entries {
+        match result {
+            Ok(catalog) => print_catalog(&profile_id, configured.as_deref(), &catalog),
+            Err(err) => println!("[SYSTEM] {profile_id}: could not list models: {err}"),
+        }
+    }
+    Ok(())
+}
+
+async fn discover(agent: &CodingAgent, current_dir: &Path) -> Result<ModelCatalog, ExecutorError> {
+    if !agent
+        .capabilities()
+        .contains(&BaseAgentCapability::ModelListing)
+    {
+        return Err(ExecutorError::ModelListingNotSupported);
+    }
+    agent.discover_models(current_dir).await
+}
+
+fn print_catalog(profile_id: &ExecutorProfileId, configured: Option<&str>, catalog: &ModelCatalog) {
+    let source = match catalog.source {
+        ModelSource::Discovered => "reported by the agent",
+        ModelSource::Known => "built-in list; other models may work",
+    };
+    println!("[SYSTEM] {profile_id} models ({source}):");
+    if catalog.models.is_empty() {
+        println!("  (none)");
+    }
+    let width = catalog
+        .models
+        .iter()
+        .map(|model| model.id.len())
+        .max()
+        .unwrap_or(0);
+    for model in &catalog.models {
+        let context = model
+            .context_window
+            .map(|tokens| format!("{}k ctx", tokens / 1000))
+            .unwrap_or_default();
+        let mut notes = Vec::new();
+        if model.is_default {
+            notes.push("default");
+        }
+        if configured == Some(model.id.as_str()) {
+            notes.push("profile");
+        }
+        let notes = if notes.is_empty() {
+            String::new()
+        } else {
+            format!(" ({})", notes.join(", "))
+        };
+        println!(
+            "  - {:<width$}  {:<10} {}{notes}",
+            model.id,
+            context,
+            model.display_name.as_deref().unwrap_or("")
+        );
+    }
+    if let Some(configured) = configured.filter(|model| catalog.find(model).is_none()) {
+        println!("  ! profile model `{configured}` is not in this list");
+    }
+}
+
+/// Check `--model` before spawning: models an agent reports are authoritative, built-in lists
+/// only produce a warning
+pub async fn check_model(
+    agent: &CodingAgent,
+    agent_type: BaseCodingAgent,
+    model: &str,
+    current_dir: &Path,
+) -> Result<()> {
+    match discover(agent, current_dir).await {
+        Ok(catalog) => {
+            catalog.validate(agent_type, model)?;
+            if catalog.find(model).is_none() {
+                println!(
+                    "[SYSTEM] `{model}` is not a known {agent_type} model; passing it through"
+                );
+            }
+        }
+        Err(ExecutorError::ModelListingNotSupported) => {}
+        Err(err) => {
+            println!("[SYSTEM] Could not list {agent_type} models to check `{model}`: {err}")
+        }
+    }
+    Ok(())
+}
+
+fn print_models_usage() {
+    print!(
+        r#"Usage: code-marshal models [--agent <AGENT[:VARIANT]>] [--json]
+
+Lists the models each agent can run, with context windows where known. OpenCode and Codex are
+asked directly (OpenCode's configured providers, the Codex app server), Cursor via
+`cursor-agent models`; Claude Code and Gemini use a built-in list. Without --agent, every agent
+with model listing is shown.
+
+`--model <MODEL>` on a run is checked against this list before the agent starts.
+"#
+    );
+}
@@ -0,0 +1,324 @@
+//! `code-marshal profiles ...`: inspect and edit executor profiles (profiles.json).
+
+use std::{path::PathBuf, str::FromStr};
+
+use anyhow::{Context, Result};
+use executors::profile::{
+    self, ExecutorConfigs, ExecutorProfileId, ProfileLayers, PROJECT_SAFE_FIELDS,
+};
+
+pub fn run(args: &[String]) -> Result<()> {
+    let Some(sub) = args.first() else {
+        print_profiles_usage();
+        return Ok(());
+    };
+    let rest = &args[1..];
+
+    match sub.as_str() {
+        "list" => list(has_flag(rest, "--json")),
+        "show" => show(required(rest, 0, "<PROFILE>")?),
+        "explain" => explain(required(rest, 0, "<PROFILE>")?, has_flag(rest, "--json")),
+        "set" => set(
+            required(rest, 0, "<PROFILE>")?,
+            required(rest, 1, "<FIELD>")?,
+            required(rest, 2, "<VALUE>")?,
+        ),
+        "unset" => unset(
+            required(rest, 0, "<PROFILE>")?,
+            rest.get(1).map(String::as_str),
+        ),
+        "validate" => validate(rest.first().map(PathBuf::from)),
+        "export" => export(
+            has_flag(rest, "--all"),
+            positional(rest).first().map(PathBuf::from),
+        ),
+        "import" => import(required(rest, 0, "<PATH>")?, has_flag(rest, "--replace")),
+        "trust" => trust(positional(rest).first().map(PathBuf::from), true),
+        "untrust" => trust(positional(rest).first().map(PathBuf::from), false),
+        "schema" => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&ExecutorConfigs::json_schema())?
+            );
+            Ok(())
+        }
+        "help" | "--help" | "-h" => {
+            print_profiles_usage();
+            Ok(())
+        }
+        other => anyhow::bail!("Unknown profiles command: {other}"),
+    }
+}
+
+fn has_flag(args: &[String], flag: &str) -> bool {
+    args.iter().any(|a| a == flag)
+}
+
+fn positional(args: &[String]) -> Vec<&String> {
+    args.iter().filter(|a| !a.starts_with("--")).collect()
+}
+
+fn required<'a>(args: &'a [String], index: usize, name: &str) -> Result<&'a str> {
+    positional(args)
+        .get(index)
+        .map(|s| s.as_str())
+        .ok_or_else(|| anyhow::anyhow!("Missing {name}"))
+}
+
+fn parse_id(raw: &str) -> Result<ExecutorProfileId> {
+    Ok(ExecutorProfileId::from_str(raw)?)
+}
+
+/// Values are parsed as JSON when possible (`true`, `42`, `["a"]`), otherwise taken as a string.
+fn parse_value(raw: &str) -> serde_json::Value {
+    serde_json::from_str(raw).unwrap_or_else(|_| serde_json::Value::String(raw.to_string()))
+}
+
+fn sorted_ids(configs: &ExecutorConfigs) -> Vec<ExecutorProfileId> {
+    let mut ids = Vec::new();
+    for (executor, config) in &configs.executors {
+        for variant in config.configurations.keys() {
+            ids.push(if variant == "DEFAULT" {
+                ExecutorProfileId::new(*executor)
+            } else {
+                ExecutorProfileId::with_variant(*executor, variant.clone())
+            });
+        }
+    }
+    ids.sort_by_key(|id| (id.executor.to_string(), id.variant.clone()));
+    ids
+}
+
+fn list(json_output: bool) -> Result<()> {
+    let configs = ExecutorConfigs::get_cached();
+    // Effective configs include the project layer; only user edits are marked as overrides
+    let overrides = ExecutorConfigs::load_user().overrides()?;
+    let ids = sorted_ids(&configs);
+
+    if json_output {
+        let entries: Vec<serde_json::Value> = ids
+            .iter()
+            .map(|id| {
+                serde_json::json!({
+                    "profile": id.to_string(),
+                    "overridden": is_overridden(&overrides, id),
+                    "config": configs.get_coding_agent(id),
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+
+    println!("[SYSTEM] Executor profiles:");
+    for id in ids {
+        let marker = if is_overridden(&overrides, &id) {
+            " (user)"
+        } else {
+            ""
+        };
+        println!("  - {id}{marker}");
+    }
+    Ok(())
+}
+
+fn is_overridden(overrides: &ExecutorConfigs, id: &ExecutorProfileId) -> bool {
+    overrides.executors.get(&id.executor).is_some_and(|c| {
+        c.configurations
+            .contains_key(id.variant.as_deref().unwrap_or("DEFAULT"))
+    })
+}
+
+fn show(raw_id: &str) -> Result<()> {
+    let id = parse_id(raw_id)?;
+    let agent = ExecutorConfigs::get_cached()
+        .get_coding_agent(&id)
+        .ok_or_else(|| anyhow::anyhow!("Unknown profile: {id}"))?;
+    println!("{}", serde_json::to_string_pretty(&agent)?);
+    Ok(())
+}
+
+fn explain(raw_id: &str, json_output: bool) -> Result<()> {
+    let id = parse_id(raw_id)?;
+    let layers = ProfileLayers::discover(&std::env::current_dir()?)?;
+    let sources = layers.explain(&id)?;
+
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&sources)?);
+        return Ok(());
+    }
+
+    println!("[SYSTEM] {id}");
+    print_layer_paths(&layers);
+    for source in sources {
+        println!("  {} = {}  ({})", source.field, source.value, source.layer);
+    }
+    Ok(())
+}
+
+// Edits apply to the user profiles.json; project-local files are edited by hand.
+fn set(raw_id: &str, field: &str, raw_value: &str) -> Result<()> {
+    let id = parse_id(raw_id)?;
+    let mut configs = load_user()?;
+    configs.set_field(&id, field, parse_value(raw_value))?;
+    save(&configs)?;
+    println!("[SYSTEM] Set {field} on {id}");
+    Ok(())
+}
+
+fn unset(raw_id: &str, field: Option<&str>) -> Result<()> {
+    let id = parse_id(raw_id)?;
+    let mut configs = load_user()?;
+    match field {
+        Some(field) => {
+            configs.unset_field(&id, field)?;
+            save(&configs)?;
+            println!("[SYSTEM] Unset {field} on {id}");
+        }
+        None => {
+            configs.reset_variant(&id)?;
+            save(&configs)?;
+            println!("[SYSTEM] Reset {id}");
+        }
+    }
+    Ok(())
+}
+
+fn print_layer_paths(layers: &ProfileLayers) {
+    println!("  user profiles   : {}", layers.user_path.display());
+    if let Some(path) = &layers.project_path {
+        let trust = if layers.project_trusted {
+            "trusted"
+        } else {
+            "untrusted: only safe fields apply"
+        };
+        println!("  project profiles: {} ({trust})", path.display());
+    }
+}
+
+/// Trust (or stop trusting) the project at `path`, by default the one whose
+/// `.code-marshal/profiles.json` is found from the working directory
+fn trust(path: Option<PathBuf>, trusted: bool) -> Result<()> {
+    let root = match path {
+        Some(path) => path,
+        None => {
+            let cwd = std::env::current_dir()?;
+            profile::find_project_profiles(&cwd)
+                .as_deref()
+                .and_then(profile::project_root)
+                .map(PathBuf::from)
+                .unwrap_or(cwd)
+        }
+    };
+    let changed = profile::set_project_trusted(&root, trusted)
+        .with_context(|| format!("Cannot update trust for {}", root.display()))?;
+    let state = match (trusted, changed) {
+        (true, true) => "now trusted",
+        (true, false) => "already trusted",
+        (false, true) => "no longer trusted",
+        (false, false) => "not trusted",
+    };
+    println!("[SYSTEM] {} is {state}", root.display());
+    Ok(())
+}
+
+fn validate(path: Option<PathBuf>) -> Result<()> {
+    let Some(path) = path else {
+        let layers = ProfileLayers::discover(&std::env::current_dir()?)?;
+        layers.merged()?;
+        println!("[SYSTEM] Profiles are valid");
+        print_layer_paths(&layers);
+        return Ok(());
+    };
+    if !path.exists() {
+        println!(
+            "[SYSTEM] No profiles file at {}, built-in defaults are in use",
+            path.display()
+        );
+        return Ok(());
+    }
+    ExecutorConfigs::load_file(&path)
+        .with_context(|| format!("Invalid profiles file {}", path.display()))?;
+    println!("[SYSTEM] {} is valid", path.display());
+    Ok(())
+}
+
+fn export(all: bool, path: Option<PathBuf>) -> Result<()> {
+    let exported = if all {
+        ExecutorConfigs::get_cached()
+    } else {
+        ExecutorConfigs::load_user().overrides()?
+    };
+    let content = serde_json::to_string_pretty(&exported)?;
+    match path {
+        Some(path) => {
+            std::fs::write(&path, content)?;
+            println!("[SYSTEM] Exported profiles to {}", path.display());
+        }
+        None => println!("{content}"),
+    }
+    Ok(())
+}
+
+fn import(path: &str, replace: bool) -> Result<()> {
+    let path = PathBuf::from(path);
+    let imported = ExecutorConfigs::load_file(&path)
+        .with_context(|| format!("Invalid profiles file {}", path.display()))?;
+    let configs = if replace {
+        imported
+    } else {
+        load_user()?.merged_with(imported.overrides()?)
+    };
+    save(&configs)?;
+    println!("[SYSTEM] Imported profiles from {}", path.display());
+    Ok(())
+}
+
+/// The user layer as it is on disk; an invalid profiles.json is an error rather than the
+/// defaults, so saving never replaces the user's overrides
+fn load_user() -> Result<ExecutorConfigs> {
+    ExecutorConfigs::try_load_user().with_context(|| {
+        format!(
+            "Invalid user profiles file {}; fix it before editing profiles",
+            workspace_utils::assets::profiles_path().display()
+        )
+    })
+}
+
+/// Write the user overrides once the result, with the project layer on top, is known to load
+fn save(configs: &ExecutorConfigs) -> Result<()> {
+    let mut layers = ProfileLayers::discover(&std::env::current_dir()?)?;
+    layers.user = Some(configs.overrides()?);
+    layers
+        .merged()
+        .context("The profiles would not load after this change; nothing was saved")?;
+    configs.save_overrides()?;
+    ExecutorConfigs::reload()?;
+    Ok(())
+}
+
+fn print_profiles_usage() {
+    print!(
+        r#"Usage: code-marshal profiles <COMMAND>
+
+Profiles are addressed as EXECUTOR[:VARIANT], e.g. CLAUDE_CODE or CODEX:HIGH.
+Effective profiles layer the built-in defaults, the user profiles.json and a project-local
+.code-marshal/profiles.json (found from the working directory upward). Edits go to the user file.
+Until a project is trusted, its layer can only set {safe}.
+
+Commands:
+  list [--json]                   List all profiles (user overrides are marked)
+  show <PROFILE>                  Print the effective config of a profile
+  explain <PROFILE> [--json]      Show which layer (default/user/project) set each field
+  set <PROFILE> <FIELD> <VALUE>   Set a field (dot paths like env.FOO; VALUE is JSON or a string)
+  unset <PROFILE> [FIELD]         Remove a field, or reset the whole variant
+  validate [PATH]                 Validate a profiles file (defaults to the user and project layers)
+  export [--all] [PATH]           Export user overrides (or the full merged config)
+  import <PATH> [--replace]       Import profiles, merging over (or replacing) user overrides
+  trust [PATH]                    Let the project's layer set every field (command, env, ...)
+  untrust [PATH]                  Restrict the project's layer to the safe fields again
+  schema                          Print the JSON Schema for profiles.json
+"#,
+        safe = PROJECT_SAFE_FIELDS.join(", ")
+    );
+}
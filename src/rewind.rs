@@ -0,0 +1,289 @@
+//! `code-marshal rewind ...`: worktree snapshots recorded at each message id of a run, so a
+//! follow-up that resumes from an earlier message (`--rewind-to`) also restores the files.
+
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use git::{GitCli, GitService};
+use tokio::{sync::mpsc, task::JoinHandle};
+
+/// Each snapshot commit is kept alive by `refs/code-marshal/rewind/<MESSAGE_ID>`
+const REF_PREFIX: &str = "refs/code-marshal/rewind/";
+/// Worktree state from before the last restore, for `rewind undo`
+const BACKUP_REF: &str = "refs/code-marshal/rewind-backup";
+
+/// A worktree snapshot recorded when the agent reported a message id. Snapshots are
+/// best-effort: the agent keeps working while the id reaches us, so one may already include
+/// edits made just after its message.
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub message_id: String,
+    pub session_id: String,
+    /// 1-based position among the session's snapshots
+    pub ordinal: usize,
+    pub commit: String,
+    pub created: String,
+}
+
+/// Top level of the git worktree containing `dir`, if any
+pub fn repo_root(dir: &Path) -> Option<PathBuf> {
+    let out = GitCli::new()
+        .git(dir, ["rev-parse", "--show-toplevel"])
+        .ok()?;
+    let root = out.trim();
+    (!root.is_empty()).then(|| PathBuf::from(root))
+}
+
+fn snapshot_subject(session_id: &str, ordinal: usize) -> String {
+    format!("Rewind snapshot {ordinal} of session {session_id}")
+}
+
+fn parse_subject(subject: &str) -> Option<(usize, String)> {
+    let (ordinal, session_id) = subject
+        .strip_prefix("Rewind snapshot ")?
+        .split_once(" of session ")?;
+    Some((ordinal.parse().ok()?, session_id.to_string()))
+}
+
+/// All recorded snapshots, ordered by session and then by message order
+pub fn list_snapshots(repo: &Path) -> Result<Vec<Snapshot>> {
+    let out = GitCli::new()
+        .git(
+            repo,
+            [
+                "for-each-ref",
+                "--format=%(refname)%09%(objectname)%09%(creatordate:iso)%09%(subject)",
+                REF_PREFIX,
+            ],
+        )
+        .context("Failed to list rewind snapshots")?;
+    let mut snapshots: Vec<Snapshot> = out
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(4, '\t');
+            let message_id = fields.next()?.strip_prefix(REF_PREFIX)?.to_string();
+            let commit = fields.next()?.to_string();
+            let created = fields.next()?.to_string();
+            let (ordinal, session_id) = parse_subject(fields.next()?)?;
+            Some(Snapshot {
+                message_id,
+                session_id,
+                ordinal,
+                commit,
+                created,
+            })
+        })
+        .collect();
+    snapshots.sort_by(|a, b| (&a.session_id, a.ordinal).cmp(&(&b.session_id, b.ordinal)));
+    Ok(snapshots)
+}
+
+/// Whether a snapshot was recorded at `message_id`
+pub fn has_snapshot(repo: &Path, message_id: &str) -> Result<bool> {
+    Ok(list_snapshots(repo)?
+        .iter()
+        .any(|s| s.message_id == message_id))
+}
+
+/// Delete the snapshot refs of `session` (all sessions when `None`), letting git collect the
+/// commits. Returns how many were deleted.
+pub fn prune(repo: &Path, session: Option<&str>) -> Result<usize> {
+    let snapshots: Vec<Snapshot> = list_snapshots(repo)?
+        .into_iter()
+        .filter(|s| session.is_none_or(|id| s.session_id == id))
+        .collect();
+    let git = GitCli::new();
+    for s in &snapshots {
+        git.git(
+            repo,
+            ["update-ref", "-d", &format!("{REF_PREFIX}{}", s.message_id)],
+        )
+        .with_context(|| format!("Failed to delete the snapshot of message {}", s.message_id))?;
+    }
+    Ok(snapshots.len())
+}
+
+/// Restore the files to the snapshot recorded at `message_id`. The current worktree is saved
+/// first so the restore can be undone.
+pub fn restore(repo: &Path, message_id: &str) -> Result<()> {
+    let snapshot = list_snapshots(repo)?
+        .into_iter()
+        .find(|s| s.message_id == message_id)
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "No worktree snapshot recorded for message {message_id}; see `code-marshal rewind list`"
+            )
+        })?;
+    restore_commit(
+        repo,
+        &snapshot.commit,
+        &format!("Worktree before rewinding to message {message_id}"),
+    )
+}
+
+/// Restore a snapshot commit, saving the current worktree for [`undo`] first. Shared with
+/// checkpoints, so `undo` reverts whichever restore came last.
+pub fn restore_commit(repo: &Path, commit: &str, backup_message: &str) -> Result<()> {
+    let git = GitService::new();
+    let backup = git.snapshot_worktree(repo, backup_message)?;
+    GitCli::new().update_ref(repo, BACKUP_REF, &backup)?;
+    git.restore_worktree_snapshot(repo, commit)
+        .with_context(|| format!("Failed to restore snapshot {commit}"))
+}
+
+/// Put the worktree back as it was before the last [`restore_commit`]
+pub fn undo(repo: &Path) -> Result<()> {
+    let backup = GitCli::new()
+        .git(repo, ["rev-parse", "--verify", "-q", BACKUP_REF])
+        .map_err(|_| anyhow::anyhow!("Nothing to undo"))?;
+    restore_commit(repo, backup.trim(), "Worktree before undoing a restore")
+}
+
+fn record(
+    repo: &Path,
+    counters: &mut HashMap<String, usize>,
+    session_id: &str,
+    message_id: &str,
+) -> Result<()> {
+    if !counters.contains_key(session_id) {
+        // Follow-ups of the same session continue its numbering
+        let existing = list_snapshots(repo)?
+            .iter()
+            .filter(|s| s.session_id == session_id)
+            .count();
+        counters.insert(session_id.to_string(), existing);
+    }
+    let ordinal = counters.get_mut(session_id).expect("inserted above");
+    *ordinal += 1;
+    let commit =
+        GitService::new().snapshot_worktree(repo, &snapshot_subject(session_id, *ordinal))?;
+    GitCli::new().update_ref(repo, &format!("{REF_PREFIX}{message_id}"), &commit)?;
+    Ok(())
+}
+
+/// Records a best-effort snapshot for every message id of a run, in order, off the streaming task
+pub struct SnapshotRecorder {
+    tx: mpsc::UnboundedSender<(String, String)>,
+    worker: JoinHandle<()>,
+}
+
+impl SnapshotRecorder {
+    pub fn start(repo: PathBuf) -> Self {
+        let (tx, mut rx) = mpsc::unbounded_channel::<(String, String)>();
+        let worker = tokio::spawn(async move {
+            let mut counters = HashMap::new();
+            while let Some((session_id, message_id)) = rx.recv().await {
+                let repo = repo.clone();
+                let result = tokio::task::spawn_blocking(move || {
+                    let result = record(&repo, &mut counters, &session_id, &message_id)
+                        .with_context(|| format!("Failed to snapshot message {message_id}"));
+                    (counters, result)
+                })
+                .await;
+                match result {
+                    Ok((returned, result)) => {
+                        counters = returned;
+                        if let Err(e) = result {
+                            tracing::warn!("{e:#}");
+                        }
+                    }
+                    Err(e) => {
+                        tracing::warn!("Snapshot task failed: {e}");
+                        break;
+                    }
+                }
+            }
+        });
+        Self { tx, worker }
+    }
+
+    pub fn record(&self, session_id: &str, message_id: &str) {
+        let _ = self
+            .tx
+            .send((session_id.to_string(), message_id.to_string()));
+    }
+
+    /// Wait for queued snapshots to be written
+    pub async fn finish(self) {
+        drop(self.tx);
+        let _ = self.worker.await;
+    }
+}
+
+pub fn run(args: &[String]) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    let repo = || repo_root(&cwd).ok_or_else(|| anyhow::anyhow!("Not inside a git worktree"));
+    match args.first().map(String::as_str) {
+        Some("list") => {
+            let session = args.get(1);
+            let snapshots: Vec<Snapshot> = list_snapshots(&repo()?)?
+                .into_iter()
+                .filter(|s| session.is_none_or(|id| &s.session_id == id))
+                .collect();
+            if snapshots.is_empty() {
+                println!("[SYSTEM] No rewind snapshots found");
+            }
+            let mut current_session: Option<&str> = None;
+            for s in &snapshots {
+                if current_session != Some(s.session_id.as_str()) {
+                    println!("Session {}:", s.session_id);
+                    current_session = Some(&s.session_id);
+                }
+                println!(
+                    "  {:>3}  {}  {}  {}",
+                    s.ordinal,
+                    s.message_id,
+                    &s.commit[..s.commit.len().min(12)],
+                    s.created
+                );
+            }
+            Ok(())
+        }
+        Some("restore") => {
+            let message_id = args
+                .get(1)
+                .ok_or_else(|| anyhow::anyhow!("Missing <MESSAGE_ID>"))?;
+            restore(&repo()?, message_id)?;
+            println!(
+                "[SYSTEM] Worktree restored to the best-effort snapshot of message {message_id}"
+            );
+            println!("[SYSTEM] Undo with `code-marshal rewind undo`");
+            Ok(())
+        }
+        Some("prune") => {
+            let session = args.get(1).map(String::as_str);
+            let pruned = prune(&repo()?, session)?;
+            println!("[SYSTEM] Deleted {pruned} rewind snapshot(s)");
+            Ok(())
+        }
+        Some("undo") => {
+            undo(&repo()?)?;
+            println!("[SYSTEM] Worktree restored to its state before the last restore");
+            Ok(())
+        }
+        _ => {
+            print!(
+                r#"Usage: code-marshal rewind <COMMAND>
+
+When an agent that supports message resets runs with --snapshots inside a git worktree, the
+worktree (including untracked files) is snapshotted at each message id
+(refs/code-marshal/rewind/<MESSAGE_ID>). Snapshots are best-effort: they are taken when the
+message id reaches code-marshal while the agent keeps working, so one may already include edits
+made just after its message. Resume from an earlier message with
+`code-marshal -a <AGENT> --follow-up <SESSION> --rewind-to <MESSAGE_ID> <PROMPT>` to roll back
+both the conversation and the files. Restores only change files; HEAD and commits are kept.
+
+Commands:
+  list [SESSION]           List snapshots, optionally for one session
+  restore <MESSAGE_ID>     Restore the files only, without resuming the conversation
+  prune [SESSION]          Delete the snapshots of a session (or all of them)
+  undo                     Restore the worktree as it was before the last restore
+"#
+            );
+            Ok(())
+        }
+    }
+}
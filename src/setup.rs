@@ -0,0 +1,206 @@
+//! `code-marshal setup <AGENT>`: install an agent's CLI and log in by running its setup action
+//! chain (tool install scripts), with the scripts' output on the terminal.
+
+use std::{path::Path, process::Stdio, str::FromStr};
+
+use anyhow::{Context, Result};
+use executors::{
+    actions::{script::ScriptRequest, ExecutorAction, ExecutorActionType},
+    env::{ExecutionEnv, RepoContext},
+    executors::{BaseCodingAgent, CodingAgent, ExecutorError, StandardCodingAgentExecutor},
+    profile::ExecutorProfileId,
+};
+
+use crate::create_agent;
+
+pub async fn run(args: &[String]) -> Result<()> {
+    let mut profile: Option<&str> = None;
+    let mut dry_run = false;
+    for arg in args {
+        match arg.as_str() {
+            "--dry-run" => dry_run = true,
+            "help" | "--help" | "-h" => {
+                print_setup_usage();
+                return Ok(());
+            }
+            other if other.starts_with('-') => anyhow::bail!("Unknown setup option: {other}"),
+            other => profile = Some(other),
+        }
+    }
+    let Some(raw) = profile else {
+        print_setup_usage();
+        return Ok(());
+    };
+
+    let profile_id = ExecutorProfileId::from_str(raw)
+        .map_err(|_| anyhow::anyhow!("Unknown agent type: {raw}"))?;
+    let agent = create_agent(&profile_id)?;
+    let agent_type = profile_id.executor;
+    let scripts = setup_scripts(&agent, agent_type).await?;
+
+    if dry_run {
+        for script in &scripts {
+            println!("{}", script.script);
+        }
+        return Ok(());
+    }
+
+    let current_dir = std::env::current_dir()?;
+    let mut env = ExecutionEnv::new(RepoContext::default(), false, String::new());
+    if let Some(cmd) = agent.cmd_overrides() {
+        env = env.with_profile(cmd);
+    }
+    for (step, script) in scripts.iter().enumerate() {
+        println!(
+            "[SYSTEM] Setup step {}/{}: {}",
+            step + 1,
+            scripts.len(),
+            script.script.replace('\n', "; ")
+        );
+        run_script(script, &current_dir, &env).await?;
+    }
+    println!("[SYSTEM] {agent_type} setup finished");
+    Ok(())
+}
+
+/// The scripts of `agent`'s setup action chain, in order
+async fn setup_scripts(
+    agent: &CodingAgent,
+    agent_type: BaseCodingAgent,
+) -> Result<Vec<ScriptRequest>> {
+    let action = match agent.get_setup_helper_action().await {
+        Err(ExecutorError::SetupHelperNotSupported) => {
+            anyhow::bail!(
+                "{agent_type} has no setup helper; install and log in to its CLI manually"
+            )
+        }
+        result => result?,
+    };
+    let mut scripts = Vec::new();
+    let mut next: Option<&ExecutorAction> = Some(&action);
+    while let Some(action) = next {
+        match action.typ() {
+            ExecutorActionType::ScriptRequest(script) => scripts.push(script.clone()),
+            other => anyhow::bail!("Unsupported setup action for {agent_type}: {other:?}"),
+        }
+        next = action.next_action();
+    }
+    Ok(scripts)
+}
+
+async fn run_script(script: &ScriptRequest, current_dir: &Path, env: &ExecutionEnv) -> Result<()> {
+    // Login flows print URLs and device codes and may prompt, so the script gets the terminal
+    let status = script
+        .command(current_dir, env)
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .status()
+        .await
+        .context("Failed to start setup script")?;
+    if !status.success() {
+        anyhow::bail!("Setup script failed ({status}): {}", script.script);
+    }
+    Ok(())
+}
+
+/// Printed when a run reports `SetupRequired`: the command that fixes it, and what it runs
+pub async fn print_setup_hint(agent: &CodingAgent, profile_id: &ExecutorProfileId) {
+    for line in setup_hint(agent, profile_id).await {
+        println!("{line}");
+    }
+}
+
+async fn setup_hint(agent: &CodingAgent, profile_id: &ExecutorProfileId) -> Vec<String> {
+    let agent_type = profile_id.executor;
+    match setup_scripts(agent, agent_type).await {
+        Ok(scripts) => {
+            let mut lines = vec![format!(
+                "[SYSTEM] {agent_type} needs setup. Run: code-marshal setup {profile_id}"
+            )];
+            lines.extend(scripts.iter().map(|script| {
+                format!(
+                    "[SYSTEM]   which runs: {}",
+                    script.script.replace('\n', "; ")
+                )
+            }));
+            lines
+        }
+        Err(_) => vec![format!(
+            "[SYSTEM] {agent_type} needs setup: install and log in to its CLI, then run again"
+        )],
+    }
+}
+
+fn print_setup_usage() {
+    print!(
+        r#"Usage: code-marshal setup <AGENT> [--dry-run]
+
+Installs the agent's CLI if needed and logs in, running the agent's setup scripts with their
+output on the terminal (login flows print a URL or device code to open in a browser).
+Supported by agents with the setup helper capability (CODEX, CURSOR_AGENT); see --list-agents.
+
+Options:
+  --dry-run   Print the setup scripts instead of running them
+"#
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use executors::executors::{codex::Codex, cursor::CursorAgent};
+
+    use super::*;
+
+    fn agent<T: serde::de::DeserializeOwned>(config: serde_json::Value) -> T {
+        serde_json::from_value(config).unwrap()
+    }
+
+    #[tokio::test]
+    async fn collects_the_setup_action_chain() {
+        let codex = CodingAgent::Codex(agent::<Codex>(
+            serde_json::json!({ "base_command_override": "codex-dev" }),
+        ));
+        let scripts = setup_scripts(&codex, BaseCodingAgent::Codex).await.unwrap();
+        assert_eq!(
+            scripts
+                .iter()
+                .map(|s| s.script.as_str())
+                .collect::<Vec<_>>(),
+            ["codex-dev login"]
+        );
+
+        // An overridden command counts as installed, so only the login step remains
+        let cursor = CodingAgent::CursorAgent(agent::<CursorAgent>(
+            serde_json::json!({ "base_command_override": "cursor-dev" }),
+        ));
+        let scripts = setup_scripts(&cursor, BaseCodingAgent::CursorAgent)
+            .await
+            .unwrap();
+        assert_eq!(scripts.len(), 1);
+        assert!(scripts[0].script.ends_with("\ncursor-dev login"));
+    }
+
+    #[tokio::test]
+    async fn setup_hint_names_the_command_and_its_scripts() {
+        let codex = CodingAgent::Codex(agent::<Codex>(
+            serde_json::json!({ "base_command_override": "codex-dev" }),
+        ));
+        let profile_id = ExecutorProfileId::new(BaseCodingAgent::Codex);
+        assert_eq!(
+            setup_hint(&codex, &profile_id).await,
+            [
+                "[SYSTEM] CODEX needs setup. Run: code-marshal setup CODEX",
+                "[SYSTEM]   which runs: codex-dev login",
+            ]
+        );
+
+        let plain = CodingAgent::Goose(agent(serde_json::json!({})));
+        let profile_id = ExecutorProfileId::new(BaseCodingAgent::Goose);
+        assert_eq!(
+            setup_hint(&plain, &profile_id).await,
+            ["[SYSTEM] GOOSE needs setup: install and log in to its CLI, then run again"]
+        );
+        assert!(setup_scripts(&plain, BaseCodingAgent::Goose).await.is_err());
+    }
+}
@@ -0,0 +1,75 @@
+//! `code-marshal templates ...`: named prompt templates stored alongside profiles.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use executors::template::{list_named_templates, load_named_template, parse_var, PromptContext};
+
+pub fn run(args: &[String]) -> Result<()> {
+    let cwd = std::env::current_dir()?;
+    match args.first().map(String::as_str) {
+        Some("list") => {
+            let templates = list_named_templates(&cwd);
+            if templates.is_empty() {
+                println!("[SYSTEM] No templates found");
+            }
+            for (name, path) in templates {
+                println!("  - {:<20} {}", name, path.display());
+            }
+            Ok(())
+        }
+        Some("show") => {
+            let name = args
+                .get(1)
+                .ok_or_else(|| anyhow::anyhow!("Missing <NAME>"))?;
+            print!("{}", load_named_template(name, &cwd)?);
+            Ok(())
+        }
+        Some("render") => {
+            let name = args
+                .get(1)
+                .ok_or_else(|| anyhow::anyhow!("Missing <NAME>"))?;
+            let mut vars = HashMap::new();
+            let mut env_names = Vec::new();
+            let mut rest = args[2..].iter();
+            while let Some(arg) = rest.next() {
+                match arg.as_str() {
+                    "--var" => {
+                        let raw = rest
+                            .next()
+                            .ok_or_else(|| anyhow::anyhow!("Missing value for --var"))?;
+                        match parse_var(raw)? {
+                            (key, Some(value)) => {
+                                vars.insert(key, value);
+                            }
+                            (name, None) => env_names.push(name),
+                        }
+                    }
+                    other => anyhow::bail!("Unknown argument: {other}"),
+                }
+            }
+            let ctx = PromptContext::new(cwd.clone())
+                .with_vars(vars)
+                .with_env(std::env::vars().collect(), &env_names)
+                .with_git_info();
+            println!("{}", ctx.render(&load_named_template(name, &cwd)?)?);
+            Ok(())
+        }
+        _ => {
+            print!(
+                r#"Usage: code-marshal templates <COMMAND>
+
+Templates are markdown files in .code-marshal/templates/<NAME>.md (project) or
+templates/<NAME>.md next to the user profiles.json.
+
+Commands:
+  list                            List available templates
+  show <NAME>                     Print a template
+  render <NAME> [--var K=V]...    Render a template without running an agent
+                                  (--var NAME exposes env var NAME as {{env.NAME}})
+"#
+            );
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,242 @@
+//! `code-marshal tools ...`: the managed tool cache, pinned installs of the npm-distributed
+//! agent CLIs that runs use instead of `npx -y` (see the executors crate's `tool_cache`).
+
+use std::str::FromStr;
+
+use anyhow::Result;
+use executors::{
+    executors::BaseCodingAgent,
+    tool_cache::{self, Compatibility, ManagedTool, VersionStep},
+};
+
+pub async fn run(args: &[String]) -> Result<()> {
+    let Some(sub) = args.first() else {
+        print_tools_usage();
+        return Ok(());
+    };
+    let rest = &args[1..];
+
+    match sub.as_str() {
+        "list" => list(has_flag(rest, "--json")),
+        "install" => install(&positional(rest)).await,
+        "upgrade" => {
+            let positional = positional(rest);
+            let agent = positional
+                .first()
+                .ok_or_else(|| anyhow::anyhow!("Missing <AGENT>"))?;
+            let version = positional.get(1).map_or("latest", |v| v.as_str());
+            upgrade(agent, version, has_flag(rest, "--force")).await
+        }
+        "compare" => {
+            let positional = positional(rest);
+            let agent = positional
+                .first()
+                .ok_or_else(|| anyhow::anyhow!("Missing <AGENT>"))?;
+            let version = positional.get(1).map_or("latest", |v| v.as_str());
+            compare(agent, version, has_flag(rest, "--json")).await
+        }
+        "remove" => {
+            let agent = positional(rest)
+                .first()
+                .copied()
+                .ok_or_else(|| anyhow::anyhow!("Missing <AGENT>"))?;
+            let tool = parse_tool(agent)?;
+            match tool_cache::remove(tool)? {
+                Some(version) => println!(
+                    "[SYSTEM] Removed {} {version}; {} runs use npx again",
+                    tool.package, tool.agent
+                ),
+                None => println!("[SYSTEM] {} is not installed", tool.package),
+            }
+            Ok(())
+        }
+        "help" | "--help" | "-h" => {
+            print_tools_usage();
+            Ok(())
+        }
+        other => anyhow::bail!("Unknown tools command: {other}"),
+    }
+}
+
+fn has_flag(args: &[String], flag: &str) -> bool {
+    args.iter().any(|a| a == flag)
+}
+
+fn positional(args: &[String]) -> Vec<&String> {
+    args.iter().filter(|a| !a.starts_with("--")).collect()
+}
+
+fn parse_tool(raw: &str) -> Result<&'static ManagedTool> {
+    let agent =
+        BaseCodingAgent::from_str(raw).map_err(|_| anyhow::anyhow!("Unknown agent type: {raw}"))?;
+    Ok(tool_cache::managed_tool(agent)?)
+}
+
+fn list(json_output: bool) -> Result<()> {
+    let tools = tool_cache::managed_tools();
+    if json_output {
+        let entries: Vec<serde_json::Value> = tools
+            .iter()
+            .map(|tool| {
+                serde_json::json!({
+                    "agent": tool.agent,
+                    "package": tool.package,
+                    "pinned": tool.pinned_version(),
+                    "installed": tool.installed(),
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+
+    println!(
+        "[SYSTEM] Managed tools in {}:",
+        workspace_utils::assets::tools_dir().display()
+    );
+    for tool in tools {
+        let in_use = match tool.installed() {
+            Some(installed)
+                if installed.version == tool.pinned_version()
+                    || tool.pinned_version() == "latest" =>
+            {
+                format!("{} (cached)", installed.version)
+            }
+            Some(installed) => format!(
+                "{} (cached; pinned {})",
+                installed.version,
+                tool.pinned_version()
+            ),
+            None => format!("npx -y {}@{} per run", tool.package, tool.pinned_version()),
+        };
+        println!("  - {:<12}  {:<26}  {in_use}", tool.agent, tool.package);
+    }
+    Ok(())
+}
+
+async fn install(agents: &[&String]) -> Result<()> {
+    let tools: Vec<&ManagedTool> = if agents.is_empty() {
+        tool_cache::managed_tools().iter().collect()
+    } else {
+        agents
+            .iter()
+            .map(|agent| parse_tool(agent))
+            .collect::<Result<_>>()?
+    };
+    for tool in tools {
+        if let Some(installed) = tool.installed() {
+            println!(
+                "[SYSTEM] {} {} is already installed; use `tools upgrade {}` to change it",
+                tool.package, installed.version, tool.agent
+            );
+            continue;
+        }
+        let version = tool_cache::resolve_version(tool, tool.pinned_version()).await?;
+        println!("[SYSTEM] Installing {}@{version}...", tool.package);
+        tool_cache::install(tool, &version).await?;
+        tool_cache::activate(tool, &version)?;
+    }
+    Ok(())
+}
+
+async fn upgrade(agent: &str, version: &str, force: bool) -> Result<()> {
+    let tool = parse_tool(agent)?;
+    let version = tool_cache::resolve_version(tool, version).await?;
+    if tool
+        .installed()
+        .is_some_and(|installed| installed.version == version)
+    {
+        println!("[SYSTEM] {} is already at {version}", tool.package);
+        return Ok(());
+    }
+    let compatibility = tool_cache::compare(tool, &version).await?;
+    print_compatibility(tool, &compatibility);
+    if !compatibility.is_compatible() && !force {
+        anyhow::bail!(
+            "Not upgrading {} to {version}; pass --force to upgrade anyway",
+            tool.package
+        );
+    }
+    tool_cache::activate(tool, &version)?;
+    println!(
+        "[SYSTEM] {} runs now use {}@{version}",
+        tool.agent, tool.package
+    );
+    Ok(())
+}
+
+async fn compare(agent: &str, version: &str, json_output: bool) -> Result<()> {
+    let tool = parse_tool(agent)?;
+    let version = tool_cache::resolve_version(tool, version).await?;
+    let compatibility = tool_cache::compare(tool, &version).await?;
+    if json_output {
+        println!("{}", serde_json::to_string_pretty(&compatibility)?);
+    } else {
+        print_compatibility(tool, &compatibility);
+    }
+    Ok(())
+}
+
+fn print_compatibility(tool: &ManagedTool, compatibility: &Compatibility) {
+    let step = match compatibility.step {
+        VersionStep::Same => "same version",
+        VersionStep::Patch => "patch release",
+        VersionStep::Minor => "minor release",
+        VersionStep::Major => "breaking release",
+        VersionStep::Downgrade => "downgrade",
+        VersionStep::Unknown => "unrecognised versions",
+    };
+    println!(
+        "[SYSTEM] {} {} -> {} ({step}; normalizers were written against {})",
+        tool.package,
+        compatibility.current,
+        compatibility.candidate,
+        tool.pinned_version()
+    );
+    if compatibility.missing_flags.is_empty() {
+        println!(
+            "  flags: every flag {} passes is still documented",
+            tool.agent
+        );
+    } else {
+        println!(
+            "  flags: no longer documented: {}",
+            compatibility.missing_flags.join(", ")
+        );
+    }
+    if !compatibility.unchecked_flags.is_empty() {
+        println!(
+            "  unchecked (documented by neither version): {}",
+            compatibility.unchecked_flags.join(", ")
+        );
+    }
+    if let Some(note) = &compatibility.note {
+        println!("  note: {note}");
+    }
+    let verdict = if compatibility.is_compatible() {
+        "likely compatible"
+    } else {
+        "likely INCOMPATIBLE"
+    };
+    println!("  verdict: {verdict}");
+}
+
+fn print_tools_usage() {
+    print!(
+        r#"Usage: code-marshal tools <COMMAND>
+
+Agent CLIs distributed through npm (Claude Code, Amp, Gemini, Qwen Code, Copilot, Codex,
+OpenCode) can be installed once into code-marshal's tool cache; runs then use the cached
+install instead of `npx -y`. Versions only change through `install` and `upgrade`.
+
+Commands:
+  list [--json]                       Managed tools, their pinned and cached versions
+  install [AGENT...]                  Install the pinned versions (Amp: the latest release)
+  upgrade <AGENT> [VERSION] [--force] Switch to VERSION (default: latest) after comparing it;
+                                      refuses when a flag code-marshal passes disappeared
+  compare <AGENT> [VERSION] [--json]  Check VERSION (default: latest) against the one in use:
+                                      release step and the flags code-marshal passes
+  remove <AGENT>                      Delete the cached install; runs use npx again
+"#
+    );
+}